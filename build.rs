@@ -0,0 +1,12 @@
+fn main() {
+    // Generates the gRPC service from proto/swarm.proto with a bundled protoc
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/swarm.proto");
+        let protoc = protoc_bin_vendored::protoc_bin_path().expect("bundled protoc");
+        std::env::set_var("PROTOC", protoc);
+        tonic_build::configure()
+            .compile_protos(&["proto/swarm.proto"], &["proto"])
+            .expect("compile proto/swarm.proto");
+    }
+}
@@ -1,39 +1,36 @@
 use async_openai::types::{
-    ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestMessage,
-    ChatCompletionRequestUserMessage, ChatCompletionRequestUserMessageContent,
+    ChatCompletionRequestMessage, ChatCompletionRequestUserMessage,
+    ChatCompletionRequestUserMessageContent,
 };
+use schemars::JsonSchema;
+use serde::Deserialize;
 use serde_json::json;
 use swarm_rs::{
     swarm::Swarm,
     types::{Agent, Tool},
 };
 
+#[derive(Deserialize, JsonSchema)]
+struct WeatherArgs {
+    /// The location to get weather for
+    location: String,
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("Swarm Simulation Started!");
 
     // 1. Setup swarm and tools
-    let mut swarm = Swarm::new(None);
+    let swarm = Swarm::new(None);
 
-    // Register weather tool with mock implementation
-    swarm.register_tool(
+    // Register weather tool with mock implementation; its schema comes from WeatherArgs
+    swarm.register_tool_typed(
         "get_weather",
         "Get the weather for a given location",
-        json!({
-            "type": "object",
-            "properties": {
-                "location": {
-                    "type": "string",
-                    "description": "The location to get weather for"
-                }
-            },
-            "required": ["location"]
-        }),
-        Box::new(|args| {
-            let location = args["location"].as_str().unwrap_or("Unknown");
-            println!("Getting weather for {}", location);
-            json!({"temp": 67, "unit": "F", "location": location})
-        }),
+        |args: WeatherArgs| {
+            println!("Getting weather for {}", args.location);
+            json!({"temp": 67, "unit": "F", "location": args.location})
+        },
     );
 
     // 2. Configure weather agent
@@ -41,24 +38,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         name: "Weather Agent".to_string(),
         model: "gpt-4".to_string(),
         instructions:
-            "You are a helpful weather assistant. Use the weather tool to check conditions."
-                .to_string(),
-        tools: vec![Tool::new(
+            "You are a helpful weather assistant. Use the weather tool to check conditions.".into(),
+        tools: vec![Tool::typed::<WeatherArgs>(
             "get_weather",
             "Get the weather for a given location",
-            json!({
-                "type": "object",
-                "properties": {
-                    "location": {
-                        "type": "string",
-                        "description": "The location to get weather for"
-                    }
-                },
-                "required": ["location"]
-            }),
         )],
-        tool_choice: None,
-        parallel_tool_calls: true,
+        ..Default::default()
     };
 
     // 3. Prepare conversation
@@ -73,33 +58,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 4. Execute and handle response
     let max_turns = 10;
     let response = swarm
-        .run(
-            agent,
-            messages,
-            None,
-            None,
-            false,
-            true, // Enable debug output
-            Some(max_turns),
-            true, // Enable tool execution
-        )
+        .runner(agent, messages)
+        .debug(true) // Enable debug output
+        .max_turns(max_turns)
         .await?;
 
     // 5. Process and display results
-    if let Some(last_message) = response.messages.last() {
-        match last_message {
-            ChatCompletionRequestMessage::Assistant(msg) => {
-                if let Some(content) = &msg.content {
-                    match content {
-                        ChatCompletionRequestAssistantMessageContent::Text(text) => {
-                            println!("Assistant's response: {}", text)
-                        }
-                        _ => println!("No text response received"),
-                    }
-                }
-            }
-            _ => println!("Received non-assistant message"),
-        }
+    for call in response.tool_calls() {
+        println!("Called {}({})", call.function.name, call.function.arguments);
+    }
+    match response.final_text() {
+        Some(text) => println!("Assistant's response: {}", text),
+        None => println!("No text response received"),
     }
 
     Ok(())
@@ -0,0 +1,63 @@
+use async_openai::types::{
+    ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestMessage,
+    ChatCompletionRequestUserMessage, ChatCompletionRequestUserMessageContent,
+};
+use serde::Serialize;
+use swarm_rs::{swarm::Swarm, tool, types::Agent};
+
+#[derive(Serialize)]
+struct Weather {
+    temp: i32,
+    unit: String,
+    location: String,
+}
+
+/// Get the weather for a given location
+#[tool]
+fn get_weather(
+    #[description = "The location to get weather for"] location: String,
+    #[description = "Temperature unit, F or C"] unit: Option<String>,
+) -> Weather {
+    println!("Getting weather for {}", location);
+    Weather {
+        temp: 67,
+        unit: unit.unwrap_or_else(|| "F".to_string()),
+        location,
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // 1. Setup swarm with the generated tool definition
+    let swarm = Swarm::new(None);
+    swarm.register(get_weather_tool());
+
+    // 2. Configure weather agent from the same definition
+    let agent = Agent {
+        name: "Weather Agent".to_string(),
+        instructions:
+            "You are a helpful weather assistant. Use the weather tool to check conditions.".into(),
+        tools: vec![get_weather_tool().tool()],
+        ..Default::default()
+    };
+
+    // 3. Run the conversation
+    let messages = vec![ChatCompletionRequestMessage::User(
+        ChatCompletionRequestUserMessage {
+            content: ChatCompletionRequestUserMessageContent::Text(
+                "What's the weather like in Boston?".into(),
+            ),
+            name: None,
+        },
+    )];
+    let response = swarm.runner(agent, messages).max_turns(10).await?;
+
+    // 4. Display the final answer
+    if let Some(ChatCompletionRequestMessage::Assistant(msg)) = response.messages.last() {
+        if let Some(ChatCompletionRequestAssistantMessageContent::Text(text)) = &msg.content {
+            println!("Assistant's response: {}", text);
+        }
+    }
+
+    Ok(())
+}
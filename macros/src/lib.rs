@@ -0,0 +1,277 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, Attribute, Error, Expr, ExprLit, FnArg, ItemFn, Lit, LitStr, Meta, Pat,
+    ReturnType, Type,
+};
+
+// Name of the parameter swarm-rs fills with the run's context variables, if declared
+const CONTEXT_VARIABLES: &str = "context_variables";
+
+// Turns a plain function into a tool definition.
+//
+// Given `fn get_weather(location: String) -> Weather`, generates a sibling
+// `fn get_weather_tool() -> swarm_rs::types::ToolDefinition` carrying the
+// JSON schema of the parameters, derived with schemars, and a registry-ready
+// function that deserializes the arguments and serializes the return value
+// into a `ToolOutput`.
+//
+// The description comes from the function's doc comment unless given as
+// `#[tool(description = "...")]`; `#[tool(name = "...")]` overrides the
+// tool name and `#[tool(strict)]` opts into strict function calling.
+// Parameters may carry `#[description = "..."]` and their types must
+// implement `JsonSchema`. Functions returning `ToolOutput` can hand off to
+// another agent or update context.
+#[proc_macro_attribute]
+pub fn tool(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut function = parse_macro_input!(item as ItemFn);
+    let mut options = ToolOptions::default();
+    let parser = syn::meta::parser(|meta| {
+        if meta.path.is_ident("name") {
+            options.name = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("description") {
+            options.description = Some(meta.value()?.parse()?);
+            Ok(())
+        } else if meta.path.is_ident("strict") {
+            options.strict = true;
+            Ok(())
+        } else {
+            Err(meta.error("expected `name`, `description` or `strict`"))
+        }
+    });
+    parse_macro_input!(attr with parser);
+
+    expand(&mut function, options)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+#[derive(Default)]
+struct ToolOptions {
+    name: Option<LitStr>,
+    description: Option<LitStr>,
+    strict: bool,
+}
+
+struct Param {
+    ident: syn::Ident,
+    ty: Type,
+}
+
+fn expand(function: &mut ItemFn, options: ToolOptions) -> syn::Result<TokenStream2> {
+    let fn_ident = function.sig.ident.clone();
+    let vis = function.vis.clone();
+    let is_async = function.sig.asyncness.is_some();
+    let tool_name = options
+        .name
+        .map(|name| name.value())
+        .unwrap_or_else(|| fn_ident.to_string());
+    let description = options
+        .description
+        .map(|description| description.value())
+        .unwrap_or_else(|| doc_string(&function.attrs));
+
+    if !function.sig.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &function.sig.generics,
+            "#[tool] functions cannot be generic",
+        ));
+    }
+
+    // 1. Collect parameters into the fields of an args struct deriving the schema,
+    // stripping helper attributes
+    let mut params = Vec::new();
+    let mut fields = Vec::new();
+    let mut context_variables = false;
+    for input in function.sig.inputs.iter_mut() {
+        let FnArg::Typed(pat_type) = input else {
+            return Err(Error::new_spanned(
+                input,
+                "#[tool] functions cannot take `self`",
+            ));
+        };
+        let Pat::Ident(pat_ident) = pat_type.pat.as_ref() else {
+            return Err(Error::new_spanned(
+                &pat_type.pat,
+                "#[tool] parameters must be plain identifiers",
+            ));
+        };
+        if matches!(pat_type.ty.as_ref(), Type::Reference(_)) {
+            return Err(Error::new_spanned(
+                &pat_type.ty,
+                "#[tool] parameters must be owned types",
+            ));
+        }
+
+        let ident = pat_ident.ident.clone();
+        let name = ident.to_string();
+        let param_description = take_description(&mut pat_type.attrs)?;
+        params.push(Param {
+            ident: ident.clone(),
+            ty: (*pat_type.ty).clone(),
+        });
+        if name == CONTEXT_VARIABLES {
+            // Declared so swarm-rs passes the context in; hidden from the model
+            context_variables = true;
+            continue;
+        }
+
+        let ty = &pat_type.ty;
+        let doc = param_description.map(|description| quote! { #[doc = #description] });
+        fields.push(quote! {
+            #doc
+            #ident: #ty
+        });
+    }
+    let declare_context_variables = context_variables.then(|| {
+        quote! {
+            parameters["properties"][#CONTEXT_VARIABLES] =
+                ::swarm_rs::__private::serde_json::json!({"type": "object"});
+        }
+    });
+
+    // 2. Deserialize each argument from the call's JSON object
+    let bindings = params.iter().map(|Param { ident, ty }| {
+        let key = ident.to_string();
+        quote! {
+            let #ident: #ty = match ::swarm_rs::__private::serde_json::from_value(
+                args.get(#key)
+                    .cloned()
+                    .unwrap_or(::swarm_rs::__private::serde_json::Value::Null),
+            ) {
+                Ok(value) => value,
+                Err(e) => {
+                    return ::swarm_rs::types::ToolOutput::Error(format!(
+                        "invalid argument `{}`: {}",
+                        #key, e
+                    ))
+                }
+            };
+        }
+    });
+    let arg_idents = params.iter().map(|param| &param.ident);
+
+    // 3. Wrap the call in a sync or async tool function
+    let call = if is_async {
+        quote! { #fn_ident(#(#arg_idents),*).await }
+    } else {
+        quote! { #fn_ident(#(#arg_idents),*) }
+    };
+    let output = if returns_tool_output(&function.sig.output) {
+        call
+    } else {
+        quote! { ::swarm_rs::types::ToolOutput::from_serializable(#call) }
+    };
+    let body = quote! {
+        #(#bindings)*
+        #output
+    };
+    let tool_function = if is_async {
+        quote! {
+            ::swarm_rs::types::ToolFunction::Async(::std::sync::Arc::new(
+                |args: ::swarm_rs::__private::serde_json::Value| {
+                    ::std::boxed::Box::pin(async move { #body })
+                },
+            ))
+        }
+    } else {
+        quote! {
+            ::swarm_rs::types::ToolFunction::Sync(::std::sync::Arc::new(
+                |args: ::swarm_rs::__private::serde_json::Value| { #body },
+            ))
+        }
+    };
+
+    let strict = options.strict.then(|| quote! { .with_strict() });
+    let definition_ident = format_ident!("{}_tool", fn_ident);
+    let doc = format!("Tool definition generated from [`{}`].", fn_ident);
+    Ok(quote! {
+        #function
+
+        #[doc = #doc]
+        #vis fn #definition_ident() -> ::swarm_rs::types::ToolDefinition {
+            #[derive(::swarm_rs::__private::schemars::JsonSchema)]
+            #[schemars(crate = "::swarm_rs::__private::schemars")]
+            #[allow(dead_code)]
+            struct Args {
+                #(#fields),*
+            }
+
+            #[allow(unused_mut)]
+            let mut parameters = ::swarm_rs::schema::schema_for::<Args>();
+            #declare_context_variables
+            ::swarm_rs::types::ToolDefinition::new(
+                ::swarm_rs::types::Tool::new(#tool_name, #description, parameters)#strict,
+                #tool_function,
+            )
+        }
+    })
+}
+
+// Joins the lines of a doc comment into a single description
+fn doc_string(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(doc), ..
+                }) => Some(doc.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Removes `#[description = "..."]` from a parameter and returns its text
+fn take_description(attrs: &mut Vec<Attribute>) -> syn::Result<Option<String>> {
+    let mut description = None;
+    let mut error = None;
+    attrs.retain(|attr| {
+        if !attr.path().is_ident("description") {
+            return true;
+        }
+        match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(text),
+                    ..
+                }) => description = Some(text.value()),
+                other => {
+                    error = Some(Error::new_spanned(other, "expected a string literal"));
+                }
+            },
+            other => {
+                error = Some(Error::new_spanned(
+                    other,
+                    "expected `#[description = \"...\"]`",
+                ));
+            }
+        }
+        false
+    });
+    match error {
+        Some(error) => Err(error),
+        None => Ok(description),
+    }
+}
+
+// Whether the function already returns a `ToolOutput`
+fn returns_tool_output(output: &ReturnType) -> bool {
+    let ReturnType::Type(_, ty) = output else {
+        return false;
+    };
+    let Type::Path(path) = ty.as_ref() else {
+        return false;
+    };
+    path.path
+        .segments
+        .last()
+        .is_some_and(|segment| segment.ident == "ToolOutput")
+}
@@ -0,0 +1,29 @@
+use async_openai::types::ChatCompletionMessageToolCall;
+use async_trait::async_trait;
+use serde_json::Value;
+
+// Decision on a tool call awaiting approval
+#[derive(Debug, Clone)]
+pub enum Approval {
+    Approve,
+    // Skip the call; the reason is fed back to the model as the tool message
+    Deny(String),
+    // Run the call with these arguments instead of the model's
+    Edit(Value),
+}
+
+// Reviews tool calls before they execute, e.g. by asking a human
+#[async_trait]
+pub trait ApprovalHandler: Send + Sync {
+    async fn review(&self, tool_call: &ChatCompletionMessageToolCall) -> Approval;
+}
+
+#[async_trait]
+impl<F> ApprovalHandler for F
+where
+    F: Fn(&ChatCompletionMessageToolCall) -> Approval + Send + Sync,
+{
+    async fn review(&self, tool_call: &ChatCompletionMessageToolCall) -> Approval {
+        self(tool_call)
+    }
+}
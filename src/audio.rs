@@ -0,0 +1,270 @@
+use async_openai::{
+    config::OpenAIConfig,
+    types::{
+        AudioInput, ChatCompletionRequestMessage, CreateSpeechRequest, CreateTranscriptionRequest,
+        SpeechModel, SpeechResponseFormat, Voice,
+    },
+    Client,
+};
+use futures::FutureExt;
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::error::Result;
+use crate::messages::{message_text, user_message};
+use crate::types::{Response, Tool, ToolDefinition, ToolFunction, ToolOutput};
+
+// Turns speech into text with the OpenAI transcription endpoint, so voice input can
+// be handed to `run()` as ordinary user messages
+#[derive(Clone)]
+pub struct Transcriber {
+    client: Client<OpenAIConfig>,
+    model: String,
+    language: Option<String>,
+    prompt: Option<String>,
+}
+
+impl Transcriber {
+    pub fn new(client: Client<OpenAIConfig>) -> Self {
+        Transcriber {
+            client,
+            model: "whisper-1".to_string(),
+            language: None,
+            prompt: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = model.to_string();
+        self
+    }
+
+    // ISO-639-1 code of the spoken language, e.g. "de"; improves accuracy and latency
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.language = Some(language.to_string());
+        self
+    }
+
+    // Text that guides spelling and style, e.g. product names the speaker uses
+    pub fn with_prompt(mut self, prompt: &str) -> Self {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
+
+    // Transcribes audio bytes; the file name's extension tells the format, e.g. "voice.m4a"
+    pub async fn transcribe(&self, file_name: &str, bytes: Vec<u8>) -> Result<String> {
+        let request = CreateTranscriptionRequest {
+            file: AudioInput::from_vec_u8(file_name.to_string(), bytes),
+            model: self.model.clone(),
+            prompt: self.prompt.clone(),
+            language: self.language.clone(),
+            ..Default::default()
+        };
+        let response = self.client.audio().transcribe(request).await?;
+        Ok(response.text)
+    }
+
+    // Transcribes an mp3, mp4, mpeg, mpga, m4a, wav or webm file
+    pub async fn transcribe_file(&self, path: impl AsRef<Path>) -> Result<String> {
+        let path = path.as_ref();
+        let bytes = tokio::fs::read(path).await?;
+        let file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "audio".to_string());
+        self.transcribe(&file_name, bytes).await
+    }
+
+    // Transcribes audio bytes into a user message
+    pub async fn user_message(
+        &self,
+        file_name: &str,
+        bytes: Vec<u8>,
+    ) -> Result<ChatCompletionRequestMessage> {
+        Ok(user_message(&self.transcribe(file_name, bytes).await?))
+    }
+
+    // Transcribes an audio file into a user message
+    pub async fn user_message_from_file(
+        &self,
+        path: impl AsRef<Path>,
+    ) -> Result<ChatCompletionRequestMessage> {
+        Ok(user_message(&self.transcribe_file(path).await?))
+    }
+
+    // `transcribe_audio` tool for agents that come across recordings, e.g. attachments.
+    // It takes a path relative to `root` and refuses files outside of it.
+    pub fn tool(self, root: impl Into<PathBuf>) -> ToolDefinition {
+        let root = root.into();
+        let transcriber = Arc::new(self);
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path of the audio file, relative to the audio directory",
+                },
+            },
+            "required": ["path"],
+        });
+        let function = move |args: Value| {
+            let transcriber = transcriber.clone();
+            let root = root.clone();
+            async move {
+                let Some(path) = args.get("path").and_then(Value::as_str) else {
+                    return ToolOutput::Error("missing path".to_string());
+                };
+                let path = match resolve_within(&root, path) {
+                    Ok(path) => path,
+                    Err(e) => return ToolOutput::Error(e),
+                };
+                match transcriber.transcribe_file(path).await {
+                    Ok(text) => ToolOutput::Text(text),
+                    Err(e) => ToolOutput::Error(e.to_string()),
+                }
+            }
+            .boxed()
+        };
+        ToolDefinition::new(
+            Tool::new(
+                "transcribe_audio",
+                "Transcribes an audio recording into text.",
+                parameters,
+            ),
+            ToolFunction::Async(Arc::new(function)),
+        )
+    }
+}
+
+impl std::fmt::Debug for Transcriber {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Transcriber")
+            .field("model", &self.model)
+            .field("language", &self.language)
+            .field("prompt", &self.prompt)
+            .finish()
+    }
+}
+
+// Joins a model-supplied path onto `root`, rejecting anything that escapes it
+fn resolve_within(root: &Path, path: &str) -> std::result::Result<PathBuf, String> {
+    let root = root.canonicalize().map_err(|e| e.to_string())?;
+    let resolved = root
+        .join(path)
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", path, e))?;
+    if !resolved.starts_with(&root) {
+        return Err(format!("{} is outside the audio directory", path));
+    }
+    Ok(resolved)
+}
+
+// Longest input the speech endpoint accepts, in characters
+const MAX_SPEECH_INPUT: usize = 4096;
+
+// Audio of a reply, in the synthesizer's format
+#[derive(Debug, Clone)]
+pub struct Speech {
+    pub format: SpeechResponseFormat,
+    pub audio: Vec<u8>,
+}
+
+// Reads replies aloud with the OpenAI speech endpoint, for voice assistants
+#[derive(Clone)]
+pub struct Synthesizer {
+    client: Client<OpenAIConfig>,
+    model: SpeechModel,
+    voice: Voice,
+    format: SpeechResponseFormat,
+    speed: Option<f32>,
+}
+
+impl Synthesizer {
+    // Speaks with `tts-1` in the alloy voice, as mp3
+    pub fn new(client: Client<OpenAIConfig>) -> Self {
+        Synthesizer {
+            client,
+            model: SpeechModel::Tts1,
+            voice: Voice::Alloy,
+            format: SpeechResponseFormat::Mp3,
+            speed: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: SpeechModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_voice(mut self, voice: Voice) -> Self {
+        self.voice = voice;
+        self
+    }
+
+    pub fn with_format(mut self, format: SpeechResponseFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    // From 0.25 to 4.0, 1.0 being normal speed
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = Some(speed.clamp(0.25, 4.0));
+        self
+    }
+
+    // Synthesizes up to 4096 characters of text; longer text is cut at the last word
+    // that fits
+    pub async fn synthesize(&self, text: &str) -> Result<Speech> {
+        let request = CreateSpeechRequest {
+            input: truncate_input(text).to_string(),
+            model: self.model.clone(),
+            voice: self.voice.clone(),
+            response_format: Some(self.format),
+            speed: self.speed,
+        };
+        let response = self.client.audio().speech(request).await?;
+        Ok(Speech {
+            format: self.format,
+            audio: response.bytes.to_vec(),
+        })
+    }
+
+    // Reads the final assistant message aloud, if the run ended with one
+    pub(crate) async fn speak(&self, response: &mut Response) -> Result<()> {
+        let text = match response.messages.last() {
+            Some(message @ ChatCompletionRequestMessage::Assistant(assistant))
+                if assistant.tool_calls.as_ref().is_none_or(Vec::is_empty) =>
+            {
+                message_text(message)
+            }
+            _ => return Ok(()),
+        };
+        if !text.trim().is_empty() {
+            response.speech = Some(self.synthesize(&text).await?);
+        }
+        Ok(())
+    }
+}
+
+fn truncate_input(text: &str) -> &str {
+    let Some((end, _)) = text.char_indices().nth(MAX_SPEECH_INPUT) else {
+        return text;
+    };
+    let text = &text[..end];
+    match text.rfind(char::is_whitespace) {
+        Some(space) if space > 0 => text[..space].trim_end(),
+        _ => text,
+    }
+}
+
+impl std::fmt::Debug for Synthesizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Synthesizer")
+            .field("model", &self.model)
+            .field("voice", &self.voice)
+            .field("format", &self.format)
+            .field("speed", &self.speed)
+            .finish()
+    }
+}
@@ -0,0 +1,288 @@
+use futures::{Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+
+use crate::types::{Agent, Tool, ToolDefinition, ToolFunction, ToolOutput};
+
+// Changes a subscriber may fall behind by before it starts missing them
+const CHANGE_BUFFER: usize = 256;
+
+// A value on the blackboard. Versions come from one counter per blackboard, so a key's
+// version grows with every write and is never reused, even after the key is removed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlackboardEntry {
+    pub value: Value,
+    pub version: u64,
+    // Agent or component that wrote it, if known
+    pub author: Option<String>,
+}
+
+// A write to the blackboard; `value` is None when the key was removed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlackboardChange {
+    pub key: String,
+    pub value: Option<Value>,
+    pub version: u64,
+    pub author: Option<String>,
+}
+
+// Key/value store shared by the agents of an orchestration, outliving any single run.
+// Unlike context variables, which each run carries along, every clone sees the same
+// entries at once and writes are announced to subscribers
+#[derive(Clone)]
+pub struct Blackboard {
+    entries: Arc<RwLock<BTreeMap<String, BlackboardEntry>>>,
+    // Last version handed out; only advanced while holding the `entries` lock
+    version: Arc<AtomicU64>,
+    changes: broadcast::Sender<BlackboardChange>,
+}
+
+impl Default for Blackboard {
+    fn default() -> Self {
+        Blackboard {
+            entries: Arc::new(RwLock::new(BTreeMap::new())),
+            version: Arc::new(AtomicU64::new(0)),
+            changes: broadcast::channel(CHANGE_BUFFER).0,
+        }
+    }
+}
+
+impl Blackboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, key: &str) -> Option<Value> {
+        self.entry(key).map(|entry| entry.value)
+    }
+
+    pub fn entry(&self, key: &str) -> Option<BlackboardEntry> {
+        self.entries.read().unwrap().get(key).cloned()
+    }
+
+    // Reads a value into a typed structure; None if absent or of another shape
+    pub fn get_as<T: serde::de::DeserializeOwned>(&self, key: &str) -> Option<T> {
+        serde_json::from_value(self.get(key)?).ok()
+    }
+
+    pub fn keys(&self) -> Vec<String> {
+        self.entries.read().unwrap().keys().cloned().collect()
+    }
+
+    // All entries, ordered by key
+    pub fn snapshot(&self) -> BTreeMap<String, BlackboardEntry> {
+        self.entries.read().unwrap().clone()
+    }
+
+    // Writes a value and returns its new version
+    pub fn set(&self, key: &str, value: impl Into<Value>) -> u64 {
+        self.write(key, value.into(), None, None)
+            .unwrap_or_default()
+    }
+
+    // Writes a value on behalf of an agent or component
+    pub fn set_by(&self, author: &str, key: &str, value: impl Into<Value>) -> u64 {
+        self.write(key, value.into(), Some(author), None)
+            .unwrap_or_default()
+    }
+
+    // Writes only if the key is still at `expected` (0 for absent), so concurrent
+    // writers do not overwrite each other; Err carries the current version
+    pub fn compare_and_set(
+        &self,
+        key: &str,
+        expected: u64,
+        value: impl Into<Value>,
+        author: Option<&str>,
+    ) -> std::result::Result<u64, u64> {
+        self.write(key, value.into(), author, Some(expected))
+    }
+
+    // Atomically replaces a value with `f` of the current one (None if absent)
+    pub fn update(&self, key: &str, f: impl FnOnce(Option<&Value>) -> Value) -> u64 {
+        let mut entries = self.entries.write().unwrap();
+        let current = entries.get(key);
+        let value = f(current.map(|entry| &entry.value));
+        self.store(&mut entries, key, value, None)
+    }
+
+    // Removes a key, returning its last value
+    pub fn remove(&self, key: &str) -> Option<Value> {
+        let mut entries = self.entries.write().unwrap();
+        let removed = entries.remove(key)?;
+        let _ = self.changes.send(BlackboardChange {
+            key: key.to_string(),
+            value: None,
+            version: self.next_version(),
+            author: None,
+        });
+        Some(removed.value)
+    }
+
+    // Receives every change made after subscribing
+    pub fn subscribe(&self) -> broadcast::Receiver<BlackboardChange> {
+        self.changes.subscribe()
+    }
+
+    // Stream of changes to one key; changes missed by a slow consumer are skipped
+    pub fn watch(&self, key: &str) -> impl Stream<Item = BlackboardChange> + Send + 'static {
+        let key = key.to_string();
+        futures::stream::unfold(self.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(change) => return Some((change, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+        .filter(move |change| std::future::ready(change.key == key))
+    }
+
+    fn write(
+        &self,
+        key: &str,
+        value: Value,
+        author: Option<&str>,
+        expected: Option<u64>,
+    ) -> std::result::Result<u64, u64> {
+        let mut entries = self.entries.write().unwrap();
+        let current = entries.get(key).map_or(0, |entry| entry.version);
+        if expected.is_some_and(|expected| expected != current) {
+            return Err(current);
+        }
+        Ok(self.store(&mut entries, key, value, author))
+    }
+
+    fn next_version(&self) -> u64 {
+        self.version.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    // Stores and announces a write while the caller holds the lock, so subscribers
+    // see changes in version order
+    fn store(
+        &self,
+        entries: &mut BTreeMap<String, BlackboardEntry>,
+        key: &str,
+        value: Value,
+        author: Option<&str>,
+    ) -> u64 {
+        let version = self.next_version();
+        let author = author.map(str::to_string);
+        entries.insert(
+            key.to_string(),
+            BlackboardEntry {
+                value: value.clone(),
+                version,
+                author: author.clone(),
+            },
+        );
+        // Nobody listening is fine
+        let _ = self.changes.send(BlackboardChange {
+            key: key.to_string(),
+            value: Some(value),
+            version,
+            author,
+        });
+        version
+    }
+
+    // `blackboard_read` and `blackboard_write` tools, writing on behalf of `author`
+    pub fn tools(&self, author: &str) -> Vec<ToolDefinition> {
+        vec![self.read_tool(), self.write_tool(author)]
+    }
+
+    // Gives the agent the blackboard tools, writing under its name
+    pub fn equip(&self, agent: Agent) -> Agent {
+        let tools = self.tools(&agent.name);
+        tools.into_iter().fold(agent, Agent::with_tool)
+    }
+
+    fn read_tool(&self) -> ToolDefinition {
+        let blackboard = self.clone();
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "string",
+                    "description": "Entry to read; omit to read every entry",
+                },
+            },
+        });
+        let function = move |args: Value| match args.get("key").and_then(Value::as_str) {
+            Some(key) => match blackboard.entry(key) {
+                Some(entry) => ToolOutput::Json(json!({ "key": key, "entry": entry })),
+                None => ToolOutput::Json(json!({ "key": key, "entry": null })),
+            },
+            None => ToolOutput::Json(json!({ "entries": blackboard.snapshot() })),
+        };
+        ToolDefinition::new(
+            Tool::new(
+                "blackboard_read",
+                "Reads notes shared between agents on the blackboard.",
+                parameters,
+            ),
+            ToolFunction::Sync(Arc::new(function)),
+        )
+    }
+
+    fn write_tool(&self, author: &str) -> ToolDefinition {
+        let blackboard = self.clone();
+        let author = author.to_string();
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "key": {
+                    "type": "string",
+                    "description": "Entry to write",
+                },
+                "value": {
+                    "description": "Any JSON value",
+                },
+                "expected_version": {
+                    "type": "integer",
+                    "description": "Only write if the entry is still at this version (0 if it \
+                                    does not exist yet), as returned by blackboard_read",
+                },
+            },
+            "required": ["key", "value"],
+        });
+        let function = move |args: Value| {
+            let Some(key) = args.get("key").and_then(Value::as_str) else {
+                return ToolOutput::Error("missing key".to_string());
+            };
+            let value = args.get("value").cloned().unwrap_or(Value::Null);
+            let written = match args.get("expected_version").and_then(Value::as_u64) {
+                Some(expected) => blackboard.compare_and_set(key, expected, value, Some(&author)),
+                None => Ok(blackboard.set_by(&author, key, value)),
+            };
+            match written {
+                Ok(version) => ToolOutput::Json(json!({ "key": key, "version": version })),
+                Err(current) => ToolOutput::Error(format!(
+                    "{} changed since it was read; it is now at version {}",
+                    key, current
+                )),
+            }
+        };
+        ToolDefinition::new(
+            Tool::new(
+                "blackboard_write",
+                "Writes a note to the blackboard shared between agents.",
+                parameters,
+            ),
+            ToolFunction::Sync(Arc::new(function)),
+        )
+    }
+}
+
+impl std::fmt::Debug for Blackboard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Blackboard")
+            .field("keys", &self.keys())
+            .finish()
+    }
+}
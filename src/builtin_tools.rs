@@ -0,0 +1,1014 @@
+use async_openai::{
+    config::OpenAIConfig,
+    types::{
+        CreateImageRequest, Image, ImageModel, ImageQuality, ImageResponseFormat, ImageSize,
+        ImageStyle,
+    },
+    Client,
+};
+use futures::FutureExt;
+use serde_json::{json, Value};
+use std::path::{Component, Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::retrieval::{KnowledgeBase, TEXT_KEY};
+use crate::search::SearchProvider;
+use crate::types::{Agent, ContextVariables, Tool, ToolDefinition, ToolFunction, ToolOutput};
+
+// Most results a `retrieve` call may ask for, keeping tool output within the context window
+const MAX_RETRIEVE_TOP_K: u64 = 20;
+
+// Results a `web_search` call returns unless it asks for another number
+const DEFAULT_SEARCH_RESULTS: u64 = 5;
+
+// Most results a `web_search` call may ask for
+const MAX_SEARCH_RESULTS: u64 = 10;
+
+// Most entries a `list_dir` call returns
+const MAX_LIST_ENTRIES: usize = 1000;
+
+// Variables a `run_command` process inherits unless configured otherwise
+const INHERITED_ENV: [&str; 1] = ["PATH"];
+
+// Context variable receiving base64 images, which are kept out of the conversation
+pub const GENERATED_IMAGES: &str = "generated_images";
+
+// Settings of the `image_generation` tool
+#[derive(Clone)]
+pub struct ImageGeneration {
+    client: Client<OpenAIConfig>,
+    model: ImageModel,
+    size: ImageSize,
+    quality: Option<ImageQuality>,
+    style: Option<ImageStyle>,
+    format: ImageResponseFormat,
+}
+
+impl ImageGeneration {
+    // Generates 1024x1024 images with dall-e-3 and returns their URLs
+    pub fn new(client: Client<OpenAIConfig>) -> Self {
+        ImageGeneration {
+            client,
+            model: ImageModel::DallE3,
+            size: ImageSize::S1024x1024,
+            quality: None,
+            style: None,
+            format: ImageResponseFormat::Url,
+        }
+    }
+
+    pub fn with_model(mut self, model: ImageModel) -> Self {
+        self.model = model;
+        self
+    }
+
+    pub fn with_size(mut self, size: ImageSize) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn with_quality(mut self, quality: ImageQuality) -> Self {
+        self.quality = Some(quality);
+        self
+    }
+
+    pub fn with_style(mut self, style: ImageStyle) -> Self {
+        self.style = Some(style);
+        self
+    }
+
+    // `B64Json` returns image data instead of URLs, which expire after an hour
+    pub fn with_format(mut self, format: ImageResponseFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    // URLs come back to the model as `{"images": [{"url", "revised_prompt"}]}`; base64
+    // images go to the `generated_images` context variable, replacing earlier ones
+    pub fn tool(self) -> ToolDefinition {
+        let settings = Arc::new(self);
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "prompt": {
+                    "type": "string",
+                    "description": "Detailed description of the image to create",
+                },
+            },
+            "required": ["prompt"],
+        });
+        let function = move |args: Value| {
+            let settings = settings.clone();
+            async move {
+                let Some(prompt) = args.get("prompt").and_then(Value::as_str) else {
+                    return ToolOutput::Error("missing prompt".to_string());
+                };
+                settings.generate(prompt).await
+            }
+            .boxed()
+        };
+        ToolDefinition::new(
+            Tool::new(
+                "image_generation",
+                "Creates an image from a text description.",
+                parameters,
+            ),
+            ToolFunction::Async(Arc::new(function)),
+        )
+    }
+
+    async fn generate(&self, prompt: &str) -> ToolOutput {
+        let request = CreateImageRequest {
+            prompt: prompt.to_string(),
+            model: Some(self.model.clone()),
+            n: Some(1),
+            quality: self.quality.clone(),
+            response_format: Some(self.format),
+            size: Some(self.size),
+            style: self.style.clone(),
+            user: None,
+        };
+        let response = match self.client.images().create(request).await {
+            Ok(response) => response,
+            Err(e) => return ToolOutput::Error(format!("image generation failed: {}", e)),
+        };
+        let images: Vec<Value> = response
+            .data
+            .iter()
+            .map(|image| match image.as_ref() {
+                Image::Url {
+                    url,
+                    revised_prompt,
+                } => json!({ "url": url, "revised_prompt": revised_prompt }),
+                Image::B64Json {
+                    b64_json,
+                    revised_prompt,
+                } => json!({ "b64_json": b64_json.as_str(), "revised_prompt": revised_prompt }),
+            })
+            .collect();
+        match self.format {
+            ImageResponseFormat::Url => ToolOutput::Json(json!({ "images": images })),
+            ImageResponseFormat::B64Json => {
+                let mut update = ContextVariables::new();
+                update.insert(GENERATED_IMAGES.to_string(), Value::Array(images));
+                ToolOutput::ContextUpdate(update)
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for ImageGeneration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImageGeneration")
+            .field("model", &self.model)
+            .field("size", &self.size)
+            .field("quality", &self.quality)
+            .field("style", &self.style)
+            .field("format", &self.format)
+            .finish()
+    }
+}
+
+// `image_generation` tool with the default settings, see `ImageGeneration`
+pub fn image_generation(client: Client<OpenAIConfig>) -> ToolDefinition {
+    ImageGeneration::new(client).tool()
+}
+
+// `retrieve` tool searching the knowledge base. The model passes a `query` and optionally
+// `top_k`, and gets back `{"results": [{"id", "score", "text", ...metadata}]}`
+pub fn retrieve(knowledge_base: KnowledgeBase) -> ToolDefinition {
+    let knowledge_base = Arc::new(knowledge_base);
+    let parameters = json!({
+        "type": "object",
+        "properties": {
+            "query": {
+                "type": "string",
+                "description": "What to look up, phrased as a question or keywords",
+            },
+            "top_k": {
+                "type": "integer",
+                "description": format!(
+                    "Number of passages to return, {} by default",
+                    knowledge_base.top_k()
+                ),
+                "minimum": 1,
+                "maximum": MAX_RETRIEVE_TOP_K,
+            },
+        },
+        "required": ["query"],
+    });
+    let function = move |args: Value| {
+        let knowledge_base = knowledge_base.clone();
+        async move {
+            let Some(query) = args.get("query").and_then(Value::as_str) else {
+                return ToolOutput::Error("missing query".to_string());
+            };
+            let top_k = args
+                .get("top_k")
+                .and_then(Value::as_u64)
+                .map(|top_k| top_k.clamp(1, MAX_RETRIEVE_TOP_K) as usize);
+            let matches = match knowledge_base.search(query, top_k).await {
+                Ok(matches) => matches,
+                Err(e) => return ToolOutput::Error(format!("retrieval failed: {}", e)),
+            };
+            let results: Vec<Value> = matches
+                .into_iter()
+                .map(|record| {
+                    let mut result = record.payload;
+                    let text = result.remove(TEXT_KEY).unwrap_or(Value::Null);
+                    result.insert("id".to_string(), Value::String(record.id));
+                    result.insert("score".to_string(), json!(record.score));
+                    result.insert(TEXT_KEY.to_string(), text);
+                    Value::Object(result)
+                })
+                .collect();
+            ToolOutput::Json(json!({ "results": results }))
+        }
+        .boxed()
+    };
+    ToolDefinition::new(
+        Tool::new(
+            "retrieve",
+            "Searches the knowledge base for passages relevant to a query.",
+            parameters,
+        ),
+        ToolFunction::Async(Arc::new(function)),
+    )
+}
+
+// `web_search` tool over any search provider. The model passes a `query` and optionally
+// `num_results`, and gets back `{"results": [{"rank", "title", "url", "snippet", "published"}]}`
+pub fn web_search(provider: impl SearchProvider + 'static) -> ToolDefinition {
+    let provider = Arc::new(provider);
+    let parameters = json!({
+        "type": "object",
+        "properties": {
+            "query": {
+                "type": "string",
+                "description": "Search terms, as typed into a search engine",
+            },
+            "num_results": {
+                "type": "integer",
+                "description": format!(
+                    "Number of results to return, {} by default",
+                    DEFAULT_SEARCH_RESULTS
+                ),
+                "minimum": 1,
+                "maximum": MAX_SEARCH_RESULTS,
+            },
+        },
+        "required": ["query"],
+    });
+    let function = move |args: Value| {
+        let provider = provider.clone();
+        async move {
+            let Some(query) = args.get("query").and_then(Value::as_str) else {
+                return ToolOutput::Error("missing query".to_string());
+            };
+            let limit = args
+                .get("num_results")
+                .and_then(Value::as_u64)
+                .unwrap_or(DEFAULT_SEARCH_RESULTS)
+                .clamp(1, MAX_SEARCH_RESULTS) as usize;
+            let results = match provider.search(query, limit).await {
+                Ok(results) => results,
+                Err(e) => return ToolOutput::Error(format!("web search failed: {}", e)),
+            };
+            let results: Vec<Value> = results
+                .into_iter()
+                .enumerate()
+                .map(|(index, result)| {
+                    let mut result = json!(result);
+                    result["rank"] = json!(index + 1);
+                    result
+                })
+                .collect();
+            ToolOutput::Json(json!({ "results": results }))
+        }
+        .boxed()
+    };
+    ToolDefinition::new(
+        Tool::new(
+            "web_search",
+            "Searches the web and returns the top results with short snippets.",
+            parameters,
+        ),
+        ToolFunction::Async(Arc::new(function)),
+    )
+}
+
+// Settings of the `run_command` tool. Commands are split into arguments and started
+// directly, without a shell, so pipes, redirection and `;` are passed through literally.
+// Only the program that is started gets checked, so the deny list is best effort: programs
+// that run another program from their arguments, such as `sh -c`, `bash -c`, `env`,
+// `xargs`, `find -exec`, `nice`, `timeout`, `sudo` and `busybox`, get past it. Use an
+// allowlist when the model must not reach arbitrary programs
+#[derive(Debug, Clone)]
+pub struct ShellCommand {
+    working_dir: PathBuf,
+    inherited_env: Vec<String>,
+    env: Vec<(String, String)>,
+    timeout: Duration,
+    max_output: usize,
+    allowed: Option<Vec<String>>,
+    denied: Vec<String>,
+}
+
+impl ShellCommand {
+    // Runs commands in `working_dir` with only PATH inherited, a 30 second timeout and
+    // 16 KiB of output per stream
+    pub fn new(working_dir: impl Into<PathBuf>) -> Self {
+        ShellCommand {
+            working_dir: working_dir.into(),
+            inherited_env: INHERITED_ENV.iter().map(|name| name.to_string()).collect(),
+            env: Vec::new(),
+            timeout: Duration::from_secs(30),
+            max_output: 16 * 1024,
+            allowed: None,
+            denied: Vec::new(),
+        }
+    }
+
+    // Variables passed through from this process, replacing the default of PATH alone
+    pub fn with_inherited_env(mut self, names: &[&str]) -> Self {
+        self.inherited_env = names.iter().map(|name| name.to_string()).collect();
+        self
+    }
+
+    pub fn with_env(mut self, name: &str, value: &str) -> Self {
+        self.env.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    // The process is killed once the timeout passes
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // Bytes kept of stdout and of stderr; a command that writes more is stopped
+    pub fn with_max_output(mut self, bytes: usize) -> Self {
+        self.max_output = bytes;
+        self
+    }
+
+    // Only these programs may run. Entries match the program exactly as the model
+    // writes it, so `ls` does not allow `/tmp/ls`
+    pub fn with_allowed_commands(mut self, programs: &[&str]) -> Self {
+        self.allowed = Some(programs.iter().map(|program| program.to_string()).collect());
+        self
+    }
+
+    // These programs never run, whatever their path; takes precedence over the allowlist
+    pub fn with_denied_commands(mut self, programs: &[&str]) -> Self {
+        self.denied = programs.iter().map(|program| program.to_string()).collect();
+        self
+    }
+
+    // The model passes a `command` line and gets back `{"exit_code", "stdout", "stderr"}`;
+    // rejected commands and timeouts come back as tool errors
+    pub fn tool(self) -> ToolDefinition {
+        let settings = Arc::new(self);
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "command": {
+                    "type": "string",
+                    "description": "Program and arguments, quoted as in a shell. \
+                                    Pipes, redirection and variables are not supported",
+                },
+            },
+            "required": ["command"],
+        });
+        let function = move |args: Value| {
+            let settings = settings.clone();
+            async move {
+                let Some(command) = args.get("command").and_then(Value::as_str) else {
+                    return ToolOutput::Error("missing command".to_string());
+                };
+                settings.run(command).await
+            }
+            .boxed()
+        };
+        ToolDefinition::new(
+            Tool::new(
+                "run_command",
+                "Runs a command in the working directory and returns its output.",
+                parameters,
+            ),
+            ToolFunction::Async(Arc::new(function)),
+        )
+    }
+
+    async fn run(&self, command: &str) -> ToolOutput {
+        // 1. Split the command line and check the program
+        let argv = match split_command(command) {
+            Ok(argv) if !argv.is_empty() => argv,
+            Ok(_) => return ToolOutput::Error("empty command".to_string()),
+            Err(e) => return ToolOutput::Error(e),
+        };
+        let program = &argv[0];
+        if let Err(e) = self.check(program) {
+            return ToolOutput::Error(e);
+        }
+
+        // 2. Start it with a scrubbed environment, in a process group of its own
+        let mut process = tokio::process::Command::new(program);
+        process
+            .args(&argv[1..])
+            .current_dir(&self.working_dir)
+            .env_clear()
+            .envs(
+                self.inherited_env
+                    .iter()
+                    .filter_map(|name| Some((name, std::env::var_os(name)?))),
+            )
+            .envs(self.env.iter().cloned())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        process.process_group(0);
+        let mut child = match process.spawn() {
+            Ok(child) => child,
+            Err(e) => return ToolOutput::Error(format!("failed to run {}: {}", program, e)),
+        };
+        // Dropping the group kills it, so nothing the command started outlives this call
+        let group = ProcessGroup(child.id());
+
+        // 3. Read both streams up to the output limit while waiting for the exit. Passing
+        // the limit, or the command exiting, kills the group so that background programs
+        // holding the pipes open cannot keep the call waiting
+        let stdout = read_limited(child.stdout.take(), self.max_output, || group.kill());
+        let stderr = read_limited(child.stderr.take(), self.max_output, || group.kill());
+        let exit = async {
+            let status = child.wait().await;
+            group.kill();
+            status
+        };
+        let running = async { tokio::join!(stdout, stderr, exit) };
+        let (stdout, stderr, status) = match tokio::time::timeout(self.timeout, running).await {
+            Ok((stdout, stderr, Ok(status))) => (stdout, stderr, status),
+            Ok((_, _, Err(e))) => {
+                return ToolOutput::Error(format!("failed to run {}: {}", program, e))
+            }
+            Err(_) => {
+                return ToolOutput::Error(format!(
+                    "command timed out after {} seconds",
+                    self.timeout.as_secs_f64()
+                ))
+            }
+        };
+        ToolOutput::Json(json!({
+            "exit_code": status.code(),
+            "stdout": stdout,
+            "stderr": stderr,
+        }))
+    }
+
+    fn check(&self, program: &str) -> std::result::Result<(), String> {
+        let name = Path::new(program)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or(program);
+        if self
+            .denied
+            .iter()
+            .any(|denied| denied == program || denied == name)
+        {
+            return Err(format!("command {} is not allowed", program));
+        }
+        match &self.allowed {
+            Some(allowed) if !allowed.iter().any(|allowed| allowed == program) => {
+                Err(format!("command {} is not allowed", program))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// The process group of a running command. Commands start as group leaders, so the
+// group id is the child's pid
+struct ProcessGroup(Option<u32>);
+
+impl ProcessGroup {
+    fn kill(&self) {
+        #[cfg(unix)]
+        if let Some(pid) = self.0 {
+            // SAFETY: kill only sends a signal; the negative pid addresses the whole group
+            unsafe {
+                libc::kill(-(pid as libc::pid_t), libc::SIGKILL);
+            }
+        }
+    }
+}
+
+impl Drop for ProcessGroup {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+// Reads a stream until it closes. Once it passes `limit` bytes, `overflow` runs and the
+// output kept so far comes back with a note that the command was stopped
+async fn read_limited(
+    stream: Option<impl tokio::io::AsyncRead + Unpin>,
+    limit: usize,
+    overflow: impl FnOnce(),
+) -> String {
+    use tokio::io::AsyncReadExt;
+
+    let Some(mut stream) = stream else {
+        return String::new();
+    };
+    let mut kept = Vec::new();
+    let mut buffer = [0u8; 8192];
+    loop {
+        match stream.read(&mut buffer).await {
+            Ok(0) | Err(_) => return String::from_utf8_lossy(&kept).into_owned(),
+            Ok(read) if kept.len() + read > limit => {
+                kept.extend_from_slice(&buffer[..limit - kept.len()]);
+                overflow();
+                return format!(
+                    "{}\n[output cut at {} bytes; the command was stopped]",
+                    String::from_utf8_lossy(&kept),
+                    limit
+                );
+            }
+            Ok(read) => kept.extend_from_slice(&buffer[..read]),
+        }
+    }
+}
+
+// Splits a command line into arguments, honouring single quotes, double quotes and
+// backslash escapes the way a POSIX shell does
+fn split_command(command: &str) -> std::result::Result<Vec<String>, String> {
+    let mut argv = Vec::new();
+    let mut current: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => argv.extend(current.take()),
+            '\'' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => arg.push(c),
+                        None => return Err("unterminated single quote".to_string()),
+                    }
+                }
+            }
+            '"' => {
+                let arg = current.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => arg.push(c),
+                            Some(c) => {
+                                arg.push('\\');
+                                arg.push(c);
+                            }
+                            None => return Err("unterminated double quote".to_string()),
+                        },
+                        Some(c) => arg.push(c),
+                        None => return Err("unterminated double quote".to_string()),
+                    }
+                }
+            }
+            '\\' => {
+                if let Some(c) = chars.next() {
+                    current.get_or_insert_with(String::new).push(c);
+                }
+            }
+            c => current.get_or_insert_with(String::new).push(c),
+        }
+    }
+    argv.extend(current);
+    Ok(argv)
+}
+
+// Settings of the `read_file`, `write_file` and `list_dir` tools, which see only the
+// files under `root`. Paths from the model are relative to the root; absolute paths and
+// paths that leave it, through `..` or a symlink, are refused
+#[derive(Debug, Clone)]
+pub struct FileTools {
+    root: PathBuf,
+    max_read_bytes: u64,
+    max_write_bytes: usize,
+    read_only: bool,
+}
+
+impl FileTools {
+    // Reads files up to 256 KiB and writes files up to 1 MiB
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        FileTools {
+            root: root.into(),
+            max_read_bytes: 256 * 1024,
+            max_write_bytes: 1024 * 1024,
+            read_only: false,
+        }
+    }
+
+    pub fn with_max_read_bytes(mut self, bytes: u64) -> Self {
+        self.max_read_bytes = bytes;
+        self
+    }
+
+    pub fn with_max_write_bytes(mut self, bytes: usize) -> Self {
+        self.max_write_bytes = bytes;
+        self
+    }
+
+    // Leaves out `write_file`
+    pub fn read_only(mut self) -> Self {
+        self.read_only = true;
+        self
+    }
+
+    // `read_file`, `list_dir` and, unless read-only, `write_file`
+    pub fn tools(&self) -> Vec<ToolDefinition> {
+        let mut tools = vec![self.read_tool(), self.list_tool()];
+        if !self.read_only {
+            tools.push(self.write_tool());
+        }
+        tools
+    }
+
+    // Gives the agent the file tools
+    pub fn equip(&self, agent: Agent) -> Agent {
+        self.tools().into_iter().fold(agent, Agent::with_tool)
+    }
+
+    fn read_tool(&self) -> ToolDefinition {
+        let files = Arc::new(self.clone());
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "File to read, relative to the root" },
+            },
+            "required": ["path"],
+        });
+        let function = move |args: Value| {
+            let files = files.clone();
+            async move {
+                let path = match files.argument_path(&args) {
+                    Ok(path) => path,
+                    Err(e) => return ToolOutput::Error(e),
+                };
+                match files.read(&path).await {
+                    Ok(text) => ToolOutput::Text(text),
+                    Err(e) => ToolOutput::Error(e),
+                }
+            }
+            .boxed()
+        };
+        ToolDefinition::new(
+            Tool::new("read_file", "Reads a text file.", parameters),
+            ToolFunction::Async(Arc::new(function)),
+        )
+    }
+
+    fn write_tool(&self) -> ToolDefinition {
+        let files = Arc::new(self.clone());
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "path": { "type": "string", "description": "File to write, relative to the root" },
+                "content": { "type": "string", "description": "New content of the whole file" },
+            },
+            "required": ["path", "content"],
+        });
+        let function = move |args: Value| {
+            let files = files.clone();
+            async move {
+                let path = match files.argument_path(&args) {
+                    Ok(path) => path,
+                    Err(e) => return ToolOutput::Error(e),
+                };
+                let Some(content) = args.get("content").and_then(Value::as_str) else {
+                    return ToolOutput::Error("missing content".to_string());
+                };
+                match files.write(&path, content).await {
+                    Ok(()) => {
+                        ToolOutput::Text(format!("Wrote {} bytes to {}", content.len(), path))
+                    }
+                    Err(e) => ToolOutput::Error(e),
+                }
+            }
+            .boxed()
+        };
+        ToolDefinition::new(
+            Tool::new(
+                "write_file",
+                "Creates or replaces a text file, creating missing directories.",
+                parameters,
+            ),
+            ToolFunction::Async(Arc::new(function)),
+        )
+    }
+
+    fn list_tool(&self) -> ToolDefinition {
+        let files = Arc::new(self.clone());
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory to list, relative to the root; the root by default",
+                },
+            },
+        });
+        let function = move |args: Value| {
+            let files = files.clone();
+            async move {
+                let path = args.get("path").and_then(Value::as_str).unwrap_or(".");
+                match files.list(path).await {
+                    Ok(listing) => ToolOutput::Json(listing),
+                    Err(e) => ToolOutput::Error(e),
+                }
+            }
+            .boxed()
+        };
+        ToolDefinition::new(
+            Tool::new(
+                "list_dir",
+                "Lists the files and directories in a directory.",
+                parameters,
+            ),
+            ToolFunction::Async(Arc::new(function)),
+        )
+    }
+
+    fn argument_path(&self, args: &Value) -> std::result::Result<String, String> {
+        args.get("path")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .ok_or_else(|| "missing path".to_string())
+    }
+
+    async fn read(&self, path: &str) -> std::result::Result<String, String> {
+        let resolved = self.resolve(path)?;
+        let metadata = tokio::fs::metadata(&resolved)
+            .await
+            .map_err(|e| format!("{}: {}", path, e))?;
+        if !metadata.is_file() {
+            return Err(format!("{} is not a file", path));
+        }
+        if metadata.len() > self.max_read_bytes {
+            return Err(format!(
+                "{} is {} bytes, more than the {} byte limit",
+                path,
+                metadata.len(),
+                self.max_read_bytes
+            ));
+        }
+        let bytes = tokio::fs::read(&resolved)
+            .await
+            .map_err(|e| format!("{}: {}", path, e))?;
+        String::from_utf8(bytes).map_err(|_| format!("{} is not a UTF-8 text file", path))
+    }
+
+    async fn write(&self, path: &str, content: &str) -> std::result::Result<(), String> {
+        if content.len() > self.max_write_bytes {
+            return Err(format!(
+                "content is {} bytes, more than the {} byte limit",
+                content.len(),
+                self.max_write_bytes
+            ));
+        }
+        let resolved = self.resolve(path)?;
+        if resolved.is_dir() {
+            return Err(format!("{} is a directory", path));
+        }
+        if let Some(parent) = resolved.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("{}: {}", path, e))?;
+        }
+        tokio::fs::write(&resolved, content)
+            .await
+            .map_err(|e| format!("{}: {}", path, e))
+    }
+
+    async fn list(&self, path: &str) -> std::result::Result<Value, String> {
+        let resolved = self.resolve(path)?;
+        let mut reader = tokio::fs::read_dir(&resolved)
+            .await
+            .map_err(|e| format!("{}: {}", path, e))?;
+        let mut entries = Vec::new();
+        while let Some(entry) = reader
+            .next_entry()
+            .await
+            .map_err(|e| format!("{}: {}", path, e))?
+        {
+            let Ok(file_type) = entry.file_type().await else {
+                continue;
+            };
+            let kind = if file_type.is_dir() {
+                "dir"
+            } else if file_type.is_symlink() {
+                "symlink"
+            } else {
+                "file"
+            };
+            let size = match file_type.is_file() {
+                true => entry.metadata().await.ok().map(|metadata| metadata.len()),
+                false => None,
+            };
+            entries.push((entry.file_name().to_string_lossy().into_owned(), kind, size));
+        }
+        entries.sort();
+        let truncated = entries.len() > MAX_LIST_ENTRIES;
+        let entries: Vec<Value> = entries
+            .into_iter()
+            .take(MAX_LIST_ENTRIES)
+            .map(|(name, kind, size)| match size {
+                Some(size) => json!({ "name": name, "type": kind, "size": size }),
+                None => json!({ "name": name, "type": kind }),
+            })
+            .collect();
+        Ok(json!({ "entries": entries, "truncated": truncated }))
+    }
+
+    // Joins a model-supplied path onto the root without following `..` out of it, then
+    // checks that the deepest existing part does not lead elsewhere through a symlink
+    fn resolve(&self, path: &str) -> std::result::Result<PathBuf, String> {
+        let root = self
+            .root
+            .canonicalize()
+            .map_err(|e| format!("root directory: {}", e))?;
+        let outside = || format!("{} is outside the root directory", path);
+        let mut resolved = root.clone();
+        for component in Path::new(path).components() {
+            match component {
+                Component::Normal(part) => resolved.push(part),
+                Component::CurDir => {}
+                Component::ParentDir if resolved != root => {
+                    resolved.pop();
+                }
+                _ => return Err(outside()),
+            }
+        }
+        let existing = resolved
+            .ancestors()
+            .find(|ancestor| ancestor.symlink_metadata().is_ok())
+            .unwrap_or(&root);
+        let real = existing
+            .canonicalize()
+            .map_err(|e| format!("{}: {}", path, e))?;
+        if !real.starts_with(&root) {
+            return Err(outside());
+        }
+        Ok(resolved)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh directory under the system temp dir, named after the test
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("swarm-rs-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn splits_plain_words() {
+        assert_eq!(split_command("ls -la  src").unwrap(), ["ls", "-la", "src"]);
+    }
+
+    #[test]
+    fn splits_quoted_arguments() {
+        assert_eq!(
+            split_command(r#"grep -r 'fn main' "src dir" it\'s"#).unwrap(),
+            ["grep", "-r", "fn main", "src dir", "it's"]
+        );
+        assert_eq!(
+            split_command(r#"echo "a \"b\" \n""#).unwrap(),
+            ["echo", r#"a "b" \n"#]
+        );
+        assert_eq!(split_command("echo '' x").unwrap(), ["echo", "", "x"]);
+    }
+
+    #[test]
+    fn rejects_unterminated_quotes() {
+        assert!(split_command("echo 'open").is_err());
+        assert!(split_command("echo \"open").is_err());
+    }
+
+    // The JSON a command returned, or the error it was refused with
+    async fn run(shell: &ShellCommand, command: &str) -> std::result::Result<Value, String> {
+        match shell.run(command).await {
+            ToolOutput::Json(output) => Ok(output),
+            ToolOutput::Error(e) => Err(e),
+            other => panic!("unexpected output {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn denies_programs_under_any_path() {
+        let shell = ShellCommand::new(std::env::temp_dir()).with_denied_commands(&["rm"]);
+        assert!(run(&shell, "rm -rf nothing-here").await.is_err());
+        assert!(run(&shell, "/bin/rm -rf nothing-here").await.is_err());
+        assert!(run(&shell, "echo rm").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn allows_programs_only_as_written() {
+        let shell = ShellCommand::new(std::env::temp_dir())
+            .with_allowed_commands(&["echo", "rm"])
+            .with_denied_commands(&["rm"]);
+        assert_eq!(run(&shell, "echo hi").await.unwrap()["stdout"], "hi\n");
+        assert!(run(&shell, "/bin/echo hi").await.is_err());
+        assert!(run(&shell, "ls").await.is_err());
+        assert!(run(&shell, "rm -rf nothing-here").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn times_out_long_commands() {
+        let shell =
+            ShellCommand::new(std::env::temp_dir()).with_timeout(Duration::from_millis(200));
+        let error = run(&shell, "sleep 5").await.unwrap_err();
+        assert!(error.contains("timed out"), "{}", error);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn kills_background_programs_when_the_command_exits() {
+        let shell = ShellCommand::new(std::env::temp_dir()).with_timeout(Duration::from_secs(20));
+        let started = std::time::Instant::now();
+        let output = run(&shell, "sh -c 'sleep 30 & echo started'")
+            .await
+            .unwrap();
+        assert_eq!(output["stdout"], "started\n");
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn stops_commands_that_pass_the_output_limit() {
+        let shell = ShellCommand::new(std::env::temp_dir())
+            .with_timeout(Duration::from_secs(20))
+            .with_max_output(100);
+        let started = std::time::Instant::now();
+        let output = run(&shell, "yes").await.unwrap();
+        let stdout = output["stdout"].as_str().unwrap();
+        assert!(stdout.starts_with(&"y\n".repeat(50)));
+        assert!(stdout.ends_with("[output cut at 100 bytes; the command was stopped]"));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[tokio::test]
+    async fn passes_only_the_chosen_environment() {
+        let shell = ShellCommand::new(std::env::temp_dir()).with_env("SWARM_TEST", "1");
+        let output = run(&shell, "env").await.unwrap();
+        let mut names: Vec<&str> = output["stdout"]
+            .as_str()
+            .unwrap()
+            .lines()
+            .filter_map(|line| line.split('=').next())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["PATH", "SWARM_TEST"]);
+    }
+
+    #[test]
+    fn resolves_paths_under_the_root() {
+        let dir = scratch_dir("resolve-inside");
+        let files = FileTools::new(&dir);
+        let root = dir.canonicalize().unwrap();
+        assert_eq!(
+            files.resolve("sub/new.txt").unwrap(),
+            root.join("sub/new.txt")
+        );
+        assert_eq!(files.resolve("./sub/../a.txt").unwrap(), root.join("a.txt"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn refuses_paths_that_leave_the_root() {
+        let dir = scratch_dir("resolve-outside");
+        let files = FileTools::new(&dir);
+        assert!(files.resolve("../etc/passwd").is_err());
+        assert!(files.resolve("sub/../../x").is_err());
+        assert!(files.resolve("/etc/passwd").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_symlinks_that_leave_the_root() {
+        let dir = scratch_dir("resolve-symlink");
+        std::os::unix::fs::symlink(std::env::temp_dir(), dir.join("escape")).unwrap();
+        let files = FileTools::new(&dir);
+        assert!(files.resolve("escape/x").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,273 @@
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use wasmtime::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::p1::WasiP1Ctx;
+use wasmtime_wasi::p2::pipe::{MemoryInputPipe, MemoryOutputPipe};
+use wasmtime_wasi::{I32Exit, WasiCtxBuilder};
+
+use crate::error::{Result, SwarmError};
+use crate::types::{Tool, ToolDefinition, ToolFunction, ToolOutput};
+
+// Placeholder in the interpreter arguments replaced by the model's code
+pub const CODE_PLACEHOLDER: &str = "{code}";
+
+// Resolution of the timeout; running programs are checked for it this often
+const TICK: Duration = Duration::from_millis(10);
+
+// Bytes captured per stream before the program is stopped, whatever `max_output` says
+const CAPTURE_LIMIT: usize = 1024 * 1024;
+
+// What a finished program printed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Execution {
+    pub exit_code: i32,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+struct SandboxState {
+    wasi: WasiP1Ctx,
+    limits: StoreLimits,
+}
+
+// Runs model-written code with an interpreter compiled to WebAssembly, e.g. CPython or
+// QuickJS built for WASI. The program gets no files, environment or network; it sees
+// only its arguments and stdin, and runs within memory and time limits
+#[derive(Clone)]
+pub struct CodeInterpreter {
+    engine: Engine,
+    module: Module,
+    linker: Arc<Linker<SandboxState>>,
+    language: String,
+    args: Vec<String>,
+    timeout: Duration,
+    max_memory: usize,
+    max_output: usize,
+}
+
+impl CodeInterpreter {
+    // Compiles a WASI command module once; `language` names it for the model
+    pub fn from_bytes(wasm: &[u8], language: &str) -> Result<Self> {
+        let mut config = Config::new();
+        config.epoch_interruption(true);
+        let engine = Engine::new(&config).map_err(sandbox_error)?;
+        let module = Module::new(&engine, wasm).map_err(sandbox_error)?;
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::p1::add_to_linker_sync(&mut linker, |state: &mut SandboxState| {
+            &mut state.wasi
+        })
+        .map_err(sandbox_error)?;
+
+        // Advance the clock that deadlines count in until the interpreter is dropped
+        let weak = engine.weak();
+        std::thread::spawn(move || loop {
+            std::thread::sleep(TICK);
+            match weak.upgrade() {
+                Some(engine) => engine.increment_epoch(),
+                None => break,
+            }
+        });
+
+        Ok(CodeInterpreter {
+            engine,
+            module,
+            linker: Arc::new(linker),
+            language: language.to_string(),
+            args: vec![
+                "main".to_string(),
+                "-c".to_string(),
+                CODE_PLACEHOLDER.to_string(),
+            ],
+            timeout: Duration::from_secs(10),
+            max_memory: 256 * 1024 * 1024,
+            max_output: 16 * 1024,
+        })
+    }
+
+    pub fn from_file(path: impl AsRef<Path>, language: &str) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?, language)
+    }
+
+    // Interpreter arguments, starting with the program name. `{code}` is replaced by the
+    // code; without it the code is passed on stdin. Defaults to `main -c {code}`
+    pub fn with_args(mut self, args: &[&str]) -> Self {
+        self.args = args.iter().map(|arg| arg.to_string()).collect();
+        self
+    }
+
+    // Wall-clock limit per run, 10 seconds by default
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // Linear memory limit per run, 256 MiB by default
+    pub fn with_max_memory(mut self, bytes: usize) -> Self {
+        self.max_memory = bytes;
+        self
+    }
+
+    // Bytes of stdout and of stderr returned to the model; the middle of longer output is cut
+    pub fn with_max_output(mut self, bytes: usize) -> Self {
+        self.max_output = bytes;
+        self
+    }
+
+    // Runs the code in a fresh instance. Timeouts and crashes of the interpreter itself
+    // are errors; a program that fails normally returns its exit code
+    pub async fn run(&self, code: &str) -> Result<Execution> {
+        let interpreter = self.clone();
+        let code = code.to_string();
+        tokio::task::spawn_blocking(move || interpreter.run_blocking(&code))
+            .await
+            .map_err(|e| SwarmError::Sandbox(e.to_string()))?
+    }
+
+    fn run_blocking(&self, code: &str) -> Result<Execution> {
+        // 1. Build a WASI context with only arguments and captured stdio
+        let stdout = MemoryOutputPipe::new(CAPTURE_LIMIT);
+        let stderr = MemoryOutputPipe::new(CAPTURE_LIMIT);
+        let code_in_args = self.args.iter().any(|arg| arg.contains(CODE_PLACEHOLDER));
+        let args: Vec<String> = self
+            .args
+            .iter()
+            .map(|arg| arg.replace(CODE_PLACEHOLDER, code))
+            .collect();
+        let mut wasi = WasiCtxBuilder::new();
+        wasi.args(&args)
+            .stdout(stdout.clone())
+            .stderr(stderr.clone());
+        if !code_in_args {
+            wasi.stdin(MemoryInputPipe::new(code.as_bytes().to_vec()));
+        }
+        let state = SandboxState {
+            wasi: wasi.build_p1(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size(self.max_memory)
+                .build(),
+        };
+
+        // 2. Start the program with its limits
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_epoch_deadline(self.timeout.as_millis().div_ceil(TICK.as_millis()) as u64);
+        let outcome = self
+            .linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|instance| instance.get_typed_func::<(), ()>(&mut store, "_start"))
+            .and_then(|start| start.call(&mut store, ()));
+
+        // 3. Read the exit status from the outcome
+        let exit_code = match outcome {
+            Ok(()) => 0,
+            Err(e) => match e.downcast_ref::<I32Exit>() {
+                Some(exit) => exit.0,
+                None if e.downcast_ref::<Trap>() == Some(&Trap::Interrupt) => {
+                    return Err(SwarmError::Sandbox(format!(
+                        "timed out after {} seconds",
+                        self.timeout.as_secs_f64()
+                    )))
+                }
+                None if stdout.contents().len() + 1 >= CAPTURE_LIMIT
+                    || stderr.contents().len() + 1 >= CAPTURE_LIMIT =>
+                {
+                    return Err(SwarmError::Sandbox(format!(
+                        "program printed more than {} bytes",
+                        CAPTURE_LIMIT
+                    )))
+                }
+                None => return Err(sandbox_error(e)),
+            },
+        };
+        Ok(Execution {
+            exit_code,
+            stdout: truncate_middle(
+                &String::from_utf8_lossy(&stdout.contents()),
+                self.max_output,
+            ),
+            stderr: truncate_middle(
+                &String::from_utf8_lossy(&stderr.contents()),
+                self.max_output,
+            ),
+        })
+    }
+
+    // The model passes `code` and gets back `{"exit_code", "stdout", "stderr"}`; only
+    // printed output is returned, so the description asks it to print results
+    pub fn tool(self) -> ToolDefinition {
+        let description = format!(
+            "Runs {} code in a sandbox without files or network and returns what it \
+             printed. Print every result you need. Each call starts from a fresh state.",
+            self.language
+        );
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "code": {
+                    "type": "string",
+                    "description": format!("{} program to run", self.language),
+                },
+            },
+            "required": ["code"],
+        });
+        let interpreter = Arc::new(self);
+        let function = move |args: Value| {
+            let interpreter = interpreter.clone();
+            async move {
+                let Some(code) = args.get("code").and_then(Value::as_str) else {
+                    return ToolOutput::Error("missing code".to_string());
+                };
+                match interpreter.run(code).await {
+                    Ok(execution) => ToolOutput::Json(json!(execution)),
+                    Err(e) => ToolOutput::Error(e.to_string()),
+                }
+            }
+            .boxed()
+        };
+        ToolDefinition::new(
+            Tool::new("code_interpreter", &description, parameters),
+            ToolFunction::Async(Arc::new(function)),
+        )
+    }
+}
+
+impl std::fmt::Debug for CodeInterpreter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CodeInterpreter")
+            .field("language", &self.language)
+            .field("args", &self.args)
+            .field("timeout", &self.timeout)
+            .field("max_memory", &self.max_memory)
+            .field("max_output", &self.max_output)
+            .finish()
+    }
+}
+
+fn sandbox_error(error: wasmtime::Error) -> SwarmError {
+    SwarmError::Sandbox(format!("{:#}", error))
+}
+
+// Keeps the start and end of long output, where commands usually report what matters
+fn truncate_middle(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+    let mut head = max_bytes / 2;
+    while !text.is_char_boundary(head) {
+        head -= 1;
+    }
+    let mut tail = text.len() - (max_bytes - max_bytes / 2);
+    while !text.is_char_boundary(tail) {
+        tail += 1;
+    }
+    format!(
+        "{}\n[... {} bytes truncated ...]\n{}",
+        &text[..head],
+        tail - head,
+        &text[tail..]
+    )
+}
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::rate_limit::RateLimit;
+use crate::retry::RetryPolicy;
+
+// Swarm-wide settings applied to every provider and tool call
+#[derive(Debug, Clone)]
+pub struct SwarmConfig {
+    pub retry: RetryPolicy,
+    pub rate_limit: Option<RateLimit>,
+    // Limit for a single tool call unless overridden in `tool_timeouts`
+    pub tool_timeout: Option<Duration>,
+    pub tool_timeouts: HashMap<String, Duration>,
+    // Limit for all tool calls requested in one turn
+    pub turn_timeout: Option<Duration>,
+    // How many runs `run_parallel` drives at once; unlimited if unset
+    pub max_parallel_runs: Option<usize>,
+    // How often `run_typed` asks again after a reply fails to parse
+    pub output_retries: usize,
+    // Checks tool arguments against the tool's schema before it runs
+    pub validate_tool_arguments: bool,
+    // Guards against agents stuck calling the same tool over and over
+    pub loop_detection: Option<LoopDetection>,
+}
+
+impl Default for SwarmConfig {
+    fn default() -> Self {
+        SwarmConfig {
+            retry: RetryPolicy::default(),
+            rate_limit: None,
+            tool_timeout: None,
+            tool_timeouts: HashMap::new(),
+            turn_timeout: None,
+            max_parallel_runs: None,
+            output_retries: 2,
+            validate_tool_arguments: true,
+            loop_detection: None,
+        }
+    }
+}
+
+impl SwarmConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
+
+    pub fn with_rate_limit(mut self, rate_limit: RateLimit) -> Self {
+        self.rate_limit = Some(rate_limit);
+        self
+    }
+
+    pub fn with_tool_timeout(mut self, timeout: Duration) -> Self {
+        self.tool_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_tool_timeout_for(mut self, name: &str, timeout: Duration) -> Self {
+        self.tool_timeouts.insert(name.to_string(), timeout);
+        self
+    }
+
+    pub fn with_turn_timeout(mut self, timeout: Duration) -> Self {
+        self.turn_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_max_parallel_runs(mut self, max_parallel_runs: usize) -> Self {
+        self.max_parallel_runs = Some(max_parallel_runs.max(1));
+        self
+    }
+
+    pub fn with_output_retries(mut self, output_retries: usize) -> Self {
+        self.output_retries = output_retries;
+        self
+    }
+
+    pub fn with_tool_argument_validation(mut self, validate: bool) -> Self {
+        self.validate_tool_arguments = validate;
+        self
+    }
+
+    pub fn with_loop_detection(mut self, loop_detection: LoopDetection) -> Self {
+        self.loop_detection = Some(loop_detection);
+        self
+    }
+
+    // Returns the timeout that applies to the named tool
+    pub fn tool_timeout_for(&self, name: &str) -> Option<Duration> {
+        self.tool_timeouts.get(name).copied().or(self.tool_timeout)
+    }
+}
+
+// What to do once a tool has been called with identical arguments too often in a row
+#[derive(Debug, Clone)]
+pub enum LoopAction {
+    // Fail the run with `SwarmError::ToolLoop`
+    Error,
+    // Run the calls, then add this user message to steer the model elsewhere
+    Nudge(String),
+    // End the run before the repeated calls, with `RunStatus::ToolLoopDetected`
+    Stop,
+}
+
+#[derive(Debug, Clone)]
+pub struct LoopDetection {
+    // Consecutive identical calls that count as a loop
+    pub threshold: usize,
+    pub action: LoopAction,
+}
+
+impl LoopDetection {
+    pub fn new(threshold: usize, action: LoopAction) -> Self {
+        LoopDetection {
+            threshold: threshold.max(2),
+            action,
+        }
+    }
+}
@@ -0,0 +1,296 @@
+use async_openai::types::{ChatCompletionRequestMessage, ImageDetail};
+use async_trait::async_trait;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+
+use crate::error::Result;
+use crate::messages::{message_images, message_role, message_text, system_message, user_message};
+use crate::provider::{ChatProvider, ChatRequest};
+use crate::types::ModelSettings;
+
+// Tokens the chat format adds around every message and to prime the reply
+const TOKENS_PER_MESSAGE: usize = 3;
+const TOKENS_PER_REPLY: usize = 3;
+
+// Image costs: low detail is fixed, higher detail depends on the size, which we do not
+// know, so assume a 1024x1024 image (four 512px tiles plus the base cost)
+const LOW_DETAIL_IMAGE_TOKENS: usize = 85;
+const HIGH_DETAIL_IMAGE_TOKENS: usize = 765;
+
+// Counts tokens in text using the model's tokenizer, defaulting to cl100k_base
+pub fn count_tokens(model: &str, text: &str) -> usize {
+    let bpe = match get_tokenizer(model) {
+        Some(Tokenizer::O200kBase) => tiktoken_rs::o200k_base_singleton(),
+        _ => tiktoken_rs::cl100k_base_singleton(),
+    };
+    let bpe = bpe.lock();
+    bpe.encode_with_special_tokens(text).len()
+}
+
+// Estimates the prompt tokens of a message list, including tool call payloads
+pub fn count_message_tokens(model: &str, messages: &[ChatCompletionRequestMessage]) -> usize {
+    messages
+        .iter()
+        .map(|message| {
+            let mut tokens = TOKENS_PER_MESSAGE + count_tokens(model, &message_text(message));
+            tokens += message_images(message)
+                .into_iter()
+                .map(|image| image_tokens(image.detail.as_ref()))
+                .sum::<usize>();
+            if let ChatCompletionRequestMessage::Assistant(msg) = message {
+                for tool_call in msg.tool_calls.iter().flatten() {
+                    tokens += count_tokens(model, &tool_call.function.name);
+                    tokens += count_tokens(model, &tool_call.function.arguments);
+                }
+            }
+            tokens
+        })
+        .sum::<usize>()
+        + TOKENS_PER_REPLY
+}
+
+// Estimates the prompt tokens of one image
+pub fn image_tokens(detail: Option<&ImageDetail>) -> usize {
+    match detail {
+        Some(ImageDetail::Low) => LOW_DETAIL_IMAGE_TOKENS,
+        _ => HIGH_DETAIL_IMAGE_TOKENS,
+    }
+}
+
+// Estimates all prompt tokens of a request, including tool definitions
+pub fn count_request_tokens(request: &ChatRequest) -> usize {
+    let tools = serde_json::to_string(&request.tools).unwrap_or_default();
+    count_message_tokens(&request.model, &request.messages)
+        + if request.tools.is_empty() {
+            0
+        } else {
+            count_tokens(&request.model, &tools)
+        }
+}
+
+pub fn context_window(model: &str) -> usize {
+    tiktoken_rs::model::get_context_size(model)
+}
+
+// Rewrites a request's history before it is sent to the provider
+#[async_trait]
+pub trait ContextPolicy: Send + Sync {
+    async fn apply(&self, request: ChatRequest) -> Result<ChatRequest>;
+}
+
+// Drops the oldest non-system messages until the request fits the context window
+#[derive(Debug, Clone, Default)]
+pub struct TruncationPolicy {
+    // Overrides the model's known context window
+    pub context_window: Option<usize>,
+    // Tokens kept free for the reply; defaults to the request's max_tokens
+    pub reserve_tokens: Option<usize>,
+}
+
+impl TruncationPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_context_window(mut self, tokens: usize) -> Self {
+        self.context_window = Some(tokens);
+        self
+    }
+
+    pub fn with_reserve_tokens(mut self, tokens: usize) -> Self {
+        self.reserve_tokens = Some(tokens);
+        self
+    }
+
+    // Token budget available for the prompt of this request
+    pub fn budget(&self, request: &ChatRequest) -> usize {
+        let window = self
+            .context_window
+            .unwrap_or_else(|| context_window(&request.model));
+        let reserve = self
+            .reserve_tokens
+            .or(request.settings.max_tokens.map(|t| t as usize))
+            .unwrap_or(0);
+        window.saturating_sub(reserve)
+    }
+}
+
+#[async_trait]
+impl ContextPolicy for TruncationPolicy {
+    async fn apply(&self, mut request: ChatRequest) -> Result<ChatRequest> {
+        let budget = self.budget(&request);
+        if count_request_tokens(&request) <= budget {
+            return Ok(request);
+        }
+
+        // 1. Pin leading system messages
+        let pinned = request
+            .messages
+            .iter()
+            .take_while(|m| matches!(m, ChatCompletionRequestMessage::System(_)))
+            .count();
+        let rest = request.messages.split_off(pinned);
+        let costs: Vec<usize> = rest
+            .iter()
+            .map(|m| {
+                count_message_tokens(&request.model, std::slice::from_ref(m)) - TOKENS_PER_REPLY
+            })
+            .collect();
+        let mut total = count_request_tokens(&request) + costs.iter().sum::<usize>();
+
+        // 2. Drop oldest messages, never leaving tool results without their call
+        let mut dropped = 0;
+        while total > budget && rest.len() - dropped > 1 {
+            total -= costs[dropped];
+            dropped += 1;
+            while rest.len() - dropped > 1
+                && matches!(rest[dropped], ChatCompletionRequestMessage::Tool(_))
+            {
+                total -= costs[dropped];
+                dropped += 1;
+            }
+        }
+
+        request.messages.extend(rest.into_iter().skip(dropped));
+        Ok(request)
+    }
+}
+
+// Replaces older turns with an LLM-written summary once history grows past a threshold
+pub struct Summarizer {
+    provider: Arc<dyn ChatProvider>,
+    model: String,
+    threshold_tokens: usize,
+    keep_recent: usize,
+    pinned: Arc<dyn Fn(&ChatCompletionRequestMessage) -> bool + Send + Sync>,
+    // Last summary, keyed by a hash of the messages it covers
+    cache: Mutex<Option<(u64, String)>>,
+}
+
+impl Summarizer {
+    pub fn new(provider: Arc<dyn ChatProvider>, model: &str) -> Self {
+        Summarizer {
+            provider,
+            model: model.to_string(),
+            threshold_tokens: 8_000,
+            keep_recent: 10,
+            pinned: Arc::new(|_| false),
+            cache: Mutex::new(None),
+        }
+    }
+
+    // Summarize once the request exceeds this many prompt tokens
+    pub fn with_threshold(mut self, tokens: usize) -> Self {
+        self.threshold_tokens = tokens;
+        self
+    }
+
+    // Number of most recent messages always kept verbatim
+    pub fn with_keep_recent(mut self, messages: usize) -> Self {
+        self.keep_recent = messages;
+        self
+    }
+
+    // Messages matching the predicate are kept verbatim instead of summarized
+    pub fn with_pinned(
+        mut self,
+        pinned: impl Fn(&ChatCompletionRequestMessage) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.pinned = Arc::new(pinned);
+        self
+    }
+
+    async fn summarize(&self, messages: &[ChatCompletionRequestMessage]) -> Result<String> {
+        // 1. Reuse the previous summary when the covered messages are unchanged
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(messages)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        let key = hasher.finish();
+        if let Some((cached_key, summary)) = self.cache.lock().unwrap().as_ref() {
+            if *cached_key == key {
+                return Ok(summary.clone());
+            }
+        }
+
+        // 2. Ask the model for a summary of the transcript
+        let transcript = messages
+            .iter()
+            .map(|m| match message_images(m).len() {
+                0 => format!("{}: {}", message_role(m), message_text(m)),
+                // The summarizer cannot see images, so at least note that they were there
+                images => format!(
+                    "{}: {} [{} image(s)]",
+                    message_role(m),
+                    message_text(m),
+                    images
+                ),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                system_message(
+                    "Summarize the following conversation. Keep facts, decisions, tool \
+                     results and open questions that later turns may rely on.",
+                ),
+                user_message(&transcript),
+            ],
+            tools: Vec::new(),
+            parallel_tool_calls: false,
+            settings: ModelSettings::default(),
+        };
+        let summary = self
+            .provider
+            .chat(request)
+            .await?
+            .message
+            .content
+            .unwrap_or_default();
+
+        *self.cache.lock().unwrap() = Some((key, summary.clone()));
+        Ok(summary)
+    }
+}
+
+#[async_trait]
+impl ContextPolicy for Summarizer {
+    async fn apply(&self, mut request: ChatRequest) -> Result<ChatRequest> {
+        if count_request_tokens(&request) <= self.threshold_tokens {
+            return Ok(request);
+        }
+
+        // 1. Split into leading system messages, older turns and recent turns
+        let pinned = request
+            .messages
+            .iter()
+            .take_while(|m| matches!(m, ChatCompletionRequestMessage::System(_)))
+            .count();
+        let mut rest = request.messages.split_off(pinned);
+        let mut boundary = rest.len().saturating_sub(self.keep_recent);
+        while boundary > 0 && matches!(rest[boundary], ChatCompletionRequestMessage::Tool(_)) {
+            boundary -= 1;
+        }
+        if boundary == 0 {
+            request.messages.append(&mut rest);
+            return Ok(request);
+        }
+        let recent = rest.split_off(boundary);
+
+        // 2. Keep pinned older messages and summarize the others
+        let (kept, summarized): (Vec<_>, Vec<_>) = rest.into_iter().partition(|m| (self.pinned)(m));
+        request.messages.extend(kept);
+        if !summarized.is_empty() {
+            let summary = self.summarize(&summarized).await?;
+            request.messages.push(system_message(&format!(
+                "Summary of the earlier conversation:\n{}",
+                summary
+            )));
+        }
+        request.messages.extend(recent);
+        Ok(request)
+    }
+}
@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::types::Usage;
+
+// USD prices per million tokens for a single model
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub prompt_per_million: f64,
+    pub completion_per_million: f64,
+}
+
+impl ModelPrice {
+    pub fn new(prompt_per_million: f64, completion_per_million: f64) -> Self {
+        ModelPrice {
+            prompt_per_million,
+            completion_per_million,
+        }
+    }
+
+    pub fn cost(&self, usage: &Usage) -> f64 {
+        (usage.prompt_tokens as f64 * self.prompt_per_million
+            + usage.completion_tokens as f64 * self.completion_per_million)
+            / 1_000_000.0
+    }
+}
+
+// Maps model names to prices, matching dated snapshots by longest prefix
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PricingTable {
+    prices: HashMap<String, ModelPrice>,
+}
+
+impl Default for PricingTable {
+    fn default() -> Self {
+        PricingTable::empty()
+            .with_price("gpt-4o", ModelPrice::new(2.50, 10.00))
+            .with_price("gpt-4o-mini", ModelPrice::new(0.15, 0.60))
+            .with_price("gpt-4-turbo", ModelPrice::new(10.00, 30.00))
+            .with_price("gpt-4", ModelPrice::new(30.00, 60.00))
+            .with_price("gpt-3.5-turbo", ModelPrice::new(0.50, 1.50))
+            .with_price("o1-preview", ModelPrice::new(15.00, 60.00))
+            .with_price("o1-mini", ModelPrice::new(3.00, 12.00))
+    }
+}
+
+impl PricingTable {
+    pub fn empty() -> Self {
+        PricingTable {
+            prices: HashMap::new(),
+        }
+    }
+
+    pub fn with_price(mut self, model: &str, price: ModelPrice) -> Self {
+        self.set_price(model, price);
+        self
+    }
+
+    pub fn set_price(&mut self, model: &str, price: ModelPrice) {
+        self.prices.insert(model.to_string(), price);
+    }
+
+    pub fn price_for(&self, model: &str) -> Option<&ModelPrice> {
+        self.prices.get(model).or_else(|| {
+            self.prices
+                .iter()
+                .filter(|(name, _)| model.starts_with(name.as_str()))
+                .max_by_key(|(name, _)| name.len())
+                .map(|(_, price)| price)
+        })
+    }
+
+    // Returns the USD cost of the usage, or zero for unknown models
+    pub fn cost(&self, model: &str, usage: &Usage) -> f64 {
+        self.price_for(model)
+            .map(|price| price.cost(usage))
+            .unwrap_or(0.0)
+    }
+}
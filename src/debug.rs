@@ -0,0 +1,102 @@
+use async_openai::types::ChatCompletionResponseMessage;
+use serde_json::Value;
+use std::fmt;
+
+use crate::types::ToolResult;
+
+// A diagnostic emitted by runs with `debug` set
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum DebugEvent {
+    RunStarted {
+        agent: String,
+        messages: usize,
+    },
+    TurnStarted {
+        turn: usize,
+        agent: String,
+    },
+    CompletionReceived {
+        agent: String,
+        message: ChatCompletionResponseMessage,
+    },
+    ToolCallProcessing {
+        agent: String,
+        name: String,
+        arguments: Value,
+    },
+    ToolResult {
+        agent: String,
+        name: String,
+        result: ToolResult,
+    },
+    Handoff {
+        from: String,
+        to: String,
+    },
+    RunEnded {
+        messages: usize,
+    },
+    RunFailed {
+        error: String,
+    },
+    // Progress of a higher-level pattern, e.g. a supervisor's plan or a router's choice
+    Pattern {
+        pattern: &'static str,
+        message: String,
+    },
+}
+
+impl fmt::Display for DebugEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DebugEvent::RunStarted { agent, messages } => {
+                write!(f, "Starting run with {} on {} messages.", agent, messages)
+            }
+            DebugEvent::TurnStarted { turn, agent } => write!(f, "Turn {} with {}.", turn, agent),
+            DebugEvent::CompletionReceived { message, .. } => {
+                write!(f, "Received completion: {:?}", message)
+            }
+            DebugEvent::ToolCallProcessing {
+                name, arguments, ..
+            } => write!(
+                f,
+                "processing tool call: {} with arguments {:?}",
+                name, arguments
+            ),
+            DebugEvent::ToolResult { name, result, .. } => {
+                write!(f, "tool {} result: {:?}", name, result)
+            }
+            DebugEvent::Handoff { from, to } => write!(f, "Handing off from {} to {}.", from, to),
+            DebugEvent::RunEnded { messages } => {
+                write!(f, "Ending run with {} messages.", messages)
+            }
+            DebugEvent::RunFailed { error } => write!(f, "Run failed: {}", error),
+            DebugEvent::Pattern { pattern, message } => write!(f, "{}: {}", pattern, message),
+        }
+    }
+}
+
+// Receives debug events so they can be routed to the application's logging stack
+pub trait DebugSink: Send + Sync {
+    fn record(&self, event: DebugEvent);
+}
+
+impl<F> DebugSink for F
+where
+    F: Fn(DebugEvent) + Send + Sync,
+{
+    fn record(&self, event: DebugEvent) {
+        self(event)
+    }
+}
+
+// Default sink, printing each event on its own line
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StdoutSink;
+
+impl DebugSink for StdoutSink {
+    fn record(&self, event: DebugEvent) {
+        println!("{}", event);
+    }
+}
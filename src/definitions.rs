@@ -0,0 +1,301 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::SwarmConfig;
+use crate::error::{Result, SwarmError};
+use crate::types::{
+    handoff_tool_with, Agent, Instructions, ModelSettings, ToolDefinition, ToolOutput, ToolRegistry,
+};
+
+// Agents built without their handoffs, by name, with the names they hand off to
+type Graph = HashMap<String, (Agent, Vec<String>)>;
+
+// An agent as written in a config file; tools and handoffs are referenced by name
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentDefinition {
+    pub name: String,
+    #[serde(default = "default_model")]
+    pub model: String,
+    #[serde(default)]
+    pub instructions: String,
+    // Renders `instructions` as a template over the context variables
+    #[serde(default)]
+    pub template: bool,
+    #[serde(default)]
+    pub model_settings: ModelSettings,
+    // Names of tools registered in code
+    #[serde(default)]
+    pub tools: Vec<String>,
+    // Names of agents this one can transfer the conversation to
+    #[serde(default)]
+    pub handoffs: Vec<String>,
+    #[serde(default)]
+    pub tool_choice: Option<String>,
+    #[serde(default = "default_parallel_tool_calls")]
+    pub parallel_tool_calls: bool,
+}
+
+fn default_model() -> String {
+    Agent::default().model
+}
+
+fn default_parallel_tool_calls() -> bool {
+    true
+}
+
+// Swarm-wide settings as written in a config file; durations are in seconds
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SettingsDefinition {
+    pub tool_timeout_secs: Option<f64>,
+    #[serde(default)]
+    pub tool_timeouts_secs: HashMap<String, f64>,
+    pub turn_timeout_secs: Option<f64>,
+    pub max_parallel_runs: Option<usize>,
+    pub output_retries: Option<usize>,
+    pub validate_tool_arguments: Option<bool>,
+}
+
+impl SettingsDefinition {
+    pub fn to_config(&self) -> SwarmConfig {
+        let defaults = SwarmConfig::default();
+        SwarmConfig {
+            tool_timeout: self.tool_timeout_secs.map(Duration::from_secs_f64),
+            tool_timeouts: self
+                .tool_timeouts_secs
+                .iter()
+                .map(|(name, secs)| (name.clone(), Duration::from_secs_f64(*secs)))
+                .collect(),
+            turn_timeout: self.turn_timeout_secs.map(Duration::from_secs_f64),
+            max_parallel_runs: self.max_parallel_runs.map(|runs| runs.max(1)),
+            output_retries: self.output_retries.unwrap_or(defaults.output_retries),
+            validate_tool_arguments: self
+                .validate_tool_arguments
+                .unwrap_or(defaults.validate_tool_arguments),
+            ..defaults
+        }
+    }
+}
+
+// Contents of a YAML or TOML config file: agents, the one runs start with, and settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SwarmDefinition {
+    #[serde(default)]
+    pub agents: Vec<AgentDefinition>,
+    // Name of the starting agent; the first agent if unset
+    #[serde(default)]
+    pub entry: Option<String>,
+    #[serde(default)]
+    pub settings: SettingsDefinition,
+}
+
+impl SwarmDefinition {
+    // Parses `.yaml`/`.yml` or `.toml` files, by extension
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        parse_file(path.as_ref())
+    }
+
+    // Builds every agent, binding tool names to functions in `registry` and wiring handoffs
+    pub fn build(&self, registry: &ToolRegistry) -> Result<Agents> {
+        // 1. Check names before building anything
+        let names = check_names(
+            self.agents
+                .iter()
+                .map(|definition| (definition.name.as_str(), definition.handoffs.as_slice())),
+        )?;
+        let entry = match &self.entry {
+            Some(entry) if names.contains(entry.as_str()) => entry.clone(),
+            Some(entry) => {
+                return Err(config_error(format!(
+                    "entry agent {} is not defined",
+                    entry
+                )))
+            }
+            None => match self.agents.first() {
+                Some(definition) => definition.name.clone(),
+                None => return Err(config_error("no agents defined".to_string())),
+            },
+        };
+
+        // 2. Build agents, then wire their handoffs
+        let mut graph = Graph::new();
+        for definition in &self.agents {
+            let agent = definition.build(registry)?;
+            graph.insert(
+                definition.name.clone(),
+                (agent, definition.handoffs.clone()),
+            );
+        }
+        Ok(Agents::wire(graph, entry))
+    }
+}
+
+impl AgentDefinition {
+    pub(crate) fn instructions(&self) -> Instructions {
+        if self.template {
+            Instructions::template(self.instructions.clone())
+        } else {
+            Instructions::from(self.instructions.clone())
+        }
+    }
+
+    // Builds the agent without its handoffs
+    fn build(&self, registry: &ToolRegistry) -> Result<Agent> {
+        let mut agent = Agent {
+            name: self.name.clone(),
+            model: self.model.clone(),
+            instructions: self.instructions(),
+            tools: Vec::new(),
+            tool_choice: self.tool_choice.clone(),
+            parallel_tool_calls: self.parallel_tool_calls,
+            model_settings: self.model_settings.clone(),
+            registry: None,
+        };
+        for name in &self.tools {
+            let definition = registry.get_definition(name).ok_or_else(|| {
+                config_error(format!(
+                    "agent {} uses unregistered tool {}",
+                    self.name, name
+                ))
+            })?;
+            agent = agent.with_tool(definition);
+        }
+        Ok(agent)
+    }
+}
+
+// Agents built from a `SwarmDefinition`
+#[derive(Debug, Clone)]
+pub struct Agents {
+    agents: HashMap<String, Agent>,
+    entry: String,
+}
+
+impl Agents {
+    // Wires agents built in code, each paired with the names of the agents it hands off to.
+    // Unlike `Agent::with_handoffs`, handoffs look their target up when called, so agents
+    // can hand the conversation back and forth. The first agent is the entry
+    pub fn new(agents: Vec<(Agent, Vec<String>)>) -> Result<Agents> {
+        check_names(
+            agents
+                .iter()
+                .map(|(agent, handoffs)| (agent.name.as_str(), handoffs.as_slice())),
+        )?;
+        let entry = match agents.first() {
+            Some((agent, _)) => agent.name.clone(),
+            None => return Err(config_error("no agents defined".to_string())),
+        };
+        let graph = agents
+            .into_iter()
+            .map(|(agent, handoffs)| (agent.name.clone(), (agent, handoffs)))
+            .collect();
+        Ok(Agents::wire(graph, entry))
+    }
+
+    // Gives every agent handoffs that look their target up when called. The graph only
+    // holds agents without handoffs, so cycles between agents do not keep it alive
+    fn wire(graph: Graph, entry: String) -> Agents {
+        let graph = Arc::new(graph);
+        let agents = graph
+            .keys()
+            .map(|name| (name.clone(), with_late_handoffs(name, &graph)))
+            .collect();
+        Agents { agents, entry }
+    }
+
+    // The agent runs start with
+    pub fn entry(&self) -> &Agent {
+        &self.agents[&self.entry]
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Agent> {
+        self.agents.get(name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.agents.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+}
+
+impl Agent {
+    // Loads the entry agent of a config file, with the handoff graph behind it
+    pub fn from_file(path: impl AsRef<Path>, registry: &ToolRegistry) -> Result<Agent> {
+        let agents = SwarmDefinition::from_file(path)?.build(registry)?;
+        Ok(agents.entry().clone())
+    }
+}
+
+impl SwarmConfig {
+    // Loads the `settings` section of a config file
+    pub fn from_file(path: impl AsRef<Path>) -> Result<SwarmConfig> {
+        Ok(SwarmDefinition::from_file(path)?.settings.to_config())
+    }
+}
+
+// Checks that names are unique and every handoff names a known agent; returns the names
+fn check_names<'a>(
+    agents: impl Iterator<Item = (&'a str, &'a [String])> + Clone,
+) -> Result<HashSet<&'a str>> {
+    let mut names = HashSet::new();
+    for (name, _) in agents.clone() {
+        if !names.insert(name) {
+            return Err(config_error(format!("agent {} is defined twice", name)));
+        }
+    }
+    for (name, handoffs) in agents {
+        if let Some(target) = handoffs.iter().find(|t| !names.contains(t.as_str())) {
+            return Err(config_error(format!(
+                "agent {} hands off to unknown agent {}",
+                name, target
+            )));
+        }
+    }
+    Ok(names)
+}
+
+// Gives an agent of the graph its handoff tools
+fn with_late_handoffs(name: &str, graph: &Arc<Graph>) -> Agent {
+    let (agent, handoffs) = &graph[name];
+    handoffs.iter().fold(agent.clone(), |agent, target| {
+        agent.with_tool(late_handoff_tool(target, graph.clone()))
+    })
+}
+
+// Like `handoff_tool`, but wires the target's own handoffs when called
+fn late_handoff_tool(target: &str, graph: Arc<Graph>) -> ToolDefinition {
+    let description = format!("Transfer the conversation to {}.", target);
+    let name = target.to_string();
+    handoff_tool_with(target, &description, move || {
+        if graph.contains_key(&name) {
+            ToolOutput::Handoff(with_late_handoffs(&name, &graph))
+        } else {
+            ToolOutput::Error(format!("agent {} is not available", name))
+        }
+    })
+}
+
+fn parse_file<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let source = std::fs::read_to_string(path)?;
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let parsed = match extension {
+        "yaml" | "yml" => serde_yaml::from_str(&source).map_err(|e| e.to_string()),
+        "toml" => toml::from_str(&source).map_err(|e| e.to_string()),
+        _ => Err("expected a .yaml, .yml or .toml file".to_string()),
+    };
+    parsed.map_err(|e| config_error(format!("{}: {}", path.display(), e)))
+}
+
+fn config_error(message: String) -> SwarmError {
+    SwarmError::Config(message)
+}
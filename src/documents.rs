@@ -0,0 +1,382 @@
+use serde_json::Value;
+use std::path::Path;
+
+use crate::error::{Result, SwarmError};
+use crate::retrieval::Document;
+
+// Metadata keys set by the loaders and `chunk_documents`
+pub const SOURCE_KEY: &str = "source";
+pub const TITLE_KEY: &str = "title";
+pub const PAGE_KEY: &str = "page";
+pub const PARENT_KEY: &str = "document_id";
+pub const CHUNK_KEY: &str = "chunk";
+
+// Separators tried in order by `ChunkStrategy::Recursive`, coarsest first
+const RECURSIVE_SEPARATORS: [&str; 4] = ["\n\n", "\n", ". ", " "];
+
+// Loads a UTF-8 text file as one document whose id is the path
+pub fn load_text(path: impl AsRef<Path>) -> Result<Document> {
+    let path = path.as_ref();
+    let text = std::fs::read_to_string(path)?;
+    Ok(source_document(path, text))
+}
+
+// Loads a markdown file, keeping its markup. YAML front matter becomes metadata and
+// the first top-level heading becomes the `title`, unless the front matter sets one
+pub fn load_markdown(path: impl AsRef<Path>) -> Result<Document> {
+    let path = path.as_ref();
+    let content = std::fs::read_to_string(path)?;
+    let (front_matter, body) = split_front_matter(&content);
+    let mut document = source_document(path, body.trim_start().to_string());
+    if let Some(front_matter) = front_matter {
+        let metadata: Value = serde_yaml::from_str(front_matter)
+            .map_err(|e| SwarmError::Config(format!("{}: {}", path.display(), e)))?;
+        if let Value::Object(metadata) = metadata {
+            document.metadata.extend(metadata);
+        }
+    }
+    if !document.metadata.contains_key(TITLE_KEY) {
+        let title = document
+            .text
+            .lines()
+            .find_map(|line| line.strip_prefix("# "))
+            .map(|title| title.trim().to_string());
+        if let Some(title) = title {
+            document
+                .metadata
+                .insert(TITLE_KEY.to_string(), title.into());
+        }
+    }
+    Ok(document)
+}
+
+// Loads the text of a PDF as one document per non-empty page, with ids `<path>#page=<n>`
+#[cfg(feature = "pdf")]
+pub fn load_pdf(path: impl AsRef<Path>) -> Result<Vec<Document>> {
+    let path = path.as_ref();
+    let pages = pdf_extract::extract_text_by_pages(path)
+        .map_err(|e| SwarmError::Config(format!("{}: {}", path.display(), e)))?;
+    Ok(pages
+        .into_iter()
+        .enumerate()
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(index, text)| {
+            let mut document = source_document(path, text.trim().to_string());
+            document.id = format!("{}#page={}", document.id, index + 1);
+            document
+                .metadata
+                .insert(PAGE_KEY.to_string(), (index + 1).into());
+            document
+        })
+        .collect())
+}
+
+// Loads a file by extension: markdown (.md, .markdown), PDF (.pdf, with the `pdf`
+// feature), and anything else as plain text
+pub fn load_file(path: impl AsRef<Path>) -> Result<Vec<Document>> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    match extension.as_deref() {
+        Some("md" | "markdown") => Ok(vec![load_markdown(path)?]),
+        #[cfg(feature = "pdf")]
+        Some("pdf") => load_pdf(path),
+        #[cfg(not(feature = "pdf"))]
+        Some("pdf") => Err(SwarmError::Config(format!(
+            "{}: loading PDFs requires the `pdf` feature",
+            path.display()
+        ))),
+        _ => Ok(vec![load_text(path)?]),
+    }
+}
+
+// Loads every file with one of the extensions under a directory, recursively, in path order
+pub fn load_dir(dir: impl AsRef<Path>, extensions: &[&str]) -> Result<Vec<Document>> {
+    let mut paths = Vec::new();
+    collect_files(dir.as_ref(), extensions, &mut paths)?;
+    paths.sort();
+    let mut documents = Vec::new();
+    for path in paths {
+        documents.extend(load_file(path)?);
+    }
+    Ok(documents)
+}
+
+fn collect_files(
+    dir: &Path,
+    extensions: &[&str],
+    paths: &mut Vec<std::path::PathBuf>,
+) -> Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, extensions, paths)?;
+        } else if path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                extensions
+                    .iter()
+                    .any(|wanted| wanted.eq_ignore_ascii_case(extension))
+            })
+        {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn source_document(path: &Path, text: String) -> Document {
+    let source = path.display().to_string();
+    Document::new(source.clone(), text).with_metadata(SOURCE_KEY, source)
+}
+
+// Splits `---` delimited front matter off the start of a markdown file
+fn split_front_matter(content: &str) -> (Option<&str>, &str) {
+    let Some(rest) = content
+        .strip_prefix("---\n")
+        .or_else(|| content.strip_prefix("---\r\n"))
+    else {
+        return (None, content);
+    };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if line.trim_end() == "---" {
+            return (Some(&rest[..offset]), &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (None, content)
+}
+
+// How text is cut into chunks; sizes are in characters
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChunkStrategy {
+    // Windows of `size` characters, each repeating the last `overlap` of the previous one
+    FixedSize { size: usize, overlap: usize },
+    // Whole sentences packed into chunks of up to `max_chars`
+    Sentence { max_chars: usize },
+    // Splits on paragraphs, then lines, sentences and words until pieces fit, and packs
+    // neighbouring pieces into chunks of up to `max_chars`
+    Recursive { max_chars: usize },
+}
+
+impl Default for ChunkStrategy {
+    fn default() -> Self {
+        ChunkStrategy::Recursive { max_chars: 1000 }
+    }
+}
+
+impl ChunkStrategy {
+    // Cuts text into trimmed, non-empty chunks, in order
+    pub fn split(&self, text: &str) -> Vec<String> {
+        let chunks = match *self {
+            ChunkStrategy::FixedSize { size, overlap } => fixed_size(text, size, overlap),
+            ChunkStrategy::Sentence { max_chars } => {
+                let sentences = sentences(text);
+                let mut pieces = Vec::new();
+                for sentence in sentences {
+                    if char_len(sentence) > max_chars {
+                        pieces.extend(fixed_size(sentence, max_chars, 0));
+                    } else {
+                        pieces.push(sentence.to_string());
+                    }
+                }
+                pack(pieces, max_chars, " ")
+            }
+            ChunkStrategy::Recursive { max_chars } => {
+                recursive(text, max_chars, &RECURSIVE_SEPARATORS)
+            }
+        };
+        chunks
+            .into_iter()
+            .map(|chunk| chunk.trim().to_string())
+            .filter(|chunk| !chunk.is_empty())
+            .collect()
+    }
+}
+
+// Splits documents into chunks with ids `<id>#<n>`, keeping their metadata and adding
+// the parent id and chunk number; documents that fit in one chunk keep their id
+pub fn chunk_documents(documents: Vec<Document>, strategy: &ChunkStrategy) -> Vec<Document> {
+    let mut chunks = Vec::new();
+    for document in documents {
+        let pieces = strategy.split(&document.text);
+        if pieces.len() == 1 {
+            chunks.push(Document {
+                text: pieces.into_iter().next().unwrap_or_default(),
+                ..document
+            });
+            continue;
+        }
+        for (index, text) in pieces.into_iter().enumerate() {
+            let mut metadata = document.metadata.clone();
+            metadata.insert(PARENT_KEY.to_string(), document.id.clone().into());
+            metadata.insert(CHUNK_KEY.to_string(), index.into());
+            chunks.push(Document {
+                id: format!("{}#{}", document.id, index),
+                text,
+                metadata,
+            });
+        }
+    }
+    chunks
+}
+
+fn char_len(text: &str) -> usize {
+    text.chars().count()
+}
+
+fn fixed_size(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let size = size.max(1);
+    let step = size.saturating_sub(overlap).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let end = (start + size).min(chars.len());
+        chunks.push(chars[start..end].iter().collect());
+        if end == chars.len() {
+            break;
+        }
+        start += step;
+    }
+    chunks
+}
+
+// Sentences end at `.`, `!` or `?` followed by whitespace, and at blank lines
+fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((index, c)) = chars.next() {
+        let next = chars.peek().map(|(_, next)| *next);
+        let boundary = match c {
+            '.' | '!' | '?' => next.is_none_or(char::is_whitespace),
+            '\n' => next == Some('\n'),
+            _ => false,
+        };
+        if boundary {
+            let end = index + c.len_utf8();
+            sentences.push(text[start..end].trim());
+            start = end;
+        }
+    }
+    sentences.push(text[start..].trim());
+    sentences.retain(|sentence| !sentence.is_empty());
+    sentences
+}
+
+fn recursive(text: &str, max_chars: usize, separators: &[&str]) -> Vec<String> {
+    if char_len(text) <= max_chars {
+        return vec![text.to_string()];
+    }
+    let Some(position) = separators.iter().position(|sep| text.contains(sep)) else {
+        return fixed_size(text, max_chars, 0);
+    };
+    let rest = &separators[position + 1..];
+    // Separators stay on the piece before them, so packing restores the original text
+    let mut pieces = Vec::new();
+    for piece in text.split_inclusive(separators[position]) {
+        if char_len(piece) > max_chars {
+            pieces.extend(recursive(piece, max_chars, rest));
+        } else {
+            pieces.push(piece.to_string());
+        }
+    }
+    pack(pieces, max_chars, "")
+}
+
+// Joins consecutive pieces while the result stays within `max_chars`
+fn pack(pieces: Vec<String>, max_chars: usize, joiner: &str) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    for piece in pieces {
+        if !current.is_empty()
+            && char_len(&current) + char_len(joiner) + char_len(&piece) > max_chars
+        {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push_str(joiner);
+        }
+        current.push_str(&piece);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_size_windows_overlap() {
+        let strategy = ChunkStrategy::FixedSize {
+            size: 4,
+            overlap: 2,
+        };
+        assert_eq!(strategy.split("abcdefgh"), ["abcd", "cdef", "efgh"]);
+    }
+
+    #[test]
+    fn fixed_size_counts_characters_not_bytes() {
+        let strategy = ChunkStrategy::FixedSize {
+            size: 2,
+            overlap: 0,
+        };
+        assert_eq!(strategy.split("ééé"), ["éé", "é"]);
+    }
+
+    #[test]
+    fn sentences_are_packed_whole() {
+        let strategy = ChunkStrategy::Sentence { max_chars: 21 };
+        assert_eq!(
+            strategy.split("One two. Three four! Five six seven eight?"),
+            ["One two. Three four!", "Five six seven eight?"]
+        );
+    }
+
+    #[test]
+    fn long_sentences_are_cut_to_size() {
+        let strategy = ChunkStrategy::Sentence { max_chars: 5 };
+        assert_eq!(strategy.split("abcdefghij."), ["abcde", "fghij", "."]);
+    }
+
+    #[test]
+    fn recursive_prefers_paragraphs() {
+        let strategy = ChunkStrategy::Recursive { max_chars: 20 };
+        assert_eq!(
+            strategy.split("First paragraph.\n\nSecond paragraph."),
+            ["First paragraph.", "Second paragraph."]
+        );
+    }
+
+    #[test]
+    fn recursive_keeps_text_that_fits() {
+        let strategy = ChunkStrategy::Recursive { max_chars: 100 };
+        assert_eq!(strategy.split("  short text  "), ["short text"]);
+    }
+
+    #[test]
+    fn chunks_stay_within_the_limit() {
+        let text = "word ".repeat(200);
+        for strategy in [
+            ChunkStrategy::Recursive { max_chars: 30 },
+            ChunkStrategy::Sentence { max_chars: 30 },
+        ] {
+            let chunks = strategy.split(&text);
+            assert!(chunks.len() > 1);
+            assert!(chunks.iter().all(|chunk| char_len(chunk) <= 30));
+        }
+    }
+
+    #[test]
+    fn empty_text_has_no_chunks() {
+        assert!(ChunkStrategy::default().split("  \n ").is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use async_openai::{
+    config::OpenAIConfig,
+    types::{CreateEmbeddingRequest, EmbeddingInput},
+    Client,
+};
+use async_trait::async_trait;
+
+use crate::error::Result;
+
+pub const DEFAULT_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+// Turns texts into vectors whose cosine similarity reflects how related they are
+#[async_trait]
+pub trait Embedder: Send + Sync {
+    // Returns one vector per text, in input order
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>>;
+}
+
+// Embedder backed by the OpenAI embeddings endpoint
+#[derive(Clone)]
+pub struct Embeddings {
+    client: Client<OpenAIConfig>,
+    model: String,
+    dimensions: Option<u32>,
+}
+
+impl Embeddings {
+    pub fn new(client: Client<OpenAIConfig>) -> Self {
+        Embeddings {
+            client,
+            model: DEFAULT_EMBEDDING_MODEL.to_string(),
+            dimensions: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    // Shortens the vectors; only the text-embedding-3 models support it
+    pub fn with_dimensions(mut self, dimensions: u32) -> Self {
+        self.dimensions = Some(dimensions);
+        self
+    }
+
+    pub async fn embed_one(&self, text: &str) -> Result<Vec<f32>> {
+        let mut vectors = self.embed(vec![text.to_string()]).await?;
+        Ok(vectors.pop().unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl Embedder for Embeddings {
+    async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let request = CreateEmbeddingRequest {
+            model: self.model.clone(),
+            input: EmbeddingInput::StringArray(texts),
+            encoding_format: None,
+            user: None,
+            dimensions: self.dimensions,
+        };
+        let mut data = self.client.embeddings().create(request).await?.data;
+        data.sort_by_key(|embedding| embedding.index);
+        Ok(data
+            .into_iter()
+            .map(|embedding| embedding.embedding)
+            .collect())
+    }
+}
+
+impl std::fmt::Debug for Embeddings {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Embeddings")
+            .field("model", &self.model)
+            .field("dimensions", &self.dimensions)
+            .finish()
+    }
+}
+
+// Cosine of the angle between two vectors, in [-1, 1]; 0 when either is empty,
+// all zeros, or the lengths differ
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    dot / (norm_a * norm_b)
+}
+
+// Indices and scores of the `k` candidates most similar to the query, best first
+pub fn most_similar<'a>(
+    query: &[f32],
+    candidates: impl IntoIterator<Item = &'a [f32]>,
+    k: usize,
+) -> Vec<(usize, f32)> {
+    let mut scored: Vec<(usize, f32)> = candidates
+        .into_iter()
+        .map(|candidate| cosine_similarity(query, candidate))
+        .enumerate()
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.truncate(k);
+    scored
+}
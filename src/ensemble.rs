@@ -0,0 +1,255 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::{Result, SwarmError};
+use crate::messages::user_message;
+use crate::orchestration::final_text;
+use crate::provider::{ChatProvider, OpenAIProvider};
+use crate::swarm::Swarm;
+use crate::types::{Agent, Response, Usage};
+
+// Scores a candidate answer; the highest score wins
+pub type ScoreFn = Arc<dyn Fn(&str) -> f64 + Send + Sync>;
+
+// How candidate answers are reduced to a winner
+#[derive(Clone)]
+pub enum Aggregation {
+    // Most frequent answer, compared case- and whitespace-insensitively
+    MajorityVote,
+    Score(ScoreFn),
+    // A model picks the best answer according to the criteria
+    Judge { model: String, criteria: String },
+}
+
+impl std::fmt::Debug for Aggregation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Aggregation::MajorityVote => f.write_str("MajorityVote"),
+            Aggregation::Score(_) => f.write_str("Score"),
+            Aggregation::Judge { model, criteria } => f
+                .debug_struct("Judge")
+                .field("model", model)
+                .field("criteria", criteria)
+                .finish(),
+        }
+    }
+}
+
+// One agent's run in an ensemble
+#[derive(Debug, Clone)]
+pub struct Candidate {
+    pub agent: String,
+    pub answer: String,
+    pub response: Option<Response>,
+    pub error: Option<String>,
+    // Number of candidates that gave the same answer
+    pub votes: usize,
+    pub score: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnsembleResponse {
+    pub winner: Response,
+    pub winner_index: usize,
+    pub candidates: Vec<Candidate>,
+    pub usage: Usage,
+    pub cost_usd: f64,
+}
+
+// Runs the same prompt through several agents and aggregates their answers
+pub struct Ensemble<'a, P: ChatProvider = OpenAIProvider> {
+    swarm: &'a Swarm<P>,
+    agents: Vec<Agent>,
+    aggregation: Aggregation,
+}
+
+impl<'a, P: ChatProvider> Ensemble<'a, P> {
+    pub fn new(swarm: &'a Swarm<P>, agents: Vec<Agent>) -> Self {
+        Ensemble {
+            swarm,
+            agents,
+            aggregation: Aggregation::MajorityVote,
+        }
+    }
+
+    // Samples the same agent `n` times, e.g. for self-consistency voting
+    pub fn repeated(swarm: &'a Swarm<P>, agent: Agent, n: usize) -> Self {
+        Ensemble::new(swarm, vec![agent; n])
+    }
+
+    pub fn with_aggregation(mut self, aggregation: Aggregation) -> Self {
+        self.aggregation = aggregation;
+        self
+    }
+
+    // Runs every candidate and picks a winner; fails only if every candidate failed
+    pub async fn run(
+        &self,
+        messages: Vec<ChatCompletionRequestMessage>,
+    ) -> Result<EnsembleResponse> {
+        // 1. Run all candidates concurrently
+        let runs = self
+            .agents
+            .iter()
+            .map(|agent| (agent.clone(), messages.clone()))
+            .collect();
+        let results = self.swarm.run_parallel(runs).await;
+
+        // 2. Collect answers and per-candidate metadata
+        let mut usage = Usage::default();
+        let mut cost_usd = 0.0;
+        let mut first_error = None;
+        let mut candidates = Vec::with_capacity(results.len());
+        for (agent, result) in self.agents.iter().zip(results) {
+            let candidate = match result {
+                Ok(response) => {
+                    usage.add(&response.usage);
+                    cost_usd += response.cost_usd;
+                    Candidate {
+                        agent: agent.name.clone(),
+                        answer: final_text(&response),
+                        response: Some(response),
+                        error: None,
+                        votes: 0,
+                        score: None,
+                    }
+                }
+                Err(e) => {
+                    let candidate = Candidate {
+                        agent: agent.name.clone(),
+                        answer: String::new(),
+                        response: None,
+                        error: Some(e.to_string()),
+                        votes: 0,
+                        score: None,
+                    };
+                    first_error.get_or_insert(e);
+                    candidate
+                }
+            };
+            candidates.push(candidate);
+        }
+        count_votes(&mut candidates);
+
+        // 3. Aggregate into a winner
+        let winner_index = match &self.aggregation {
+            Aggregation::MajorityVote => best_by(&candidates, |candidate| candidate.votes as f64),
+            Aggregation::Score(score) => {
+                for candidate in candidates.iter_mut().filter(|c| c.response.is_some()) {
+                    candidate.score = Some(score(&candidate.answer));
+                }
+                best_by(&candidates, |candidate| {
+                    candidate.score.unwrap_or(f64::NEG_INFINITY)
+                })
+            }
+            Aggregation::Judge { model, criteria } => {
+                let (index, judge_usage, judge_cost_usd) =
+                    self.judge(&messages, &candidates, model, criteria).await?;
+                usage.add(&judge_usage);
+                cost_usd += judge_cost_usd;
+                index
+            }
+        };
+        let Some(winner) = winner_index.and_then(|index| candidates[index].response.clone()) else {
+            return Err(first_error
+                .unwrap_or_else(|| SwarmError::Config("ensemble has no agents".to_string())));
+        };
+
+        Ok(EnsembleResponse {
+            winner,
+            winner_index: winner_index.unwrap_or_default(),
+            candidates,
+            usage,
+            cost_usd,
+        })
+    }
+
+    // Asks a judge model for the index of the best successful candidate, along with what
+    // the judging cost
+    async fn judge(
+        &self,
+        messages: &[ChatCompletionRequestMessage],
+        candidates: &[Candidate],
+        model: &str,
+        criteria: &str,
+    ) -> Result<(Option<usize>, Usage, f64)> {
+        let successful: Vec<usize> = (0..candidates.len())
+            .filter(|&index| candidates[index].response.is_some())
+            .collect();
+        if successful.len() < 2 {
+            return Ok((successful.first().copied(), Usage::default(), 0.0));
+        }
+
+        // 1. Show the judge the request and the numbered answers
+        let mut prompt = String::from("Candidate answers:\n");
+        for (number, &index) in successful.iter().enumerate() {
+            prompt.push_str(&format!(
+                "\n[{}]\n{}\n",
+                number + 1,
+                candidates[index].answer
+            ));
+        }
+        prompt.push_str("\nReply with the number of the best answer only.");
+        let judge = Agent {
+            name: "Judge".to_string(),
+            model: model.to_string(),
+            instructions: format!(
+                "You judge answers to the conversation below. Criteria: {}",
+                criteria
+            )
+            .into(),
+            ..Default::default()
+        };
+        let mut history = messages.to_vec();
+        history.push(user_message(&prompt));
+        let response = self
+            .swarm
+            .run_with_options(judge, history, Default::default())
+            .await?;
+
+        // 2. Parse the first number in the verdict, falling back to the majority
+        let verdict = final_text(&response);
+        let choice = verdict
+            .split(|c: char| !c.is_ascii_digit())
+            .find_map(|number| number.parse::<usize>().ok())
+            .and_then(|number| successful.get(number.checked_sub(1)?).copied())
+            .or_else(|| best_by(candidates, |candidate| candidate.votes as f64));
+        Ok((choice, response.usage, response.cost_usd))
+    }
+}
+
+// Counts how many candidates share each normalized answer
+fn count_votes(candidates: &mut [Candidate]) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for candidate in candidates.iter().filter(|c| c.response.is_some()) {
+        *counts.entry(normalize(&candidate.answer)).or_default() += 1;
+    }
+    for candidate in candidates.iter_mut().filter(|c| c.response.is_some()) {
+        candidate.votes = counts[&normalize(&candidate.answer)];
+    }
+}
+
+// Returns the first successful candidate with the highest key
+fn best_by(candidates: &[Candidate], key: impl Fn(&Candidate) -> f64) -> Option<usize> {
+    let mut best: Option<(usize, f64)> = None;
+    for (index, candidate) in candidates.iter().enumerate() {
+        if candidate.response.is_none() {
+            continue;
+        }
+        let value = key(candidate);
+        if best.is_none_or(|(_, best_value)| value > best_value) {
+            best = Some((index, value));
+        }
+    }
+    best.map(|(index, _)| index)
+}
+
+fn normalize(answer: &str) -> String {
+    answer
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['.', '!'])
+        .to_lowercase()
+}
@@ -0,0 +1,62 @@
+use async_openai::error::OpenAIError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SwarmError {
+    #[error("api error: {0}")]
+    Api(#[from] OpenAIError),
+    #[error("provider error: {0}")]
+    Provider(String),
+    #[error("failed to parse arguments for tool {name}: {source}")]
+    ToolArguments {
+        name: String,
+        #[source]
+        source: serde_json::Error,
+    },
+    #[error("tool {0} not found")]
+    ToolNotFound(String),
+    #[error("tool {0} is already registered")]
+    DuplicateTool(String),
+    #[error("agent {agent} uses tool {tool}, which is not registered")]
+    UnboundTool { agent: String, tool: String },
+    #[error("completion returned no choices")]
+    EmptyChoices,
+    #[error("completion for agent {0} was stopped by the content filter")]
+    ContentFiltered(String),
+    #[error("guardrail {guardrail} tripped: {reason}")]
+    GuardrailTripped { guardrail: String, reason: String },
+    #[error("content flagged by moderation: {}", .0.join(", "))]
+    Moderated(Vec<String>),
+    #[error("tool {name} was called {repeats} times in a row with the same arguments")]
+    ToolLoop { name: String, repeats: usize },
+    #[error("session error: {0}")]
+    Session(String),
+    #[error("vector store error: {0}")]
+    VectorStore(String),
+    #[error("database error: {0}")]
+    Database(String),
+    #[error("search error: {0}")]
+    Search(String),
+    #[error("sandbox error: {0}")]
+    Sandbox(String),
+    #[error("max turns ({0}) exceeded")]
+    MaxTurnsExceeded(usize),
+    #[error("run context of type {0} is not available")]
+    MissingContext(&'static str),
+    #[error("router has no routes")]
+    NoRoutes,
+    #[error("workflow error: {0}")]
+    Workflow(String),
+    #[error("telemetry error: {0}")]
+    Telemetry(String),
+    #[error("failed to parse structured output: {0}")]
+    StructuredOutput(#[source] serde_json::Error),
+    #[error("template error: {0}")]
+    Template(#[from] minijinja::Error),
+    #[error("config error: {0}")]
+    Config(String),
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, SwarmError>;
@@ -0,0 +1,108 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::io::Write;
+
+use crate::error::Result;
+use crate::messages::system_message;
+use crate::run_context::RunContext;
+use crate::types::Response;
+
+// One conversation in OpenAI's chat fine-tuning format
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FinetuneExample {
+    pub messages: Vec<ChatCompletionRequestMessage>,
+    // Function definitions, as `{"type": "function", "function": {...}}`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+}
+
+impl FinetuneExample {
+    // Builds an example from a run's new messages, preceded by the instructions of the
+    // agent that finished the run; runs that received user input should prefer
+    // `from_conversation`, as responses do not include their input
+    pub fn from_response(response: &Response) -> Self {
+        Self::from_conversation(&[], response)
+    }
+
+    // Builds an example from the messages a run started with and its response. Without a
+    // system message in `input`, the final agent's instructions are prepended when they
+    // render without a typed context. Tools are the final agent's
+    pub fn from_conversation(input: &[ChatCompletionRequestMessage], response: &Response) -> Self {
+        let mut messages = Vec::with_capacity(input.len() + response.messages.len() + 1);
+        let has_system = input
+            .iter()
+            .any(|message| matches!(message, ChatCompletionRequestMessage::System(_)));
+        if let (false, Some(agent)) = (has_system, &response.agent) {
+            let instructions = agent
+                .instructions
+                .render(&response.context_variables, &RunContext::default())
+                .ok()
+                .filter(|text| !text.is_empty());
+            if let Some(instructions) = instructions {
+                messages.push(system_message(&instructions));
+            }
+        }
+        messages.extend_from_slice(input);
+        messages.extend(response.messages.iter().cloned());
+
+        let tools = response
+            .agent
+            .iter()
+            .flat_map(|agent| &agent.tools)
+            .map(|tool| {
+                let tool = tool.without_context_variables();
+                json!({
+                    "type": "function",
+                    "function": {
+                        "name": tool.name,
+                        "description": tool.description,
+                        "parameters": tool.parameters,
+                    },
+                })
+            })
+            .collect::<Vec<_>>();
+        let parallel_tool_calls = match (&response.agent, tools.is_empty()) {
+            (Some(agent), false) => Some(agent.parallel_tool_calls),
+            _ => None,
+        };
+        FinetuneExample {
+            messages,
+            tools,
+            parallel_tool_calls,
+        }
+    }
+
+    // Fine-tuning only learns from assistant messages, so examples need at least one
+    pub fn is_trainable(&self) -> bool {
+        self.messages
+            .iter()
+            .any(|message| matches!(message, ChatCompletionRequestMessage::Assistant(_)))
+    }
+}
+
+// Converts runs, each the messages it started with and its response, into fine-tuning
+// JSONL, one line per run with an assistant message; see
+// `FinetuneExample::from_conversation` for what each line holds
+pub fn to_finetune_jsonl(runs: &[(Vec<ChatCompletionRequestMessage>, Response)]) -> Result<String> {
+    let examples: Vec<FinetuneExample> = runs
+        .iter()
+        .map(|(input, response)| FinetuneExample::from_conversation(input, response))
+        .collect();
+    let mut out = Vec::new();
+    write_finetune_jsonl(&examples, &mut out)?;
+    Ok(String::from_utf8_lossy(&out).into_owned())
+}
+
+// Writes trainable examples as JSONL and returns how many were written
+pub fn write_finetune_jsonl(examples: &[FinetuneExample], mut writer: impl Write) -> Result<usize> {
+    let mut written = 0;
+    for example in examples.iter().filter(|example| example.is_trainable()) {
+        serde_json::to_writer(&mut writer, example).map_err(std::io::Error::from)?;
+        writer.write_all(b"\n")?;
+        written += 1;
+    }
+    Ok(written)
+}
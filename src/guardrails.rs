@@ -0,0 +1,72 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+// Verdict of an input guardrail
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GuardrailDecision {
+    Allow,
+    // Stop the run before the model is called, failing it with `SwarmError::GuardrailTripped`
+    Tripwire(String),
+}
+
+// Checks a run's input before the first model call, e.g. for off-topic or abusive requests
+#[async_trait]
+pub trait InputGuardrail: Send + Sync {
+    async fn check(&self, messages: &[ChatCompletionRequestMessage]) -> GuardrailDecision;
+
+    // Identifies the guardrail in the error when it trips
+    fn name(&self) -> &str {
+        "input_guardrail"
+    }
+}
+
+#[async_trait]
+impl<F> InputGuardrail for F
+where
+    F: Fn(&[ChatCompletionRequestMessage]) -> GuardrailDecision + Send + Sync,
+{
+    async fn check(&self, messages: &[ChatCompletionRequestMessage]) -> GuardrailDecision {
+        self(messages)
+    }
+}
+
+// Verdict of an output guardrail on the final assistant message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OutputDecision {
+    Pass,
+    // Withhold the message, replacing it with this text; later guardrails are skipped
+    Block(String),
+    // Replace the message with this text, e.g. with a disclaimer added
+    Rewrite(String),
+    // Keep the message and record a note
+    Annotate(String),
+}
+
+// Record of a guardrail that did not pass the final message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GuardrailOutcome {
+    pub guardrail: String,
+    pub decision: OutputDecision,
+}
+
+// Checks the final assistant message of a run, e.g. for schema conformance or banned content
+#[async_trait]
+pub trait OutputGuardrail: Send + Sync {
+    async fn check(&self, message: &str) -> OutputDecision;
+
+    // Identifies the guardrail in `Response::guardrail_outcomes`
+    fn name(&self) -> &str {
+        "output_guardrail"
+    }
+}
+
+#[async_trait]
+impl<F> OutputGuardrail for F
+where
+    F: Fn(&str) -> OutputDecision + Send + Sync,
+{
+    async fn check(&self, message: &str) -> OutputDecision {
+        self(message)
+    }
+}
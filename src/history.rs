@@ -0,0 +1,88 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use serde::{Deserialize, Serialize};
+
+use crate::types::Response;
+
+// A conversation that can be forked to explore alternate continuations. Forks are
+// independent copies, so continuing one never changes the branch it came from
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    messages: Vec<ChatCompletionRequestMessage>,
+    // Length of the prefix shared with the branch this one was forked from
+    #[serde(default)]
+    forked_at: Option<usize>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_messages(messages: Vec<ChatCompletionRequestMessage>) -> Self {
+        History {
+            messages,
+            forked_at: None,
+        }
+    }
+
+    pub fn messages(&self) -> &[ChatCompletionRequestMessage] {
+        &self.messages
+    }
+
+    pub fn into_messages(self) -> Vec<ChatCompletionRequestMessage> {
+        self.messages
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn push(&mut self, message: ChatCompletionRequestMessage) {
+        self.messages.push(message);
+    }
+
+    // Appends the messages a run added
+    pub fn record(&mut self, response: &Response) {
+        self.messages.extend(response.messages.iter().cloned());
+    }
+
+    // New branch keeping the messages before `index`; an index past the end keeps them all
+    pub fn fork(&self, index: usize) -> History {
+        let index = index.min(self.messages.len());
+        History {
+            messages: self.messages[..index].to_vec(),
+            forked_at: Some(index),
+        }
+    }
+
+    // New branch ending at the last user message, ready to retry the reply to it,
+    // e.g. with other instructions or another model; None without user messages
+    pub fn fork_last_turn(&self) -> Option<History> {
+        let last_user = self
+            .messages
+            .iter()
+            .rposition(|message| matches!(message, ChatCompletionRequestMessage::User(_)))?;
+        Some(self.fork(last_user + 1))
+    }
+
+    // Number of messages this branch shares with its origin, if it is a fork
+    pub fn forked_at(&self) -> Option<usize> {
+        self.forked_at
+    }
+}
+
+impl From<Vec<ChatCompletionRequestMessage>> for History {
+    fn from(messages: Vec<ChatCompletionRequestMessage>) -> Self {
+        Self::from_messages(messages)
+    }
+}
+
+impl AsRef<[ChatCompletionRequestMessage]> for History {
+    fn as_ref(&self) -> &[ChatCompletionRequestMessage] {
+        &self.messages
+    }
+}
@@ -0,0 +1,125 @@
+use async_openai::types::{ChatCompletionRequestMessage, ChatCompletionResponseMessage};
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::debug::{DebugEvent, DebugSink, StdoutSink};
+use crate::error::Result;
+use crate::types::{Agent, Response, ToolResult, Usage};
+
+// Observes the lifecycle of every run on a swarm, e.g. for logging or analytics.
+// All methods default to doing nothing, so implementors only override what they need.
+#[allow(unused_variables)]
+pub trait SwarmHooks: Send + Sync {
+    fn on_run_start(&self, agent: &Agent, messages: &[ChatCompletionRequestMessage]) {}
+
+    fn on_turn_start(&self, turn: usize, agent: &Agent) {}
+
+    fn on_llm_response(
+        &self,
+        agent: &Agent,
+        message: &ChatCompletionResponseMessage,
+        usage: Option<&Usage>,
+    ) {
+    }
+
+    // Called with the parsed arguments, after approval and before middleware
+    fn on_tool_start(&self, agent: &Agent, name: &str, args: &Value) {}
+
+    // Called for every requested tool, including unknown, denied and failed ones
+    fn on_tool_end(&self, agent: &Agent, name: &str, result: &ToolResult) {}
+
+    fn on_handoff(&self, from: &Agent, to: &Agent) {}
+
+    fn on_run_end(&self, result: &Result<Response>) {}
+}
+
+// Turns lifecycle events into `DebugEvent`s for a sink; installed implicitly for runs
+// with `debug` set, using the swarm's debug sink
+#[derive(Clone)]
+pub struct DebugHooks {
+    sink: Arc<dyn DebugSink>,
+}
+
+impl DebugHooks {
+    pub fn new(sink: Arc<dyn DebugSink>) -> Self {
+        DebugHooks { sink }
+    }
+
+    pub fn sink(&self) -> &Arc<dyn DebugSink> {
+        &self.sink
+    }
+}
+
+impl Default for DebugHooks {
+    fn default() -> Self {
+        DebugHooks::new(Arc::new(StdoutSink))
+    }
+}
+
+impl std::fmt::Debug for DebugHooks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DebugHooks").finish_non_exhaustive()
+    }
+}
+
+impl SwarmHooks for DebugHooks {
+    fn on_run_start(&self, agent: &Agent, messages: &[ChatCompletionRequestMessage]) {
+        self.sink.record(DebugEvent::RunStarted {
+            agent: agent.name.clone(),
+            messages: messages.len(),
+        });
+    }
+
+    fn on_turn_start(&self, turn: usize, agent: &Agent) {
+        self.sink.record(DebugEvent::TurnStarted {
+            turn,
+            agent: agent.name.clone(),
+        });
+    }
+
+    fn on_llm_response(
+        &self,
+        agent: &Agent,
+        message: &ChatCompletionResponseMessage,
+        _usage: Option<&Usage>,
+    ) {
+        self.sink.record(DebugEvent::CompletionReceived {
+            agent: agent.name.clone(),
+            message: message.clone(),
+        });
+    }
+
+    fn on_tool_start(&self, agent: &Agent, name: &str, args: &Value) {
+        self.sink.record(DebugEvent::ToolCallProcessing {
+            agent: agent.name.clone(),
+            name: name.to_string(),
+            arguments: args.clone(),
+        });
+    }
+
+    fn on_tool_end(&self, agent: &Agent, name: &str, result: &ToolResult) {
+        self.sink.record(DebugEvent::ToolResult {
+            agent: agent.name.clone(),
+            name: name.to_string(),
+            result: result.clone(),
+        });
+    }
+
+    fn on_handoff(&self, from: &Agent, to: &Agent) {
+        self.sink.record(DebugEvent::Handoff {
+            from: from.name.clone(),
+            to: to.name.clone(),
+        });
+    }
+
+    fn on_run_end(&self, result: &Result<Response>) {
+        self.sink.record(match result {
+            Ok(response) => DebugEvent::RunEnded {
+                messages: response.messages.len(),
+            },
+            Err(e) => DebugEvent::RunFailed {
+                error: e.to_string(),
+            },
+        });
+    }
+}
@@ -0,0 +1,351 @@
+use ::axum::{
+    body::Body,
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, State,
+    },
+    http::{header, StatusCode},
+    response::{IntoResponse, Response as HttpResponse},
+    routing::{get, post},
+    Json, Router,
+};
+use async_openai::types::ChatCompletionRequestMessage;
+use futures::{channel::mpsc, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use std::convert::Infallible;
+use std::sync::Arc;
+use tokio::task::JoinHandle;
+
+use crate::error::SwarmError;
+use crate::messages::user_message;
+use crate::provider::ChatProvider;
+use crate::swarm::Swarm;
+use crate::types::{Agent, ContextVariables, RunOptions, SwarmEvent};
+use crate::web::sse::{event_data, event_name, SseEncoder};
+
+// Body of `/chat` and `/chat/stream`; `message` is appended after `messages`
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ChatRequest {
+    #[serde(default)]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub messages: Vec<ChatCompletionRequestMessage>,
+    #[serde(default)]
+    pub context_variables: ContextVariables,
+    #[serde(default)]
+    pub max_turns: Option<usize>,
+}
+
+impl ChatRequest {
+    fn into_parts(
+        self,
+    ) -> (
+        Option<String>,
+        Vec<ChatCompletionRequestMessage>,
+        RunOptions,
+    ) {
+        let mut messages = self.messages;
+        messages.extend(self.message.as_deref().map(user_message));
+        let options = RunOptions {
+            context_variables: self.context_variables,
+            max_turns: self.max_turns,
+            ..Default::default()
+        };
+        (self.session_id, messages, options)
+    }
+}
+
+// HTTP API over a swarm and its entry agent:
+// - `POST /chat` runs to completion and returns the `Response` as JSON
+// - `POST /chat/stream` streams the run's events as Server-Sent Events
+// - `GET /chat/ws` chats over a WebSocket, see `serve_socket`
+// - `GET /sessions/{id}` returns a stored session
+// Requests with a `session_id` continue that session in the swarm's `SessionStore`
+pub struct SwarmApi<P: ChatProvider> {
+    swarm: Arc<Swarm<P>>,
+    agent: Agent,
+    encoder: SseEncoder,
+}
+
+impl<P: ChatProvider> Clone for SwarmApi<P> {
+    fn clone(&self) -> Self {
+        SwarmApi {
+            swarm: self.swarm.clone(),
+            agent: self.agent.clone(),
+            encoder: self.encoder.clone(),
+        }
+    }
+}
+
+impl<P: ChatProvider + 'static> SwarmApi<P> {
+    pub fn new(swarm: Arc<Swarm<P>>, agent: Agent) -> Self {
+        SwarmApi {
+            swarm,
+            agent,
+            encoder: SseEncoder::new(),
+        }
+    }
+
+    // Controls event naming and heartbeats on `/chat/stream`
+    pub fn with_encoder(mut self, encoder: SseEncoder) -> Self {
+        self.encoder = encoder;
+        self
+    }
+
+    pub fn router(self) -> Router {
+        Router::new()
+            .route("/chat", post(chat::<P>))
+            .route("/chat/stream", post(chat_stream::<P>))
+            .route("/chat/ws", get(chat_ws::<P>))
+            .route("/sessions/{id}", get(session::<P>))
+            .with_state(self)
+    }
+}
+
+// Builds the router with default settings, ready to `nest` or serve
+pub fn router<P: ChatProvider + 'static>(swarm: Arc<Swarm<P>>, agent: Agent) -> Router {
+    SwarmApi::new(swarm, agent).router()
+}
+
+async fn chat<P: ChatProvider + 'static>(
+    State(api): State<SwarmApi<P>>,
+    Json(request): Json<ChatRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let (session_id, messages, options) = request.into_parts();
+    let agent = api.agent.clone();
+    let response = match session_id {
+        Some(id) => api.swarm.run_session(&id, agent, messages, options).await?,
+        None => api.swarm.run_with_options(agent, messages, options).await?,
+    };
+    Ok(Json(response).into_response())
+}
+
+async fn chat_stream<P: ChatProvider + 'static>(
+    State(api): State<SwarmApi<P>>,
+    Json(request): Json<ChatRequest>,
+) -> Result<HttpResponse, ApiError> {
+    let run = start_run(&api, request, Vec::new()).await?;
+
+    // Encode events as SSE frames
+    let frames = api
+        .encoder
+        .encode_stream(run.events)
+        .map(Ok::<_, Infallible>);
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/event-stream"),
+            (header::CACHE_CONTROL, "no-cache"),
+        ],
+        Body::from_stream(frames),
+    )
+        .into_response())
+}
+
+// Frame sent by a WebSocket client
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientFrame {
+    // Starts a run; without a `session_id` it continues the connection's conversation
+    Message(ChatRequest),
+    // Aborts the run in progress
+    Cancel,
+}
+
+async fn chat_ws<P: ChatProvider + 'static>(
+    State(api): State<SwarmApi<P>>,
+    upgrade: WebSocketUpgrade,
+) -> HttpResponse {
+    upgrade.on_upgrade(move |socket| serve_socket(api, socket))
+}
+
+// Runs one conversation per connection. Events go out as `{"event": ..., "data": ...}`
+// text frames named as on `/chat/stream`; a cancelled run sends `cancelled` and
+// leaves the conversation as it was before the run
+async fn serve_socket<P: ChatProvider + 'static>(api: SwarmApi<P>, mut socket: WebSocket) {
+    let mut history = Vec::new();
+    let mut run: Option<ActiveRun> = None;
+    loop {
+        tokio::select! {
+            frame = socket.recv() => {
+                let text = match frame {
+                    Some(Ok(Message::Text(text))) => text,
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                    Some(Ok(_)) => continue,
+                };
+                let reply = match serde_json::from_str::<ClientFrame>(&text) {
+                    Ok(ClientFrame::Message(_)) if run.is_some() => {
+                        Some(error_frame("a run is already in progress"))
+                    }
+                    Ok(ClientFrame::Message(request)) => {
+                        let prior = match request.session_id {
+                            Some(_) => Vec::new(),
+                            None => history.clone(),
+                        };
+                        match start_run(&api, request, prior).await {
+                            Ok(started) => {
+                                run = Some(started);
+                                None
+                            }
+                            Err(e) => Some(error_frame(&e.to_string())),
+                        }
+                    }
+                    Ok(ClientFrame::Cancel) => run.take().map(|cancelled| {
+                        cancelled.task.abort();
+                        json!({ "event": "cancelled", "data": {} })
+                    }),
+                    Err(e) => Some(error_frame(&format!("invalid frame: {}", e))),
+                };
+                if let Some(reply) = reply {
+                    if socket.send(Message::Text(reply.to_string().into())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+            event = next_event(&mut run) => {
+                let Some(event) = event else {
+                    run = None;
+                    continue;
+                };
+                match (&run, &event) {
+                    (Some(active), SwarmEvent::RunCompleted(response)) => {
+                        history = active.messages.clone();
+                        history.extend(response.messages.iter().cloned());
+                        run = None;
+                    }
+                    (_, SwarmEvent::RunFailed(_)) => run = None,
+                    _ => {}
+                }
+                let frame = json!({ "event": event_name(&event), "data": event_data(&event) });
+                if socket.send(Message::Text(frame.to_string().into())).await.is_err() {
+                    break;
+                }
+            }
+        }
+    }
+    if let Some(run) = run {
+        run.task.abort();
+    }
+}
+
+fn error_frame(message: &str) -> serde_json::Value {
+    json!({ "event": "error", "data": { "error": message } })
+}
+
+// Waits for the next event of the active run, or forever when idle
+async fn next_event(run: &mut Option<ActiveRun>) -> Option<SwarmEvent> {
+    match run {
+        Some(run) => run.events.next().await,
+        None => std::future::pending().await,
+    }
+}
+
+// A run driven on its own task, so its events outlive the request borrowing the swarm
+struct ActiveRun {
+    events: mpsc::UnboundedReceiver<SwarmEvent>,
+    task: JoinHandle<()>,
+    // Input of the run, including prior history
+    messages: Vec<ChatCompletionRequestMessage>,
+}
+
+// Starts a streamed run after `prior`, continuing and saving the request's session if it names one
+async fn start_run<P: ChatProvider + 'static>(
+    api: &SwarmApi<P>,
+    request: ChatRequest,
+    prior: Vec<ChatCompletionRequestMessage>,
+) -> Result<ActiveRun, SwarmError> {
+    let (session_id, mut messages, mut options) = request.into_parts();
+    messages.splice(0..0, prior);
+
+    // 1. Prepend stored history, as run_session does
+    if let Some(id) = &session_id {
+        let session = store(&api.swarm)?.load(id).await?.unwrap_or_default();
+        messages.splice(0..0, session.messages);
+        let mut context_variables = session.context_variables;
+        context_variables.extend(options.context_variables);
+        options.context_variables = context_variables;
+    }
+
+    // 2. Forward events, saving the session once the run completes
+    let (tx, rx) = mpsc::unbounded();
+    let swarm = api.swarm.clone();
+    let agent = api.agent.clone();
+    let input = messages.clone();
+    let task = tokio::spawn(async move {
+        let mut history = messages.clone();
+        let mut events = Box::pin(swarm.run_stream(agent, messages, options));
+        while let Some(event) = events.next().await {
+            if let (Some(id), SwarmEvent::RunCompleted(response)) = (&session_id, &event) {
+                history.extend(response.messages.iter().cloned());
+                let saved = match store(&swarm) {
+                    Ok(store) => store.save(id, &history, &response.context_variables).await,
+                    Err(e) => Err(e),
+                };
+                if let Err(e) = saved {
+                    let _ = tx.unbounded_send(SwarmEvent::RunFailed(e));
+                    return;
+                }
+            }
+            if tx.unbounded_send(event).is_err() {
+                // The client went away
+                return;
+            }
+        }
+    });
+    Ok(ActiveRun {
+        events: rx,
+        task,
+        messages: input,
+    })
+}
+
+async fn session<P: ChatProvider + 'static>(
+    State(api): State<SwarmApi<P>>,
+    Path(id): Path<String>,
+) -> Result<HttpResponse, ApiError> {
+    match store(&api.swarm)?.load(&id).await? {
+        Some(session) => Ok(Json(session).into_response()),
+        None => Err(ApiError::NotFound(format!("session {} not found", id))),
+    }
+}
+
+fn store<P: ChatProvider>(
+    swarm: &Swarm<P>,
+) -> Result<&Arc<dyn crate::session::SessionStore>, SwarmError> {
+    swarm
+        .session_store()
+        .ok_or_else(|| SwarmError::Session("no session store configured".to_string()))
+}
+
+// Error body `{"error": "..."}`; rejected input maps to 4xx, everything else to 500
+#[derive(Debug)]
+pub enum ApiError {
+    NotFound(String),
+    Swarm(SwarmError),
+}
+
+impl From<SwarmError> for ApiError {
+    fn from(error: SwarmError) -> Self {
+        ApiError::Swarm(error)
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> HttpResponse {
+        let status = match &self {
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::Swarm(SwarmError::GuardrailTripped { .. } | SwarmError::Moderated(_)) => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            ApiError::Swarm(SwarmError::ContentFiltered(_)) => StatusCode::UNPROCESSABLE_ENTITY,
+            ApiError::Swarm(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+        let message = match self {
+            ApiError::NotFound(message) => message,
+            ApiError::Swarm(error) => error.to_string(),
+        };
+        (status, Json(json!({ "error": message }))).into_response()
+    }
+}
@@ -0,0 +1,304 @@
+// tonic::Status is large, but it is what every handler returns
+#![allow(clippy::result_large_err)]
+
+use async_openai::types::ChatCompletionRequestMessage;
+use futures::{channel::mpsc, stream::BoxStream, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tonic::{Request, Response as GrpcResponse, Status};
+
+use crate::error::SwarmError;
+use crate::messages::{message_role, message_text};
+use crate::provider::ChatProvider;
+use crate::swarm::Swarm;
+use crate::types::{Agent, ContextVariables, Response, RunOptions, RunStatus, SwarmEvent, Usage};
+
+// Types and stubs generated from proto/swarm.proto
+pub mod proto {
+    tonic::include_proto!("swarm.v1");
+}
+
+use proto::run_event::Event;
+use proto::swarm_service_server::{SwarmService as SwarmRpc, SwarmServiceServer};
+
+// gRPC front end for a swarm; requests pick one of the registered agents by name
+pub struct SwarmService<P: ChatProvider> {
+    swarm: Arc<Swarm<P>>,
+    agents: HashMap<String, Agent>,
+    default_agent: String,
+}
+
+impl<P: ChatProvider + 'static> SwarmService<P> {
+    // Serves the agent to requests that do not name one
+    pub fn new(swarm: Arc<Swarm<P>>, agent: Agent) -> Self {
+        let default_agent = agent.name.clone();
+        SwarmService {
+            swarm,
+            agents: HashMap::from([(agent.name.clone(), agent)]),
+            default_agent,
+        }
+    }
+
+    pub fn with_agent(mut self, agent: Agent) -> Self {
+        self.agents.insert(agent.name.clone(), agent);
+        self
+    }
+
+    // Wraps the service for `tonic::transport::Server::add_service`
+    pub fn into_server(self) -> SwarmServiceServer<Self> {
+        SwarmServiceServer::new(self)
+    }
+
+    fn agent(&self, name: &str) -> Result<Agent, Status> {
+        let name = if name.is_empty() {
+            &self.default_agent
+        } else {
+            name
+        };
+        self.agents
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("agent {} not found", name)))
+    }
+
+    fn parts(
+        &self,
+        request: proto::RunRequest,
+    ) -> Result<(Agent, Vec<ChatCompletionRequestMessage>, RunOptions), Status> {
+        let agent = self.agent(&request.agent)?;
+        let messages = request
+            .messages
+            .into_iter()
+            .map(from_proto_message)
+            .collect::<Result<_, _>>()?;
+        let options = RunOptions {
+            context_variables: parse_context(&request.context_variables)?,
+            max_turns: request.max_turns.map(|turns| turns as usize),
+            model_override: request.model_override,
+            ..Default::default()
+        };
+        Ok((agent, messages, options))
+    }
+}
+
+#[tonic::async_trait]
+impl<P: ChatProvider + 'static> SwarmRpc for SwarmService<P> {
+    async fn run(
+        &self,
+        request: Request<proto::RunRequest>,
+    ) -> Result<GrpcResponse<proto::RunResponse>, Status> {
+        let (agent, messages, options) = self.parts(request.into_inner())?;
+        let response = self
+            .swarm
+            .run_with_options(agent, messages, options)
+            .await
+            .map_err(to_status)?;
+        Ok(GrpcResponse::new(to_proto_response(&response)))
+    }
+
+    type RunStreamStream = BoxStream<'static, Result<proto::RunEvent, Status>>;
+
+    async fn run_stream(
+        &self,
+        request: Request<proto::RunRequest>,
+    ) -> Result<GrpcResponse<Self::RunStreamStream>, Status> {
+        let (agent, messages, options) = self.parts(request.into_inner())?;
+
+        // Drive the run on its own task so the stream does not borrow the service
+        let (tx, rx) = mpsc::unbounded();
+        let swarm = self.swarm.clone();
+        tokio::spawn(async move {
+            let mut events = Box::pin(swarm.run_stream(agent, messages, options));
+            while let Some(event) = events.next().await {
+                let event = proto::RunEvent {
+                    event: Some(to_proto_event(event)),
+                };
+                if tx.unbounded_send(Ok(event)).is_err() {
+                    // The client went away
+                    return;
+                }
+            }
+        });
+        Ok(GrpcResponse::new(rx.boxed()))
+    }
+
+    async fn resume_session(
+        &self,
+        request: Request<proto::ResumeSessionRequest>,
+    ) -> Result<GrpcResponse<proto::RunResponse>, Status> {
+        let request = request.into_inner();
+        let (agent, messages, options) = self.parts(request.run.unwrap_or_default())?;
+        let response = self
+            .swarm
+            .run_session(&request.session_id, agent, messages, options)
+            .await
+            .map_err(to_status)?;
+        Ok(GrpcResponse::new(to_proto_response(&response)))
+    }
+}
+
+fn to_status(error: SwarmError) -> Status {
+    match &error {
+        SwarmError::GuardrailTripped { .. }
+        | SwarmError::Moderated(_)
+        | SwarmError::ContentFiltered(_) => Status::invalid_argument(error.to_string()),
+        SwarmError::Session(_) => Status::failed_precondition(error.to_string()),
+        _ => Status::internal(error.to_string()),
+    }
+}
+
+fn parse_context(context_variables: &str) -> Result<ContextVariables, Status> {
+    if context_variables.trim().is_empty() {
+        return Ok(ContextVariables::new());
+    }
+    serde_json::from_str(context_variables)
+        .map_err(|e| Status::invalid_argument(format!("context_variables: {}", e)))
+}
+
+// Builds the message through its wire format, so roles are validated as the API does
+fn from_proto_message(message: proto::ChatMessage) -> Result<ChatCompletionRequestMessage, Status> {
+    let mut value = json!({ "role": message.role, "content": message.content });
+    if let Some(name) = message.name {
+        value["name"] = json!(name);
+    }
+    if let Some(tool_call_id) = message.tool_call_id {
+        value["tool_call_id"] = json!(tool_call_id);
+    }
+    if !message.tool_calls.is_empty() {
+        value["tool_calls"] = message
+            .tool_calls
+            .into_iter()
+            .map(|call| {
+                json!({
+                    "id": call.id,
+                    "type": "function",
+                    "function": { "name": call.name, "arguments": call.arguments },
+                })
+            })
+            .collect();
+    }
+    serde_json::from_value(value).map_err(|e| Status::invalid_argument(format!("message: {}", e)))
+}
+
+fn to_proto_message(message: &ChatCompletionRequestMessage) -> proto::ChatMessage {
+    let wire = serde_json::to_value(message).unwrap_or(Value::Null);
+    let field = |key: &str| wire.get(key).and_then(Value::as_str).map(str::to_string);
+    let tool_calls = match message {
+        ChatCompletionRequestMessage::Assistant(message) => message
+            .tool_calls
+            .iter()
+            .flatten()
+            .map(|call| proto::ToolCall {
+                id: call.id.clone(),
+                name: call.function.name.clone(),
+                arguments: call.function.arguments.clone(),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    proto::ChatMessage {
+        role: message_role(message).to_string(),
+        content: message_text(message),
+        name: field("name"),
+        tool_calls,
+        tool_call_id: field("tool_call_id"),
+    }
+}
+
+fn to_proto_usage(usage: &Usage) -> proto::Usage {
+    proto::Usage {
+        prompt_tokens: usage.prompt_tokens,
+        completion_tokens: usage.completion_tokens,
+        total_tokens: usage.total_tokens,
+    }
+}
+
+fn to_proto_response(response: &Response) -> proto::RunResponse {
+    let status = match response.status {
+        RunStatus::Completed => "completed",
+        RunStatus::BudgetExceeded => "budget_exceeded",
+        RunStatus::TurnLimitReached => "turn_limit_reached",
+        RunStatus::Stopped => "stopped",
+        RunStatus::ToolLoopDetected => "tool_loop_detected",
+    };
+    proto::RunResponse {
+        messages: response.messages.iter().map(to_proto_message).collect(),
+        agent: response
+            .agent
+            .as_ref()
+            .map(|agent| agent.name.clone())
+            .unwrap_or_default(),
+        context_variables: Value::Object(response.context_variables.clone()).to_string(),
+        usage: Some(to_proto_usage(&response.usage)),
+        cost_usd: response.cost_usd,
+        status: status.to_string(),
+    }
+}
+
+fn to_proto_event(event: SwarmEvent) -> Event {
+    match event {
+        SwarmEvent::TurnStarted { turn, agent } => Event::TurnStarted(proto::TurnStarted {
+            turn: turn as u32,
+            agent,
+        }),
+        SwarmEvent::TokenDelta { agent, content } => {
+            Event::TokenDelta(proto::TokenDelta { agent, content })
+        }
+        SwarmEvent::ToolCallRequested { agent, tool_call } => {
+            Event::ToolCallRequested(proto::ToolCallRequested {
+                agent,
+                tool_call: Some(proto::ToolCall {
+                    id: tool_call.id,
+                    name: tool_call.function.name,
+                    arguments: tool_call.function.arguments,
+                }),
+            })
+        }
+        SwarmEvent::ToolResult {
+            tool_call_id,
+            name,
+            content,
+        } => Event::ToolResult(proto::ToolResult {
+            tool_call_id,
+            name,
+            content,
+        }),
+        SwarmEvent::AgentHandoff { from, to } => {
+            Event::AgentHandoff(proto::AgentHandoff { from, to })
+        }
+        SwarmEvent::UsageUpdated { usage, cost_usd } => Event::UsageUpdated(proto::UsageUpdated {
+            usage: Some(to_proto_usage(&usage)),
+            cost_usd,
+        }),
+        SwarmEvent::PlanCreated { steps } => Event::PlanCreated(proto::PlanCreated {
+            steps: steps
+                .into_iter()
+                .map(|step| proto::PlanStep {
+                    id: step.id as u32,
+                    description: step.description,
+                })
+                .collect(),
+        }),
+        SwarmEvent::StepStarted { step, description } => Event::StepStarted(proto::StepStarted {
+            step: step as u32,
+            description,
+        }),
+        SwarmEvent::StepFinished {
+            step,
+            status,
+            output,
+        } => Event::StepFinished(proto::StepFinished {
+            step: step as u32,
+            status: serde_json::to_value(status)
+                .ok()
+                .and_then(|status| status.as_str().map(str::to_string))
+                .unwrap_or_default(),
+            output,
+        }),
+        SwarmEvent::RunCompleted(response) => Event::RunCompleted(to_proto_response(&response)),
+        SwarmEvent::RunFailed(error) => Event::RunFailed(proto::RunFailed {
+            error: error.to_string(),
+        }),
+    }
+}
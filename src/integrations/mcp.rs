@@ -0,0 +1,202 @@
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::error::Result;
+use crate::provider::ChatProvider;
+use crate::run_context::RunContext;
+use crate::swarm::Swarm;
+use crate::types::{Agent, ContextVariables, Tool};
+
+// Protocol revisions this server speaks, newest first
+const PROTOCOL_VERSIONS: [&str; 3] = ["2025-06-18", "2025-03-26", "2024-11-05"];
+
+// JSON-RPC error codes
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const PARSE_ERROR: i64 = -32700;
+
+// Model Context Protocol server exposing a swarm's registered tools, and optionally
+// whole agents, to MCP clients such as desktop assistants or other frameworks.
+// Calls go through the swarm's middleware, hooks and timeouts without context
+// variables; requests are answered one at a time
+pub struct McpServer<P: ChatProvider> {
+    swarm: Arc<Swarm<P>>,
+    name: String,
+    version: String,
+    // Registry tools to expose; all of them when None
+    allowed: Option<HashSet<String>>,
+    // Holds the agents exposed as tools in its own registry
+    agents: Agent,
+}
+
+impl<P: ChatProvider + 'static> McpServer<P> {
+    pub fn new(swarm: Arc<Swarm<P>>) -> Self {
+        McpServer {
+            swarm,
+            name: env!("CARGO_PKG_NAME").to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            allowed: None,
+            agents: Agent {
+                name: "mcp".to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    // Name and version reported to clients
+    pub fn with_server_info(mut self, name: &str, version: &str) -> Self {
+        self.name = name.to_string();
+        self.version = version.to_string();
+        self
+    }
+
+    // Exposes only these registry tools; agents added with `with_agent` are always exposed
+    pub fn with_allowed_tools(mut self, names: &[&str]) -> Self {
+        self.allowed = Some(names.iter().map(|name| name.to_string()).collect());
+        self
+    }
+
+    // Exposes an agent as a tool taking an `input` string and returning its final reply
+    pub fn with_agent(mut self, name: &str, description: &str, agent: &Agent) -> Self {
+        let tool = agent.as_tool(name, description, self.swarm.clone());
+        self.agents = self.agents.with_tool(tool);
+        self
+    }
+
+    // Serves newline-delimited JSON-RPC on stdin and stdout until stdin closes
+    pub async fn serve_stdio(&self) -> Result<()> {
+        let stdin = tokio::io::BufReader::new(tokio::io::stdin());
+        self.serve(stdin, tokio::io::stdout()).await
+    }
+
+    // Serves newline-delimited JSON-RPC on any transport
+    pub async fn serve(
+        &self,
+        reader: impl AsyncBufRead + Unpin,
+        mut writer: impl AsyncWrite + Unpin,
+    ) -> Result<()> {
+        let mut lines = reader.lines();
+        while let Some(line) = lines.next_line().await? {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let reply = match serde_json::from_str::<Value>(&line) {
+                Ok(message) => self.handle(message).await,
+                Err(e) => Some(error(Value::Null, PARSE_ERROR, &e.to_string())),
+            };
+            if let Some(reply) = reply {
+                let mut bytes = serde_json::to_vec(&reply).map_err(std::io::Error::from)?;
+                bytes.push(b'\n');
+                writer.write_all(&bytes).await?;
+                writer.flush().await?;
+            }
+        }
+        Ok(())
+    }
+
+    // Answers one JSON-RPC message; notifications get no reply
+    pub async fn handle(&self, message: Value) -> Option<Value> {
+        let id = message.get("id").cloned()?;
+        let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        Some(match method {
+            "initialize" => {
+                let requested = params.get("protocolVersion").and_then(Value::as_str);
+                let version = requested
+                    .filter(|version| PROTOCOL_VERSIONS.contains(version))
+                    .unwrap_or(PROTOCOL_VERSIONS[0]);
+                result(
+                    id,
+                    json!({
+                        "protocolVersion": version,
+                        "capabilities": { "tools": { "listChanged": false } },
+                        "serverInfo": { "name": self.name, "version": self.version },
+                    }),
+                )
+            }
+            "ping" => result(id, json!({})),
+            "tools/list" => {
+                let tools: Vec<Value> = self.tools().iter().map(describe).collect();
+                result(id, json!({ "tools": tools }))
+            }
+            "tools/call" => {
+                let Some(name) = params.get("name").and_then(Value::as_str) else {
+                    return Some(error(id, INVALID_PARAMS, "missing tool name"));
+                };
+                if !self.tools().iter().any(|tool| tool.name == name) {
+                    return Some(error(id, INVALID_PARAMS, &format!("unknown tool {}", name)));
+                }
+                let arguments = params
+                    .get("arguments")
+                    .cloned()
+                    .unwrap_or_else(|| json!({}));
+                result(id, self.call(name, &arguments).await)
+            }
+            _ => error(
+                id,
+                METHOD_NOT_FOUND,
+                &format!("method {} not supported", method),
+            ),
+        })
+    }
+
+    // Read on every request, so tools registered with the swarm later show up
+    fn tools(&self) -> Vec<Tool> {
+        let registry = self
+            .swarm
+            .registry()
+            .list_tools()
+            .into_iter()
+            .filter(|tool| {
+                self.allowed
+                    .as_ref()
+                    .is_none_or(|allowed| allowed.contains(&tool.name))
+            });
+        self.agents.tools.iter().cloned().chain(registry).collect()
+    }
+
+    async fn call(&self, name: &str, arguments: &Value) -> Value {
+        let outcome = self
+            .swarm
+            .call_tool(
+                &self.agents,
+                name,
+                &arguments.to_string(),
+                &ContextVariables::new(),
+                &RunContext::default(),
+                false,
+            )
+            .await;
+        // Tool errors reach the model as text starting with "Error: "
+        let (text, is_error) = match outcome {
+            Ok(result) => {
+                let is_error = result.value.starts_with("Error: ");
+                (result.value, is_error)
+            }
+            Err(e) => (e.to_string(), true),
+        };
+        json!({
+            "content": [{ "type": "text", "text": text }],
+            "isError": is_error,
+        })
+    }
+}
+
+fn describe(tool: &Tool) -> Value {
+    let tool = tool.without_context_variables();
+    json!({
+        "name": tool.name,
+        "description": tool.description,
+        "inputSchema": tool.parameters,
+    })
+}
+
+fn result(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn error(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
@@ -0,0 +1,5 @@
+#[cfg(feature = "axum")]
+pub mod axum;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod mcp;
@@ -1,2 +1,61 @@
+pub mod approval;
+pub mod audio;
+pub mod blackboard;
+pub mod builtin_tools;
+#[cfg(feature = "code-interpreter")]
+pub mod code_interpreter;
+pub mod config;
+pub mod context;
+pub mod cost;
+pub mod debug;
+pub mod definitions;
+pub mod documents;
+pub mod embeddings;
+pub mod ensemble;
+pub mod error;
+pub mod export;
+pub mod guardrails;
+pub mod history;
+pub mod hooks;
+pub mod integrations;
+pub mod memory;
+pub mod messages;
+pub mod metrics;
+pub mod middleware;
+pub mod moderation;
+#[cfg(feature = "openapi")]
+pub mod openapi;
+pub mod orchestration;
+#[cfg(feature = "otel")]
+pub mod otel;
+pub mod patterns;
+pub mod pii;
+pub mod provider;
+pub mod rate_limit;
+pub mod reload;
+pub mod repl;
+pub mod retrieval;
+pub mod retry;
+pub mod run_context;
+pub mod schema;
+pub mod search;
+pub mod session;
+#[cfg(any(feature = "postgres", feature = "sqlite"))]
+pub mod sql;
 pub mod swarm;
+mod telemetry;
+pub mod transcript;
 pub mod types;
+pub mod vector_store;
+pub mod web;
+pub mod workflow;
+
+#[cfg(feature = "macros")]
+pub use swarm_rs_macros::tool;
+
+// Paths used by code generated from `#[tool]`
+#[doc(hidden)]
+pub mod __private {
+    pub use schemars;
+    pub use serde_json;
+}
@@ -0,0 +1,574 @@
+use async_openai::types::{ChatCompletionRequestMessage, FinishReason};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::task::JoinHandle;
+
+use crate::embeddings::{most_similar, Embedder};
+use crate::error::{Result, SwarmError};
+use crate::messages::{message_role, message_text, system_message, user_message};
+use crate::provider::{ChatProvider, ChatRequest};
+use crate::schema::extract_json_object;
+use crate::types::ModelSettings;
+
+// Instructions of the default extraction agent
+const EXTRACTION_INSTRUCTIONS: &str = "You maintain long-term memory about a user. Given the \
+facts known so far and a new conversation, return the updated list of durable facts about \
+the user: preferences, personal details, goals and decisions worth remembering in future \
+conversations. Keep each fact short and self-contained, merge duplicates, drop facts the \
+conversation contradicts and ignore small talk. Reply with JSON only: {\"facts\": [\"...\"]}";
+
+// Messages of a run shown to the extraction agent, most recent last
+const EXTRACTION_WINDOW: usize = 20;
+
+// Reply budget of the extraction agent, which writes back the whole list of facts
+const EXTRACTION_MAX_TOKENS: u32 = 4096;
+
+// A remembered snippet, its embedding and the user it belongs to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryEntry {
+    pub text: String,
+    pub embedding: Vec<f32>,
+    // Only runs with this `user_id` recall the snippet; None for runs without one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+// Long-term memory shared by runs: every completed run stores its last user message
+// and final reply under its `user_id`, and each turn recalls that user's snippets most
+// similar to the latest user message into a system message after the instructions.
+// Runs without a `user_id` share one pool, so set it whenever runs serve several users
+pub struct SemanticMemory {
+    embedder: Arc<dyn Embedder>,
+    entries: Mutex<Vec<MemoryEntry>>,
+    top_k: usize,
+    min_score: f32,
+    max_entries: Option<usize>,
+    // Embedding of the last query, reused by the turns of a run
+    last_query: Mutex<Option<(String, Vec<f32>)>>,
+}
+
+impl SemanticMemory {
+    // Recalls up to 3 snippets scoring at least 0.3
+    pub fn new(embedder: impl Embedder + 'static) -> Self {
+        SemanticMemory {
+            embedder: Arc::new(embedder),
+            entries: Mutex::new(Vec::new()),
+            top_k: 3,
+            min_score: 0.3,
+            max_entries: None,
+            last_query: Mutex::new(None),
+        }
+    }
+
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    pub fn with_min_score(mut self, min_score: f32) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    // Forgets the oldest snippets beyond this many
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = Some(max_entries);
+        self
+    }
+
+    // Restores snippets saved with `entries`
+    pub fn with_entries(self, entries: Vec<MemoryEntry>) -> Self {
+        *self.entries.lock().unwrap() = entries;
+        self
+    }
+
+    pub fn entries(&self) -> Vec<MemoryEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    // Drops the snippets stored under the key
+    pub fn forget(&self, key: Option<&str>) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|entry| entry.key.as_deref() != key);
+    }
+
+    pub async fn remember(&self, key: Option<&str>, texts: Vec<String>) -> Result<()> {
+        let texts: Vec<String> = texts.into_iter().filter(|t| !t.trim().is_empty()).collect();
+        let embeddings = self.embedder.embed(texts.clone()).await?;
+        let mut entries = self.entries.lock().unwrap();
+        entries.extend(
+            texts
+                .into_iter()
+                .zip(embeddings)
+                .map(|(text, embedding)| MemoryEntry {
+                    text,
+                    embedding,
+                    key: key.map(str::to_string),
+                }),
+        );
+        if let Some(max_entries) = self.max_entries {
+            let excess = entries.len().saturating_sub(max_entries);
+            entries.drain(..excess);
+        }
+        Ok(())
+    }
+
+    // Snippets of the key most similar to the query with their scores, best first
+    pub async fn recall(&self, key: Option<&str>, query: &str) -> Result<Vec<(String, f32)>> {
+        if self.is_empty() || query.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let cached = match &*self.last_query.lock().unwrap() {
+            Some((text, embedding)) if text == query => Some(embedding.clone()),
+            _ => None,
+        };
+        let query_embedding = match cached {
+            Some(embedding) => embedding,
+            None => {
+                let embedding = self
+                    .embedder
+                    .embed(vec![query.to_string()])
+                    .await?
+                    .pop()
+                    .unwrap_or_default();
+                *self.last_query.lock().unwrap() = Some((query.to_string(), embedding.clone()));
+                embedding
+            }
+        };
+        let entries = self.entries.lock().unwrap();
+        let entries: Vec<&MemoryEntry> = entries
+            .iter()
+            .filter(|entry| entry.key.as_deref() == key)
+            .collect();
+        Ok(most_similar(
+            &query_embedding,
+            entries.iter().map(|entry| entry.embedding.as_slice()),
+            self.top_k,
+        )
+        .into_iter()
+        .filter(|(_, score)| *score >= self.min_score)
+        .map(|(index, score)| (entries[index].text.clone(), score))
+        .collect())
+    }
+
+    // Adds the key's recalled snippets for the request's latest user message
+    pub(crate) async fn inject(&self, key: Option<&str>, request: &mut ChatRequest) -> Result<()> {
+        let Some(query) = last_user_text(&request.messages) else {
+            return Ok(());
+        };
+        let recalled = self.recall(key, &query).await?;
+        if recalled.is_empty() {
+            return Ok(());
+        }
+        let mut text = "Relevant memories from earlier conversations:".to_string();
+        for (snippet, _) in recalled {
+            text.push_str("\n- ");
+            text.push_str(&snippet.replace('\n', "\n  "));
+        }
+        insert_system_context(request, &text);
+        Ok(())
+    }
+
+    // Stores the exchange of a completed run under the key
+    pub(crate) async fn record(
+        &self,
+        key: Option<&str>,
+        input: &[ChatCompletionRequestMessage],
+        output: &[ChatCompletionRequestMessage],
+    ) -> Result<()> {
+        let (Some(question), Some(answer)) = (last_user_text(input), last_assistant_text(output))
+        else {
+            return Ok(());
+        };
+        self.remember(
+            key,
+            vec![format!("User: {}\nAssistant: {}", question, answer)],
+        )
+        .await
+    }
+}
+
+impl std::fmt::Debug for SemanticMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SemanticMemory")
+            .field("entries", &self.len())
+            .field("top_k", &self.top_k)
+            .field("min_score", &self.min_score)
+            .field("max_entries", &self.max_entries)
+            .finish()
+    }
+}
+
+fn last_user_text(messages: &[ChatCompletionRequestMessage]) -> Option<String> {
+    messages
+        .iter()
+        .rev()
+        .find(|message| matches!(message, ChatCompletionRequestMessage::User(_)))
+        .map(message_text)
+        .filter(|text| !text.trim().is_empty())
+}
+
+fn last_assistant_text(messages: &[ChatCompletionRequestMessage]) -> Option<String> {
+    messages
+        .iter()
+        .rev()
+        .find(|message| matches!(message, ChatCompletionRequestMessage::Assistant(_)))
+        .map(message_text)
+        .filter(|text| !text.trim().is_empty())
+}
+
+// Adds a system message after the leading system messages, i.e. after the instructions
+fn insert_system_context(request: &mut ChatRequest, text: &str) {
+    let position = request
+        .messages
+        .iter()
+        .take_while(|message| matches!(message, ChatCompletionRequestMessage::System(_)))
+        .count();
+    request.messages.insert(position, system_message(text));
+}
+
+// Storage backend for facts remembered about a user or session
+#[async_trait]
+pub trait FactStore: Send + Sync {
+    // Returns the facts stored under the key, empty if there are none
+    async fn load(&self, key: &str) -> Result<Vec<String>>;
+
+    // Replaces the facts stored under the key
+    async fn save(&self, key: &str, facts: &[String]) -> Result<()>;
+}
+
+// Process-local fact store, lost on restart
+#[derive(Debug, Default)]
+pub struct InMemoryFactStore {
+    facts: tokio::sync::RwLock<HashMap<String, Vec<String>>>,
+}
+
+impl InMemoryFactStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl FactStore for InMemoryFactStore {
+    async fn load(&self, key: &str) -> Result<Vec<String>> {
+        Ok(self
+            .facts
+            .read()
+            .await
+            .get(key)
+            .cloned()
+            .unwrap_or_default())
+    }
+
+    async fn save(&self, key: &str, facts: &[String]) -> Result<()> {
+        self.facts
+            .write()
+            .await
+            .insert(key.to_string(), facts.to_vec());
+        Ok(())
+    }
+}
+
+// Long-term memory per user: runs with a `user_id` start with the facts stored under it
+// in a system message after the instructions, and once they complete, an extraction
+// agent updates those facts from the conversation in the background
+pub struct AgentMemory {
+    store: Arc<dyn FactStore>,
+    provider: Arc<dyn ChatProvider>,
+    model: String,
+    instructions: String,
+    max_facts: usize,
+    settings: ModelSettings,
+    // Serializes extractions per key, since each reads, merges and writes back its facts
+    extraction: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    pending: Mutex<Vec<JoinHandle<Result<()>>>>,
+}
+
+impl AgentMemory {
+    pub fn new(store: Arc<dyn FactStore>, provider: Arc<dyn ChatProvider>, model: &str) -> Self {
+        AgentMemory {
+            store,
+            provider,
+            model: model.to_string(),
+            instructions: EXTRACTION_INSTRUCTIONS.to_string(),
+            max_facts: 50,
+            settings: ModelSettings {
+                max_tokens: Some(EXTRACTION_MAX_TOKENS),
+                ..ModelSettings::default()
+            },
+            extraction: Mutex::new(HashMap::new()),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    // Replaces the extraction agent's instructions; replies must still be
+    // `{"facts": [...]}` JSON
+    pub fn with_instructions(mut self, instructions: &str) -> Self {
+        self.instructions = instructions.to_string();
+        self
+    }
+
+    // Keeps only the most recent facts beyond this many
+    pub fn with_max_facts(mut self, max_facts: usize) -> Self {
+        self.max_facts = max_facts;
+        self
+    }
+
+    // Settings of the extraction requests; the default allows 4096 reply tokens so the
+    // updated list fits
+    pub fn with_settings(mut self, settings: ModelSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    pub async fn facts(&self, key: &str) -> Result<Vec<String>> {
+        self.store.load(key).await
+    }
+
+    pub async fn forget(&self, key: &str) -> Result<()> {
+        self.store.save(key, &[]).await
+    }
+
+    // Updates the key's facts from a conversation and returns them. A reply that is cut
+    // off or has no `facts` array is an error and leaves the stored facts untouched
+    pub async fn extract(
+        &self,
+        key: &str,
+        messages: &[ChatCompletionRequestMessage],
+    ) -> Result<Vec<String>> {
+        let lock = self.key_lock(key);
+        let _guard = lock.lock().await;
+
+        // 1. Show the agent the known facts and the recent conversation
+        let known = self.store.load(key).await?;
+        let transcript = messages
+            .iter()
+            .filter(|m| {
+                matches!(
+                    m,
+                    ChatCompletionRequestMessage::User(_)
+                        | ChatCompletionRequestMessage::Assistant(_)
+                )
+            })
+            .map(|m| format!("{}: {}", message_role(m), message_text(m)))
+            .filter(|line| !line.ends_with(": "))
+            .collect::<Vec<_>>();
+        if transcript.is_empty() {
+            return Ok(known);
+        }
+        let transcript =
+            transcript[transcript.len().saturating_sub(EXTRACTION_WINDOW)..].join("\n");
+        let known_text = serde_json::to_string(&known).unwrap_or_default();
+        let request = ChatRequest {
+            model: self.model.clone(),
+            messages: vec![
+                system_message(&self.instructions),
+                user_message(&format!(
+                    "Known facts: {}\n\nConversation:\n{}",
+                    known_text, transcript
+                )),
+            ],
+            tools: Vec::new(),
+            parallel_tool_calls: false,
+            settings: self.settings.clone(),
+        };
+        let response = self.provider.chat(request).await?;
+        if response.finish_reason == Some(FinishReason::Length) {
+            return Err(SwarmError::StructuredOutput(serde::de::Error::custom(
+                "memory extraction reply was cut off; raise max_tokens with with_settings",
+            )));
+        }
+        let reply = response.message.content.unwrap_or_default();
+
+        // 2. Store the updated list
+        let mut facts = parse_facts(&reply)?;
+        let mut seen = std::collections::HashSet::new();
+        facts.retain(|fact| seen.insert(fact.clone()));
+        let excess = facts.len().saturating_sub(self.max_facts);
+        facts.drain(..excess);
+        self.store.save(key, &facts).await?;
+        Ok(facts)
+    }
+
+    // The lock serializing extractions of one key. Locks no extraction holds are dropped
+    // here so the map does not grow with every user seen
+    fn key_lock(&self, key: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut locks = self.extraction.lock().unwrap();
+        locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+        locks.entry(key.to_string()).or_default().clone()
+    }
+
+    // Waits for background extractions, returning the first error
+    pub async fn flush(&self) -> Result<()> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        let mut result = Ok(());
+        for handle in pending {
+            let outcome = handle.await.unwrap_or_else(|e| {
+                Err(SwarmError::Provider(format!(
+                    "memory extraction failed: {}",
+                    e
+                )))
+            });
+            if result.is_ok() {
+                result = outcome;
+            }
+        }
+        result
+    }
+
+    // Loads the key's facts as the system message added to every request of a run
+    pub(crate) async fn context_message(&self, key: &str) -> Result<Option<String>> {
+        let facts = self.store.load(key).await?;
+        if facts.is_empty() {
+            return Ok(None);
+        }
+        let mut text = "Known facts about the user:".to_string();
+        for fact in facts {
+            text.push_str("\n- ");
+            text.push_str(&fact);
+        }
+        Ok(Some(text))
+    }
+
+    pub(crate) fn inject(request: &mut ChatRequest, context: &str) {
+        insert_system_context(request, context);
+    }
+
+    // Starts an extraction without waiting for it; see `flush`
+    pub(crate) fn extract_in_background(
+        self: &Arc<Self>,
+        key: String,
+        messages: Vec<ChatCompletionRequestMessage>,
+    ) {
+        let memory = self.clone();
+        let handle = tokio::spawn(async move { memory.extract(&key, &messages).await.map(drop) });
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|handle| !handle.is_finished());
+        pending.push(handle);
+    }
+}
+
+impl std::fmt::Debug for AgentMemory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AgentMemory")
+            .field("model", &self.model)
+            .field("max_facts", &self.max_facts)
+            .finish_non_exhaustive()
+    }
+}
+
+// Reads `{"facts": [...]}`, tolerating text or code fences around the object
+fn parse_facts(reply: &str) -> Result<Vec<String>> {
+    let value: Value =
+        serde_json::from_str(extract_json_object(reply)).map_err(SwarmError::StructuredOutput)?;
+    let Some(facts) = value.get("facts").and_then(Value::as_array) else {
+        return Err(SwarmError::StructuredOutput(serde::de::Error::custom(
+            "memory extraction reply has no facts array",
+        )));
+    };
+    Ok(facts
+        .iter()
+        .filter_map(Value::as_str)
+        .map(|fact| fact.trim().to_string())
+        .filter(|fact| !fact.is_empty())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::MockProvider;
+
+    // Embeds every text as the same vector, so recall returns whatever may be recalled
+    struct SameEmbedder;
+
+    #[async_trait]
+    impl Embedder for SameEmbedder {
+        async fn embed(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+            Ok(texts.iter().map(|_| vec![1.0, 0.0]).collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn recalls_only_the_users_own_snippets() {
+        let memory = SemanticMemory::new(SameEmbedder);
+        memory
+            .remember(Some("alice"), vec!["alice's card ends 4242".to_string()])
+            .await
+            .unwrap();
+        memory
+            .remember(None, vec!["anonymous note".to_string()])
+            .await
+            .unwrap();
+        let recalled = memory.recall(Some("bob"), "card").await.unwrap();
+        assert!(recalled.is_empty());
+        let recalled = memory.recall(Some("alice"), "card").await.unwrap();
+        assert_eq!(recalled[0].0, "alice's card ends 4242");
+        let recalled = memory.recall(None, "card").await.unwrap();
+        assert_eq!(recalled.len(), 1);
+        assert_eq!(recalled[0].0, "anonymous note");
+
+        memory.forget(Some("alice"));
+        assert_eq!(memory.len(), 1);
+    }
+
+    // Memory over a store already holding one fact for `user`
+    async fn memory(provider: MockProvider) -> AgentMemory {
+        let store = Arc::new(InMemoryFactStore::new());
+        store
+            .save("user", &["likes tea".to_string()])
+            .await
+            .unwrap();
+        AgentMemory::new(store, Arc::new(provider), "gpt-4o-mini")
+    }
+
+    #[test]
+    fn parses_facts_in_code_fences() {
+        let reply = "```json\n{\"facts\": [\" likes tea \", \"\", 3]}\n```";
+        assert_eq!(parse_facts(reply).unwrap(), ["likes tea"]);
+        assert!(parse_facts(r#"{"facts": []}"#).unwrap().is_empty());
+    }
+
+    #[test]
+    fn rejects_replies_without_a_facts_array() {
+        assert!(parse_facts("{}").is_err());
+        assert!(parse_facts(r#"{"fact": ["likes tea"]}"#).is_err());
+        assert!(parse_facts(r#"{"facts": "likes tea"}"#).is_err());
+        assert!(parse_facts("no json").is_err());
+    }
+
+    #[tokio::test]
+    async fn malformed_replies_keep_the_stored_facts() {
+        let memory = memory(MockProvider::new().with_text(r#"{"fact": []}"#)).await;
+        let messages = [user_message("I moved to Oslo")];
+        assert!(memory.extract("user", &messages).await.is_err());
+        assert_eq!(memory.facts("user").await.unwrap(), ["likes tea"]);
+    }
+
+    #[tokio::test]
+    async fn cut_off_replies_keep_the_stored_facts() {
+        let provider = MockProvider::new().with_cut_off_text(r#"{"facts": ["lives in Oslo"]}"#);
+        let memory = memory(provider).await;
+        let messages = [user_message("I moved to Oslo")];
+        assert!(memory.extract("user", &messages).await.is_err());
+        assert_eq!(memory.facts("user").await.unwrap(), ["likes tea"]);
+    }
+}
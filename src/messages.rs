@@ -0,0 +1,256 @@
+use async_openai::types::{
+    ChatCompletionRequestAssistantMessage, ChatCompletionRequestAssistantMessageContent,
+    ChatCompletionRequestAssistantMessageContentPart, ChatCompletionRequestMessage,
+    ChatCompletionRequestMessageContentPartImage, ChatCompletionRequestMessageContentPartText,
+    ChatCompletionRequestSystemMessage, ChatCompletionRequestSystemMessageContent,
+    ChatCompletionRequestSystemMessageContentPart, ChatCompletionRequestToolMessageContent,
+    ChatCompletionRequestToolMessageContentPart, ChatCompletionRequestUserMessage,
+    ChatCompletionRequestUserMessageContent, ChatCompletionRequestUserMessageContentPart,
+    ImageDetail, ImageUrl,
+};
+use base64::Engine;
+use std::path::Path;
+
+use crate::error::{Result, SwarmError};
+
+// Builds a plain-text system message
+pub fn system_message(text: &str) -> ChatCompletionRequestMessage {
+    ChatCompletionRequestMessage::System(ChatCompletionRequestSystemMessage {
+        content: ChatCompletionRequestSystemMessageContent::Text(text.to_string()),
+        name: None,
+    })
+}
+
+// Builds a plain-text user message
+pub fn user_message(text: &str) -> ChatCompletionRequestMessage {
+    ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+        content: ChatCompletionRequestUserMessageContent::Text(text.to_string()),
+        name: None,
+    })
+}
+
+// Builds a user message from text and image parts, in order
+pub fn user_message_with_parts(
+    parts: Vec<ChatCompletionRequestUserMessageContentPart>,
+) -> ChatCompletionRequestMessage {
+    ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+        content: ChatCompletionRequestUserMessageContent::Array(parts),
+        name: None,
+    })
+}
+
+// Builds a user message asking about one or more images, given as URLs or data URLs
+pub fn user_message_with_images(
+    text: &str,
+    image_urls: impl IntoIterator<Item = impl Into<String>>,
+) -> ChatCompletionRequestMessage {
+    let mut parts = vec![text_part(text)];
+    parts.extend(
+        image_urls
+            .into_iter()
+            .map(|url| image_part(url, ImageDetail::Auto)),
+    );
+    user_message_with_parts(parts)
+}
+
+pub fn text_part(text: &str) -> ChatCompletionRequestUserMessageContentPart {
+    ChatCompletionRequestUserMessageContentPart::Text(ChatCompletionRequestMessageContentPartText {
+        text: text.to_string(),
+    })
+}
+
+// Image part; `Low` detail costs a fixed 85 tokens, `High` lets the model read fine print
+pub fn image_part(
+    url: impl Into<String>,
+    detail: ImageDetail,
+) -> ChatCompletionRequestUserMessageContentPart {
+    ChatCompletionRequestUserMessageContentPart::ImageUrl(
+        ChatCompletionRequestMessageContentPartImage {
+            image_url: ImageUrl {
+                url: url.into(),
+                detail: Some(detail),
+            },
+        },
+    )
+}
+
+// Encodes image bytes as a data URL, e.g. `image_data_url("image/png", &screenshot)`
+pub fn image_data_url(media_type: &str, bytes: &[u8]) -> String {
+    format!(
+        "data:{};base64,{}",
+        media_type,
+        base64::engine::general_purpose::STANDARD.encode(bytes)
+    )
+}
+
+// Reads a PNG, JPEG, GIF or WebP file into a data URL
+pub fn image_file_data_url(path: impl AsRef<Path>) -> Result<String> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let media_type = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        _ => {
+            return Err(SwarmError::Config(format!(
+                "{}: expected a png, jpeg, gif or webp image",
+                path.display()
+            )))
+        }
+    };
+    Ok(image_data_url(media_type, &std::fs::read(path)?))
+}
+
+// Returns the images attached to a message; only user messages carry them
+pub fn message_images(message: &ChatCompletionRequestMessage) -> Vec<&ImageUrl> {
+    match message {
+        ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+            content: ChatCompletionRequestUserMessageContent::Array(parts),
+            ..
+        }) => parts
+            .iter()
+            .filter_map(|part| match part {
+                ChatCompletionRequestUserMessageContentPart::ImageUrl(image) => {
+                    Some(&image.image_url)
+                }
+                ChatCompletionRequestUserMessageContentPart::Text(_) => None,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Builds a plain-text assistant message
+pub fn assistant_message(text: &str) -> ChatCompletionRequestMessage {
+    ChatCompletionRequestMessage::Assistant(ChatCompletionRequestAssistantMessage {
+        content: Some(ChatCompletionRequestAssistantMessageContent::Text(
+            text.to_string(),
+        )),
+        ..Default::default()
+    })
+}
+
+// Returns the role name of a message as sent on the wire
+pub fn message_role(message: &ChatCompletionRequestMessage) -> &'static str {
+    match message {
+        ChatCompletionRequestMessage::System(_) => "system",
+        ChatCompletionRequestMessage::User(_) => "user",
+        ChatCompletionRequestMessage::Assistant(_) => "assistant",
+        ChatCompletionRequestMessage::Tool(_) => "tool",
+        ChatCompletionRequestMessage::Function(_) => "function",
+    }
+}
+
+// Extracts the plain text of a message, joining multi-part content with newlines
+pub fn message_text(message: &ChatCompletionRequestMessage) -> String {
+    match message {
+        ChatCompletionRequestMessage::System(msg) => match &msg.content {
+            ChatCompletionRequestSystemMessageContent::Text(text) => text.clone(),
+            ChatCompletionRequestSystemMessageContent::Array(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    ChatCompletionRequestSystemMessageContentPart::Text(p) => p.text.as_str(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        },
+        ChatCompletionRequestMessage::User(msg) => match &msg.content {
+            ChatCompletionRequestUserMessageContent::Text(text) => text.clone(),
+            ChatCompletionRequestUserMessageContent::Array(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    ChatCompletionRequestUserMessageContentPart::Text(p) => Some(p.text.as_str()),
+                    ChatCompletionRequestUserMessageContentPart::ImageUrl(_) => None,
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        },
+        ChatCompletionRequestMessage::Assistant(msg) => match &msg.content {
+            Some(ChatCompletionRequestAssistantMessageContent::Text(text)) => text.clone(),
+            Some(ChatCompletionRequestAssistantMessageContent::Array(parts)) => parts
+                .iter()
+                .map(|part| match part {
+                    ChatCompletionRequestAssistantMessageContentPart::Text(p) => p.text.as_str(),
+                    ChatCompletionRequestAssistantMessageContentPart::Refusal(p) => {
+                        p.refusal.as_str()
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+            None => msg.refusal.clone().unwrap_or_default(),
+        },
+        ChatCompletionRequestMessage::Tool(msg) => match &msg.content {
+            ChatCompletionRequestToolMessageContent::Text(text) => text.clone(),
+            ChatCompletionRequestToolMessageContent::Array(parts) => parts
+                .iter()
+                .map(|part| match part {
+                    ChatCompletionRequestToolMessageContentPart::Text(p) => p.text.as_str(),
+                })
+                .collect::<Vec<_>>()
+                .join("\n"),
+        },
+        ChatCompletionRequestMessage::Function(msg) => msg.content.clone().unwrap_or_default(),
+    }
+}
+
+// Rewrites every text part of a message in place, leaving images and tool calls untouched
+pub fn map_message_text(
+    message: &mut ChatCompletionRequestMessage,
+    mut f: impl FnMut(&str) -> String,
+) {
+    match message {
+        ChatCompletionRequestMessage::System(msg) => match &mut msg.content {
+            ChatCompletionRequestSystemMessageContent::Text(text) => *text = f(text),
+            ChatCompletionRequestSystemMessageContent::Array(parts) => {
+                for part in parts {
+                    match part {
+                        ChatCompletionRequestSystemMessageContentPart::Text(p) => {
+                            p.text = f(&p.text)
+                        }
+                    }
+                }
+            }
+        },
+        ChatCompletionRequestMessage::User(msg) => match &mut msg.content {
+            ChatCompletionRequestUserMessageContent::Text(text) => *text = f(text),
+            ChatCompletionRequestUserMessageContent::Array(parts) => {
+                for part in parts {
+                    if let ChatCompletionRequestUserMessageContentPart::Text(p) = part {
+                        p.text = f(&p.text);
+                    }
+                }
+            }
+        },
+        ChatCompletionRequestMessage::Assistant(msg) => match &mut msg.content {
+            Some(ChatCompletionRequestAssistantMessageContent::Text(text)) => *text = f(text),
+            Some(ChatCompletionRequestAssistantMessageContent::Array(parts)) => {
+                for part in parts {
+                    if let ChatCompletionRequestAssistantMessageContentPart::Text(p) = part {
+                        p.text = f(&p.text);
+                    }
+                }
+            }
+            None => {}
+        },
+        ChatCompletionRequestMessage::Tool(msg) => match &mut msg.content {
+            ChatCompletionRequestToolMessageContent::Text(text) => *text = f(text),
+            ChatCompletionRequestToolMessageContent::Array(parts) => {
+                for part in parts {
+                    match part {
+                        ChatCompletionRequestToolMessageContentPart::Text(p) => p.text = f(&p.text),
+                    }
+                }
+            }
+        },
+        ChatCompletionRequestMessage::Function(msg) => {
+            if let Some(content) = &mut msg.content {
+                *content = f(content);
+            }
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::types::Usage;
+
+const TURN_BUCKETS: &[f64] = &[1.0, 2.0, 3.0, 5.0, 8.0, 13.0, 21.0, 34.0];
+const LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+// Operational counters and histograms for a swarm, rendered in the Prometheus text format.
+// Share one instance between the swarm (`Swarm::with_metrics`) and the scrape endpoint.
+#[derive(Debug, Default)]
+pub struct SwarmMetrics {
+    runs_started: AtomicU64,
+    runs_completed: AtomicU64,
+    runs_failed: AtomicU64,
+    prompt_tokens: AtomicU64,
+    completion_tokens: AtomicU64,
+    provider_errors: AtomicU64,
+    turns_per_run: Mutex<Option<Histogram>>,
+    tool_latency: Mutex<BTreeMap<String, Histogram>>,
+}
+
+impl SwarmMetrics {
+    pub fn new() -> Self {
+        SwarmMetrics::default()
+    }
+
+    pub fn runs_started(&self) -> u64 {
+        self.runs_started.load(Ordering::Relaxed)
+    }
+
+    pub fn runs_completed(&self) -> u64 {
+        self.runs_completed.load(Ordering::Relaxed)
+    }
+
+    pub fn runs_failed(&self) -> u64 {
+        self.runs_failed.load(Ordering::Relaxed)
+    }
+
+    pub fn provider_errors(&self) -> u64 {
+        self.provider_errors.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn run_started(&self) {
+        self.runs_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn run_finished(&self, succeeded: bool) {
+        let counter = if succeeded {
+            &self.runs_completed
+        } else {
+            &self.runs_failed
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(crate) fn observe_turns(&self, turns: usize) {
+        lock(&self.turns_per_run)
+            .get_or_insert_with(|| Histogram::new(TURN_BUCKETS))
+            .observe(turns as f64);
+    }
+
+    pub(crate) fn observe_tool(&self, name: &str, latency: Duration) {
+        lock(&self.tool_latency)
+            .entry(name.to_string())
+            .or_insert_with(|| Histogram::new(LATENCY_BUCKETS))
+            .observe(latency.as_secs_f64());
+    }
+
+    pub(crate) fn add_tokens(&self, usage: &Usage) {
+        self.prompt_tokens
+            .fetch_add(usage.prompt_tokens.into(), Ordering::Relaxed);
+        self.completion_tokens
+            .fetch_add(usage.completion_tokens.into(), Ordering::Relaxed);
+    }
+
+    pub(crate) fn provider_error(&self) {
+        self.provider_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    // Renders every metric in the Prometheus text exposition format
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        counter(
+            &mut out,
+            "swarm_runs_started_total",
+            "Runs started.",
+            &[("", self.runs_started())],
+        );
+        counter(
+            &mut out,
+            "swarm_runs_finished_total",
+            "Runs finished, by outcome.",
+            &[
+                ("status=\"completed\"", self.runs_completed()),
+                ("status=\"failed\"", self.runs_failed()),
+            ],
+        );
+        counter(
+            &mut out,
+            "swarm_tokens_total",
+            "Tokens consumed, by kind.",
+            &[
+                (
+                    "kind=\"prompt\"",
+                    self.prompt_tokens.load(Ordering::Relaxed),
+                ),
+                (
+                    "kind=\"completion\"",
+                    self.completion_tokens.load(Ordering::Relaxed),
+                ),
+            ],
+        );
+        counter(
+            &mut out,
+            "swarm_provider_errors_total",
+            "Failed provider calls, including retried ones.",
+            &[("", self.provider_errors())],
+        );
+
+        let _ = writeln!(
+            out,
+            "# HELP swarm_turns_per_run Turns taken by completed runs."
+        );
+        let _ = writeln!(out, "# TYPE swarm_turns_per_run histogram");
+        let turns = lock(&self.turns_per_run);
+        turns
+            .as_ref()
+            .unwrap_or(&Histogram::new(TURN_BUCKETS))
+            .render(&mut out, "swarm_turns_per_run", "");
+
+        let _ = writeln!(
+            out,
+            "# HELP swarm_tool_duration_seconds Tool execution time."
+        );
+        let _ = writeln!(out, "# TYPE swarm_tool_duration_seconds histogram");
+        for (tool, histogram) in lock(&self.tool_latency).iter() {
+            let labels = format!("tool=\"{}\"", escape(tool));
+            histogram.render(&mut out, "swarm_tool_duration_seconds", &labels);
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    bounds: &'static [f64],
+    // Non-cumulative count per bucket, plus one for +Inf
+    counts: Vec<u64>,
+    sum: f64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Histogram {
+            bounds,
+            counts: vec![0; bounds.len() + 1],
+            sum: 0.0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = self
+            .bounds
+            .iter()
+            .position(|&bound| value <= bound)
+            .unwrap_or(self.bounds.len());
+        self.counts[bucket] += 1;
+        self.sum += value;
+    }
+
+    fn render(&self, out: &mut String, name: &str, labels: &str) {
+        let separator = if labels.is_empty() { "" } else { "," };
+        let mut cumulative = 0;
+        for (bound, count) in self.bounds.iter().zip(&self.counts) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "{}_bucket{{{}{}le=\"{}\"}} {}",
+                name, labels, separator, bound, cumulative
+            );
+        }
+        let total: u64 = self.counts.iter().sum();
+        let _ = writeln!(
+            out,
+            "{}_bucket{{{}{}le=\"+Inf\"}} {}",
+            name, labels, separator, total
+        );
+        let labels = if labels.is_empty() {
+            String::new()
+        } else {
+            format!("{{{}}}", labels)
+        };
+        let _ = writeln!(out, "{}_sum{} {}", name, labels, self.sum);
+        let _ = writeln!(out, "{}_count{} {}", name, labels, total);
+    }
+}
+
+// Writes a counter family; an empty label set renders the bare metric name
+fn counter(out: &mut String, name: &str, help: &str, samples: &[(&str, u64)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} counter", name);
+    for (labels, value) in samples {
+        if labels.is_empty() {
+            let _ = writeln!(out, "{} {}", name, value);
+        } else {
+            let _ = writeln!(out, "{}{{{}}} {}", name, labels, value);
+        }
+    }
+}
+
+fn escape(label: &str) -> String {
+    label
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// Metrics stay usable even if a recording thread panicked
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
@@ -0,0 +1,27 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use crate::types::ToolOutput;
+
+// What a middleware decides before a tool runs
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum BeforeCall {
+    // Continue with the (possibly rewritten) arguments
+    Proceed(Value),
+    // Skip the tool and answer the model with this output
+    Respond(ToolOutput),
+}
+
+// Hooks wrapped around every tool call, e.g. for logging, caching or policy checks.
+// Middlewares run `before_call` in installation order and `after_call` in reverse.
+#[async_trait]
+pub trait ToolMiddleware: Send + Sync {
+    async fn before_call(&self, _name: &str, args: Value) -> BeforeCall {
+        BeforeCall::Proceed(args)
+    }
+
+    async fn after_call(&self, _name: &str, output: ToolOutput) -> ToolOutput {
+        output
+    }
+}
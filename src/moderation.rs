@@ -0,0 +1,146 @@
+use async_openai::{
+    config::OpenAIConfig,
+    types::{ChatCompletionRequestMessage, CreateModerationRequestArgs, ModerationInput},
+    Client,
+};
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::error::{Result, SwarmError};
+use crate::messages::{message_text, user_message};
+
+// What happens to content the moderation check flags
+#[derive(Debug, Clone)]
+pub enum ModerationAction {
+    // Fail the run with `SwarmError::Moderated`
+    Abort,
+    // Replace the flagged text and carry on
+    Sanitize(String),
+}
+
+// Guardrail that runs user input, and optionally the assistant's replies,
+// through the OpenAI Moderations endpoint
+pub struct Moderation {
+    client: Client<OpenAIConfig>,
+    thresholds: HashMap<String, f32>,
+    check_output: bool,
+    action: ModerationAction,
+}
+
+impl Moderation {
+    pub fn new(client: Client<OpenAIConfig>) -> Self {
+        Moderation {
+            client,
+            thresholds: HashMap::new(),
+            check_output: false,
+            action: ModerationAction::Abort,
+        }
+    }
+
+    // Flags a category, e.g. "violence" or "self-harm/intent", once its score reaches
+    // the threshold; categories without one follow the endpoint's own verdict
+    pub fn with_threshold(mut self, category: &str, score: f32) -> Self {
+        self.thresholds.insert(category.to_string(), score);
+        self
+    }
+
+    // Also checks every assistant reply before it joins the history
+    pub fn with_output_check(mut self, check_output: bool) -> Self {
+        self.check_output = check_output;
+        self
+    }
+
+    pub fn with_action(mut self, action: ModerationAction) -> Self {
+        self.action = action;
+        self
+    }
+
+    // Returns the flagged categories of each text, in input order
+    pub async fn check(&self, texts: Vec<String>) -> Result<Vec<Vec<String>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        let request = CreateModerationRequestArgs::default()
+            .input(ModerationInput::StringArray(texts))
+            .build()?;
+        let response = self.client.moderations().create(request).await?;
+        Ok(response
+            .results
+            .into_iter()
+            .map(|result| {
+                let flags = serde_json::to_value(&result.categories).unwrap_or(Value::Null);
+                let scores = serde_json::to_value(&result.category_scores).unwrap_or(Value::Null);
+                let Value::Object(scores) = scores else {
+                    return Vec::new();
+                };
+                scores
+                    .into_iter()
+                    .filter(|(category, score)| match self.thresholds.get(category) {
+                        Some(threshold) => score.as_f64().unwrap_or(0.0) >= *threshold as f64,
+                        None => flags.get(category) == Some(&Value::Bool(true)),
+                    })
+                    .map(|(category, _)| category)
+                    .collect()
+            })
+            .collect())
+    }
+
+    // Checks the user messages at the end of the history, i.e. the run's new input
+    pub(crate) async fn moderate_input(
+        &self,
+        history: &mut [ChatCompletionRequestMessage],
+    ) -> Result<()> {
+        // 1. Collect the trailing block of user messages
+        let start = history
+            .iter()
+            .rposition(|message| !matches!(message, ChatCompletionRequestMessage::User(_)))
+            .map_or(0, |index| index + 1);
+        let texts = history[start..].iter().map(message_text).collect();
+
+        // 2. Abort on, or blank out, flagged messages
+        let verdicts = self.check(texts).await?;
+        for (message, categories) in history[start..].iter_mut().zip(verdicts) {
+            if categories.is_empty() {
+                continue;
+            }
+            match &self.action {
+                ModerationAction::Abort => return Err(SwarmError::Moderated(categories)),
+                ModerationAction::Sanitize(replacement) => *message = user_message(replacement),
+            }
+        }
+        Ok(())
+    }
+
+    // Checks an assistant reply when output checks are enabled
+    pub(crate) async fn moderate_output(&self, content: &mut Option<String>) -> Result<()> {
+        let Some(text) = content.as_ref().filter(|_| self.check_output) else {
+            return Ok(());
+        };
+        let categories = self
+            .check(vec![text.clone()])
+            .await?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        if categories.is_empty() {
+            return Ok(());
+        }
+        match &self.action {
+            ModerationAction::Abort => Err(SwarmError::Moderated(categories)),
+            ModerationAction::Sanitize(replacement) => {
+                *content = Some(replacement.clone());
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::fmt::Debug for Moderation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Moderation")
+            .field("thresholds", &self.thresholds)
+            .field("check_output", &self.check_output)
+            .field("action", &self.action)
+            .finish()
+    }
+}
@@ -0,0 +1,435 @@
+use futures::FutureExt;
+use reqwest::Method;
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::error::{Result, SwarmError};
+use crate::types::{Tool, ToolDefinition, ToolFunction, ToolOutput, ToolRegistry};
+
+const METHODS: [&str; 8] = [
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+// `$ref` chains longer than this are treated as cycles and left unresolved
+const MAX_REF_DEPTH: usize = 32;
+
+// Argument holding the JSON request body of operations that take one
+const BODY_ARG: &str = "body";
+
+// Credentials attached to every request made by the generated tools
+#[derive(Clone, Default)]
+pub enum OpenApiAuth {
+    #[default]
+    None,
+    Bearer(String),
+    Basic {
+        username: String,
+        password: String,
+    },
+    // A key sent in the named header
+    ApiKeyHeader {
+        name: String,
+        value: String,
+    },
+    // A key sent as the named query parameter
+    ApiKeyQuery {
+        name: String,
+        value: String,
+    },
+}
+
+impl std::fmt::Debug for OpenApiAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print secrets
+        let kind = match self {
+            OpenApiAuth::None => "None",
+            OpenApiAuth::Bearer(_) => "Bearer",
+            OpenApiAuth::Basic { .. } => "Basic",
+            OpenApiAuth::ApiKeyHeader { .. } => "ApiKeyHeader",
+            OpenApiAuth::ApiKeyQuery { .. } => "ApiKeyQuery",
+        };
+        f.write_str(kind)
+    }
+}
+
+// A parsed OpenAPI 3 document, optionally narrowed to some operations
+#[derive(Debug, Clone)]
+pub struct OpenApiSpec {
+    document: Value,
+    allowed: Option<HashSet<String>>,
+}
+
+impl OpenApiSpec {
+    pub fn from_value(document: Value) -> Result<Self> {
+        if !document.get("paths").is_some_and(Value::is_object) {
+            return Err(SwarmError::Config(
+                "OpenAPI document has no paths".to_string(),
+            ));
+        }
+        Ok(OpenApiSpec {
+            document,
+            allowed: None,
+        })
+    }
+
+    // Parses a JSON or YAML document
+    pub fn parse(text: &str) -> Result<Self> {
+        let document = match serde_json::from_str(text) {
+            Ok(document) => document,
+            Err(_) => serde_yaml::from_str(text)
+                .map_err(|e| SwarmError::Config(format!("invalid OpenAPI document: {}", e)))?,
+        };
+        Self::from_value(document)
+    }
+
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    // Keeps only the operations with these operation ids (or generated tool names);
+    // fails on names the document does not define
+    pub fn allow(mut self, operations: &[&str]) -> Result<Self> {
+        let known: HashSet<String> = self.operations().into_iter().map(|op| op.name).collect();
+        if let Some(unknown) = operations.iter().find(|name| !known.contains(**name)) {
+            return Err(SwarmError::Config(format!(
+                "OpenAPI document has no operation {}",
+                unknown
+            )));
+        }
+        self.allowed = Some(operations.iter().map(|name| name.to_string()).collect());
+        Ok(self)
+    }
+
+    fn operations(&self) -> Vec<Operation> {
+        let mut operations = Vec::new();
+        let Some(paths) = self.document.get("paths").and_then(Value::as_object) else {
+            return operations;
+        };
+        for (path, item) in paths {
+            let item = self.resolve(item);
+            let shared = item.get("parameters").cloned().unwrap_or(json!([]));
+            for method in METHODS {
+                let Some(operation) = item.get(method) else {
+                    continue;
+                };
+                let name = operation
+                    .get("operationId")
+                    .and_then(Value::as_str)
+                    .map(tool_name)
+                    .unwrap_or_else(|| tool_name(&format!("{}_{}", method, path)));
+                let description = ["summary", "description"]
+                    .iter()
+                    .filter_map(|key| operation.get(*key).and_then(Value::as_str))
+                    .collect::<Vec<_>>()
+                    .join("\n\n");
+
+                // Operation parameters override path-level ones with the same name and location
+                let mut parameters: Vec<Value> = Vec::new();
+                let own = operation.get("parameters").cloned().unwrap_or(json!([]));
+                for parameter in own
+                    .as_array()
+                    .into_iter()
+                    .chain(shared.as_array())
+                    .flatten()
+                {
+                    let parameter = self.resolve(parameter);
+                    let duplicate = parameters.iter().any(|existing| {
+                        existing["name"] == parameter["name"] && existing["in"] == parameter["in"]
+                    });
+                    if !duplicate {
+                        parameters.push(parameter);
+                    }
+                }
+                let body = operation
+                    .get("requestBody")
+                    .map(|body| self.resolve(body))
+                    .and_then(|body| {
+                        let schema = body.pointer("/content/application~1json/schema")?;
+                        let required = body.get("required").and_then(Value::as_bool);
+                        Some((self.inline(schema), required.unwrap_or(false)))
+                    });
+                operations.push(Operation {
+                    name,
+                    description,
+                    method: method.to_uppercase(),
+                    path: path.clone(),
+                    parameters,
+                    body,
+                });
+            }
+        }
+        operations
+    }
+
+    // Follows a top-level `$ref` to its target
+    fn resolve(&self, value: &Value) -> Value {
+        let mut value = value.clone();
+        for _ in 0..MAX_REF_DEPTH {
+            let Some(target) = value
+                .get("$ref")
+                .and_then(Value::as_str)
+                .and_then(|reference| reference.strip_prefix('#'))
+                .and_then(|pointer| self.document.pointer(pointer))
+            else {
+                break;
+            };
+            value = target.clone();
+        }
+        value
+    }
+
+    // Replaces every local `$ref` inside a schema with its target, so tools are
+    // self-contained. Recursive references and unresolvable ones accept any value
+    fn inline(&self, schema: &Value) -> Value {
+        self.inline_within(schema, &mut Vec::new())
+    }
+
+    fn inline_within(&self, schema: &Value, expanding: &mut Vec<String>) -> Value {
+        match schema {
+            Value::Object(object) if object.contains_key("$ref") => {
+                let reference = object["$ref"].as_str().unwrap_or_default().to_string();
+                let resolved = self.resolve(schema);
+                if resolved.get("$ref").is_some() || expanding.contains(&reference) {
+                    return json!({});
+                }
+                expanding.push(reference);
+                let inlined = self.inline_within(&resolved, expanding);
+                expanding.pop();
+                inlined
+            }
+            Value::Object(object) => Value::Object(
+                object
+                    .iter()
+                    .map(|(key, value)| (key.clone(), self.inline_within(value, expanding)))
+                    .collect(),
+            ),
+            Value::Array(items) => Value::Array(
+                items
+                    .iter()
+                    .map(|item| self.inline_within(item, expanding))
+                    .collect(),
+            ),
+            other => other.clone(),
+        }
+    }
+}
+
+// One HTTP operation of the document
+#[derive(Debug, Clone)]
+struct Operation {
+    name: String,
+    description: String,
+    method: String,
+    path: String,
+    parameters: Vec<Value>,
+    // Inlined JSON body schema and whether a body is required
+    body: Option<(Value, bool)>,
+}
+
+impl Operation {
+    fn schema(&self, spec: &OpenApiSpec) -> Value {
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+        for parameter in &self.parameters {
+            let Some(name) = parameter.get("name").and_then(Value::as_str) else {
+                continue;
+            };
+            if parameter.get("in").and_then(Value::as_str) == Some("cookie") {
+                continue;
+            }
+            let mut schema = parameter
+                .get("schema")
+                .map(|schema| spec.inline(schema))
+                .unwrap_or_else(|| json!({ "type": "string" }));
+            if let (Some(description), Value::Object(schema)) = (
+                parameter.get("description").and_then(Value::as_str),
+                &mut schema,
+            ) {
+                schema
+                    .entry("description")
+                    .or_insert_with(|| description.into());
+            }
+            properties.insert(name.to_string(), schema);
+            let is_path = parameter.get("in").and_then(Value::as_str) == Some("path");
+            if is_path || parameter.get("required").and_then(Value::as_bool) == Some(true) {
+                required.push(Value::String(name.to_string()));
+            }
+        }
+        if let Some((schema, body_required)) = &self.body {
+            properties.insert(BODY_ARG.to_string(), schema.clone());
+            if *body_required {
+                required.push(Value::String(BODY_ARG.to_string()));
+            }
+        }
+        json!({ "type": "object", "properties": properties, "required": required })
+    }
+}
+
+impl ToolRegistry {
+    // Turns each operation of the spec (see `OpenApiSpec::allow`) into a tool that calls
+    // `base_url` over HTTP. Parameters become arguments by name and a JSON request body
+    // becomes the `body` argument; JSON responses are returned as JSON, others as text,
+    // and error statuses as tool errors. Fails if two operations map to the same tool name
+    pub fn from_openapi(spec: &OpenApiSpec, base_url: &str, auth: OpenApiAuth) -> Result<Self> {
+        let http = reqwest::Client::new();
+        let base_url = Arc::new(base_url.trim_end_matches('/').to_string());
+        let auth = Arc::new(auth);
+        let registry = ToolRegistry::new();
+        for operation in spec.operations() {
+            if spec
+                .allowed
+                .as_ref()
+                .is_some_and(|allowed| !allowed.contains(&operation.name))
+            {
+                continue;
+            }
+            let tool = Tool::new(
+                &operation.name,
+                &operation.description,
+                operation.schema(spec),
+            );
+            let operation = Arc::new(operation);
+            let (http, base_url, auth) = (http.clone(), base_url.clone(), auth.clone());
+            let function = move |args: Value| {
+                let (http, base_url, auth, operation) = (
+                    http.clone(),
+                    base_url.clone(),
+                    auth.clone(),
+                    operation.clone(),
+                );
+                async move { call(&http, &base_url, &auth, &operation, args).await }.boxed()
+            };
+            registry.try_register(ToolDefinition::new(
+                tool,
+                ToolFunction::Async(Arc::new(function)),
+            ))?;
+        }
+        Ok(registry)
+    }
+}
+
+async fn call(
+    http: &reqwest::Client,
+    base_url: &str,
+    auth: &OpenApiAuth,
+    operation: &Operation,
+    args: Value,
+) -> ToolOutput {
+    // 1. Place each argument where the spec says it goes
+    let mut path = operation.path.clone();
+    let mut query: Vec<(String, String)> = Vec::new();
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for parameter in &operation.parameters {
+        let (Some(name), Some(location)) = (
+            parameter.get("name").and_then(Value::as_str),
+            parameter.get("in").and_then(Value::as_str),
+        ) else {
+            continue;
+        };
+        let Some(value) = args.get(name).filter(|value| !value.is_null()) else {
+            if location == "path" {
+                return ToolOutput::Error(format!("missing path parameter {}", name));
+            }
+            continue;
+        };
+        match location {
+            "path" => {
+                // Dot segments would be resolved by the server and reach another endpoint
+                let segment = scalar(value);
+                if segment == "." || segment == ".." {
+                    return ToolOutput::Error(format!("invalid path parameter {}", name));
+                }
+                path = path.replace(&format!("{{{}}}", name), &encode_segment(&segment))
+            }
+            "query" => match value {
+                Value::Array(items) => {
+                    query.extend(items.iter().map(|item| (name.to_string(), scalar(item))))
+                }
+                _ => query.push((name.to_string(), scalar(value))),
+            },
+            "header" => headers.push((name.to_string(), scalar(value))),
+            _ => {}
+        }
+    }
+
+    // 2. Build and send the request
+    let Ok(method) = Method::from_bytes(operation.method.as_bytes()) else {
+        return ToolOutput::Error(format!("unsupported method {}", operation.method));
+    };
+    let mut request = http.request(method, format!("{}{}", base_url, path));
+    match auth {
+        OpenApiAuth::None => {}
+        OpenApiAuth::Bearer(token) => request = request.bearer_auth(token),
+        OpenApiAuth::Basic { username, password } => {
+            request = request.basic_auth(username, Some(password))
+        }
+        OpenApiAuth::ApiKeyHeader { name, value } => request = request.header(name, value),
+        OpenApiAuth::ApiKeyQuery { name, value } => query.push((name.clone(), value.clone())),
+    }
+    request = request.query(&query);
+    for (name, value) in headers {
+        request = request.header(name, value);
+    }
+    if let Some(body) = args.get(BODY_ARG).filter(|_| operation.body.is_some()) {
+        request = request.json(body);
+    }
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return ToolOutput::Error(format!("request failed: {}", e)),
+    };
+
+    // 3. Report the response body
+    let status = response.status();
+    let text = match response.text().await {
+        Ok(text) => text,
+        Err(e) => return ToolOutput::Error(format!("reading response failed: {}", e)),
+    };
+    if !status.is_success() {
+        return ToolOutput::Error(format!("HTTP {}: {}", status, text));
+    }
+    match serde_json::from_str(&text) {
+        Ok(json) => ToolOutput::Json(json),
+        Err(_) => ToolOutput::Text(text),
+    }
+}
+
+// Parameters are sent as plain strings; arrays as comma-separated values
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(scalar).collect::<Vec<_>>().join(","),
+        other => other.to_string(),
+    }
+}
+
+fn encode_segment(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+// Tool names allow letters, digits, `_` and `-`, up to 64 characters
+fn tool_name(raw: &str) -> String {
+    let mut name: String = raw
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    while name.contains("__") {
+        name = name.replace("__", "_");
+    }
+    name.trim_matches('_').chars().take(64).collect()
+}
@@ -0,0 +1,147 @@
+use futures::FutureExt;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+use crate::messages::user_message;
+use crate::provider::ChatProvider;
+use crate::run_context::RunContext;
+use crate::swarm::Swarm;
+use crate::types::{Agent, RunOptions, Tool, ToolDefinition, ToolFunction, ToolOutput};
+
+impl Agent {
+    // Wraps this agent as a tool: each call runs a full conversation with the agent on
+    // `swarm` and returns its final text, without handing off the calling conversation.
+    // The conversation gets the calling run's context, turn limit and remaining budget,
+    // and its usage and cost count toward the calling run
+    pub fn as_tool<P: ChatProvider + 'static>(
+        &self,
+        name: &str,
+        description: &str,
+        swarm: Arc<Swarm<P>>,
+    ) -> ToolDefinition {
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "input": {
+                    "type": "string",
+                    "description": format!("The request for {}", self.name)
+                }
+            },
+            "required": ["input"]
+        });
+        let agent = self.clone();
+        let function = move |context: RunContext, args: Value| {
+            let swarm = swarm.clone();
+            let agent = agent.clone();
+            async move {
+                let Some(input) = args.get("input").and_then(Value::as_str) else {
+                    return ToolOutput::Error("missing string argument `input`".to_string());
+                };
+                let messages = vec![user_message(input)];
+                let limits = context.limits();
+                let options = RunOptions {
+                    max_turns: limits.max_turns,
+                    max_cost_usd: limits.max_cost_usd,
+                    max_total_tokens: limits.max_total_tokens,
+                    context,
+                    ..Default::default()
+                };
+                match swarm.run_with_options(agent, messages, options).await {
+                    Ok(response) => ToolOutput::SubRun(response),
+                    Err(e) => ToolOutput::Error(e.to_string()),
+                }
+            }
+            .boxed()
+        };
+        ToolDefinition::new(
+            Tool::new(name, description, parameters),
+            ToolFunction::ContextualAsync(Arc::new(function)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::MockProvider;
+    use crate::types::{RunStatus, Usage};
+
+    fn usage(total_tokens: u32) -> Usage {
+        Usage {
+            prompt_tokens: total_tokens,
+            completion_tokens: 0,
+            total_tokens,
+        }
+    }
+
+    // A caller that delegates once to a translator running on `translator_swarm`
+    fn caller(translator_swarm: Arc<Swarm<MockProvider>>) -> Agent {
+        let translator = Agent {
+            name: "Translator".to_string(),
+            ..Agent::default()
+        }
+        .with_tool(ToolDefinition::new(
+            Tool::new("lookup", "", json!({ "type": "object" })),
+            ToolFunction::Sync(Arc::new(|_| ToolOutput::Text("found".to_string()))),
+        ));
+        Agent::default().with_tool(translator.as_tool("translate", "", translator_swarm))
+    }
+
+    fn delegating_provider() -> MockProvider {
+        MockProvider::new()
+            .with_tool_call("translate", json!({ "input": "hi" }))
+            .with_usage(usage(10))
+            .with_text("done")
+            .with_usage(usage(10))
+    }
+
+    #[tokio::test]
+    async fn counts_the_sub_runs_usage() {
+        let translator = MockProvider::new().with_text("hola").with_usage(usage(5));
+        let swarm = Swarm::with_provider(delegating_provider());
+        let agent = caller(Arc::new(Swarm::with_provider(translator)));
+        let response = swarm
+            .runner(agent, vec![user_message("translate hi")])
+            .await
+            .unwrap();
+        assert_eq!(response.final_text(), Some("done".to_string()));
+        assert_eq!(response.usage.total_tokens, 25);
+    }
+
+    #[tokio::test]
+    async fn sub_runs_share_the_callers_budget() {
+        let translator = MockProvider::new()
+            .with_tool_call("lookup", json!({}))
+            .with_usage(usage(5))
+            .with_text("hola")
+            .with_usage(usage(5));
+        let translator_swarm = Arc::new(Swarm::with_provider(translator));
+        let swarm = Swarm::with_provider(delegating_provider());
+        let agent = caller(translator_swarm.clone());
+        let response = swarm
+            .runner(agent, vec![user_message("translate hi")])
+            .max_total_tokens(12)
+            .await
+            .unwrap();
+        // 10 tokens leave the translator 2, which its first turn uses up
+        assert_eq!(translator_swarm.provider().remaining(), 1);
+        assert_eq!(response.status, RunStatus::BudgetExceeded);
+        assert_eq!(response.usage.total_tokens, 15);
+    }
+
+    #[tokio::test]
+    async fn sub_runs_inherit_the_turn_limit() {
+        let translator = (0..3).fold(MockProvider::new(), |provider, _| {
+            provider.with_tool_call("lookup", json!({}))
+        });
+        let translator_swarm = Arc::new(Swarm::with_provider(translator));
+        let swarm = Swarm::with_provider(delegating_provider());
+        let agent = caller(translator_swarm.clone());
+        swarm
+            .runner(agent, vec![user_message("translate hi")])
+            .max_turns(2)
+            .await
+            .unwrap();
+        assert_eq!(translator_swarm.provider().remaining(), 1);
+    }
+}
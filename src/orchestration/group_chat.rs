@@ -0,0 +1,259 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use std::sync::Arc;
+
+use super::{attribute_messages, author_name};
+use crate::error::Result;
+use crate::messages::message_text;
+use crate::provider::{ChatProvider, OpenAIProvider};
+use crate::swarm::Swarm;
+use crate::types::{Agent, Response, RunOptions};
+
+// Picks the index of the next speaker given the history, roster and previous speaker
+pub type SpeakerSelectorFn =
+    Arc<dyn Fn(&[ChatCompletionRequestMessage], &[Agent], Option<usize>) -> usize + Send + Sync>;
+
+// Decides from the shared history whether the chat is over
+pub type TerminationFn = Arc<dyn Fn(&[ChatCompletionRequestMessage]) -> bool + Send + Sync>;
+
+// How the next speaker in a group chat is chosen
+#[derive(Clone)]
+pub enum SpeakerSelection {
+    RoundRobin,
+    // Ask a model to name the next speaker; falls back to round-robin on unknown names
+    Llm { model: String },
+    Rule(SpeakerSelectorFn),
+}
+
+impl std::fmt::Debug for SpeakerSelection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpeakerSelection::RoundRobin => f.write_str("RoundRobin"),
+            SpeakerSelection::Llm { model } => f.debug_struct("Llm").field("model", model).finish(),
+            SpeakerSelection::Rule(_) => f.write_str("Rule"),
+        }
+    }
+}
+
+// N agents taking turns on a shared history, each turn being a full `Swarm` run
+pub struct GroupChat<'a, P: ChatProvider = OpenAIProvider> {
+    swarm: &'a Swarm<P>,
+    agents: Vec<Agent>,
+    selection: SpeakerSelection,
+    max_rounds: usize,
+    termination: Option<TerminationFn>,
+    options: RunOptions,
+}
+
+impl<'a, P: ChatProvider> GroupChat<'a, P> {
+    pub fn new(swarm: &'a Swarm<P>, agents: Vec<Agent>) -> Self {
+        GroupChat {
+            swarm,
+            agents,
+            selection: SpeakerSelection::RoundRobin,
+            max_rounds: 10,
+            termination: None,
+            options: RunOptions::default(),
+        }
+    }
+
+    pub fn with_selection(mut self, selection: SpeakerSelection) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    pub fn with_termination(
+        mut self,
+        termination: impl Fn(&[ChatCompletionRequestMessage]) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.termination = Some(Arc::new(termination));
+        self
+    }
+
+    // Ends the chat once a message contains the keyword, e.g. "TERMINATE"
+    pub fn terminate_on(self, keyword: &str) -> Self {
+        let keyword = keyword.to_string();
+        self.with_termination(move |history| {
+            history
+                .last()
+                .is_some_and(|message| message_text(message).contains(&keyword))
+        })
+    }
+
+    // Options applied to every speaker's run
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    // Runs the chat until a termination condition or the round limit is hit
+    pub async fn run(&self, messages: Vec<ChatCompletionRequestMessage>) -> Result<Response> {
+        // 1. Initialize shared history and accumulated response
+        let mut history = messages;
+        let init_len = history.len();
+        let mut total = Response {
+            context_variables: self.options.context_variables.clone(),
+            ..Response::empty()
+        };
+        let mut previous = None;
+
+        // 2. Let the selected speaker run on the shared history each round
+        for _ in 0..self.max_rounds {
+            if self.agents.is_empty() {
+                break;
+            }
+            let speaker = self.select_speaker(&history, previous, &mut total).await?;
+            let agent = self.agents[speaker].clone();
+            if self.options.debug {
+                self.swarm
+                    .debug_event("group_chat", format!("speaker: {}", agent.name));
+            }
+
+            let options = RunOptions {
+                context_variables: total.context_variables.clone(),
+                ..self.options.clone()
+            };
+            let mut response = self
+                .swarm
+                .run_with_options(agent.clone(), history.clone(), options)
+                .await?;
+            attribute_messages(&mut response.messages, &agent.name);
+            total.absorb(&response);
+            history.extend(response.messages);
+            total.context_variables = response.context_variables;
+            total.agent = Some(agent);
+            previous = Some(speaker);
+
+            // 2.1 Stop once the termination condition holds
+            if self
+                .termination
+                .as_ref()
+                .is_some_and(|termination| termination(&history))
+            {
+                break;
+            }
+        }
+
+        // 3. Return everything said after the initial messages
+        total.messages = history.split_off(init_len);
+        Ok(total)
+    }
+
+    // Chooses the next speaker according to the selection policy, adding the coordinator's
+    // usage and cost to the chat's total
+    async fn select_speaker(
+        &self,
+        history: &[ChatCompletionRequestMessage],
+        previous: Option<usize>,
+        total: &mut Response,
+    ) -> Result<usize> {
+        let round_robin = previous.map_or(0, |index| (index + 1) % self.agents.len());
+        match &self.selection {
+            SpeakerSelection::RoundRobin => Ok(round_robin),
+            SpeakerSelection::Rule(select) => {
+                Ok(select(history, &self.agents, previous).min(self.agents.len() - 1))
+            }
+            SpeakerSelection::Llm { model } => {
+                // 1. Describe the roster to a coordinator agent
+                let roster = self
+                    .agents
+                    .iter()
+                    .map(|agent| format!("- {}: {}", author_name(&agent.name), agent.name))
+                    .collect::<Vec<_>>()
+                    .join("\n");
+                let coordinator = Agent {
+                    name: "Coordinator".to_string(),
+                    model: model.clone(),
+                    instructions: format!(
+                        "You coordinate a group chat. Based on the conversation, choose who speaks next.\n\
+                         Participants:\n{}\n\
+                         Reply with the participant's identifier only.",
+                        roster
+                    )
+                    .into(),
+                    ..Default::default()
+                };
+
+                // 2. Ask it for a single reply and count what that cost
+                let options = RunOptions {
+                    context_variables: total.context_variables.clone(),
+                    context: self.options.context.clone(),
+                    debug: self.options.debug,
+                    max_turns: Some(1),
+                    execute_tools: false,
+                    ..Default::default()
+                };
+                let selection = self
+                    .swarm
+                    .run_with_options(coordinator, history.to_vec(), options)
+                    .await?;
+                total.usage.add(&selection.usage);
+                total.cost_usd += selection.cost_usd;
+
+                // 3. Match the reply against the roster
+                let reply = selection.final_text().unwrap_or_default();
+                let reply = reply.trim().trim_matches(|c: char| c == '"' || c == '.');
+                Ok(self
+                    .agents
+                    .iter()
+                    .position(|agent| {
+                        author_name(&agent.name).eq_ignore_ascii_case(reply)
+                            || agent.name.eq_ignore_ascii_case(reply)
+                    })
+                    .unwrap_or(round_robin))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cost::{ModelPrice, PricingTable};
+    use crate::messages::user_message;
+    use crate::provider::MockProvider;
+    use crate::types::Usage;
+
+    fn usage(prompt_tokens: u32) -> Usage {
+        Usage {
+            prompt_tokens,
+            completion_tokens: 0,
+            total_tokens: prompt_tokens,
+        }
+    }
+
+    #[tokio::test]
+    async fn counts_the_coordinators_usage_and_cost() {
+        let provider = MockProvider::new()
+            .with_text("critic")
+            .with_usage(usage(10))
+            .with_text("looks good")
+            .with_usage(usage(5));
+        // Every prompt token costs $1
+        let pricing = PricingTable::empty().with_price("gpt-4", ModelPrice::new(1e6, 0.0));
+        let swarm = Swarm::with_provider(provider).with_pricing(pricing);
+        let agents = ["Writer", "Critic"]
+            .map(|name| Agent {
+                name: name.to_string(),
+                ..Agent::default()
+            })
+            .to_vec();
+        let response = GroupChat::new(&swarm, agents)
+            .with_selection(SpeakerSelection::Llm {
+                model: "gpt-4".to_string(),
+            })
+            .with_max_rounds(1)
+            .run(vec![user_message("draft a tagline")])
+            .await
+            .unwrap();
+
+        assert_eq!(response.agent.unwrap().name, "Critic");
+        assert_eq!(response.messages.len(), 1);
+        assert_eq!(response.usage.total_tokens, 15);
+        assert!((response.cost_usd - 15.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,86 @@
+// Multi-agent patterns built on top of `Swarm` runs
+mod agent_tool;
+mod group_chat;
+mod router;
+mod supervisor;
+
+pub use group_chat::{GroupChat, SpeakerSelection, SpeakerSelectorFn, TerminationFn};
+pub use router::Router;
+pub use supervisor::{Assignment, Supervisor};
+
+use async_openai::types::ChatCompletionRequestMessage;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+
+use crate::error::{Result, SwarmError};
+use crate::messages::user_message;
+use crate::provider::ChatProvider;
+use crate::schema::extract_json_object;
+use crate::swarm::Swarm;
+use crate::types::Response;
+
+// Turns an agent name into a valid message author name, e.g. "Sales Agent" -> "Sales_Agent"
+pub(crate) fn author_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+// Labels a run's assistant messages with the agent that produced them
+pub(crate) fn attribute_messages(messages: &mut [ChatCompletionRequestMessage], name: &str) {
+    for message in messages {
+        if let ChatCompletionRequestMessage::Assistant(msg) = message {
+            msg.name.get_or_insert_with(|| author_name(name));
+        }
+    }
+}
+
+// Text of the last assistant message of a run
+pub fn final_text(response: &Response) -> String {
+    response.final_text().unwrap_or_default()
+}
+
+// Runs a planning agent until its final reply parses as `T`, tolerating text or code
+// fences around the JSON object. A reply that fails to parse is pointed out and asked
+// again up to `SwarmConfig::output_retries` times; every attempt is absorbed into `total`
+pub(crate) async fn run_for_json<T, P, Fut>(
+    swarm: &Swarm<P>,
+    messages: &[ChatCompletionRequestMessage],
+    total: &mut Response,
+    debug: Option<&'static str>,
+    mut run: impl FnMut(Vec<ChatCompletionRequestMessage>) -> Fut,
+) -> Result<T>
+where
+    T: DeserializeOwned,
+    P: ChatProvider,
+    Fut: Future<Output = Result<Response>>,
+{
+    let mut history = messages.to_vec();
+    let mut attempt = 0;
+    loop {
+        let response = run(history.clone()).await?;
+        total.absorb(&response);
+        let text = final_text(&response);
+        match serde_json::from_str::<T>(extract_json_object(&text)) {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < swarm.config().output_retries => {
+                if let Some(source) = debug {
+                    swarm.debug_event(source, format!("failed to parse plan: {}", e));
+                }
+                history.extend(response.messages);
+                history.push(user_message(&format!(
+                    "Your reply could not be parsed: {}. Reply again with only JSON in the requested form.",
+                    e
+                )));
+                attempt += 1;
+            }
+            Err(e) => return Err(SwarmError::StructuredOutput(e)),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use async_openai::types::ChatCompletionRequestMessage;
+
+use crate::error::{Result, SwarmError};
+use crate::provider::{ChatProvider, OpenAIProvider};
+use crate::swarm::Swarm;
+use crate::types::{handoff_tool, Agent, Response, RunOptions};
+
+// Classifies the first user message and hands the conversation to the matching specialist
+pub struct Router<'a, P: ChatProvider = OpenAIProvider> {
+    swarm: &'a Swarm<P>,
+    routes: Vec<(Agent, String)>,
+    model: String,
+    fallback: Option<Agent>,
+    options: RunOptions,
+}
+
+impl<'a, P: ChatProvider> Router<'a, P> {
+    // Each route pairs a specialist with a description of the requests it handles
+    pub fn new(swarm: &'a Swarm<P>, routes: Vec<(Agent, String)>) -> Self {
+        Router {
+            swarm,
+            routes,
+            model: "gpt-4o-mini".to_string(),
+            fallback: None,
+            options: RunOptions::default(),
+        }
+    }
+
+    pub fn route(mut self, agent: Agent, description: impl Into<String>) -> Self {
+        self.routes.push((agent, description.into()));
+        self
+    }
+
+    // Model used by the classifier agent
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    // Specialist used when the classifier picks no route; defaults to the first route
+    pub fn with_fallback(mut self, agent: Agent) -> Self {
+        self.fallback = Some(agent);
+        self
+    }
+
+    // Options applied to the specialist run
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    // Routes the conversation and returns the specialist's response
+    pub async fn run(&self, messages: Vec<ChatCompletionRequestMessage>) -> Result<Response> {
+        let (specialist, classification) = self.classify(&messages).await?;
+        if self.options.debug {
+            self.swarm
+                .debug_event("router", format!("selected: {}", specialist.name));
+        }
+        let mut response = self
+            .swarm
+            .run_with_options(specialist, messages, self.options.clone())
+            .await?;
+        // Only the classifier's cost carries over; the specialist's run is the answer
+        response.usage.add(&classification.usage);
+        response.cost_usd += classification.cost_usd;
+        Ok(response)
+    }
+
+    // Picks a specialist for the first user message; returns it with the classifier's run
+    pub async fn classify(
+        &self,
+        messages: &[ChatCompletionRequestMessage],
+    ) -> Result<(Agent, Response)> {
+        let fallback = self
+            .fallback
+            .as_ref()
+            .or_else(|| self.routes.first().map(|(agent, _)| agent))
+            .ok_or(SwarmError::NoRoutes)?
+            .clone();
+
+        // 1. Build a classifier with one handoff tool per route
+        let classifier = self.routes.iter().fold(
+            Agent {
+                name: "Router".to_string(),
+                model: self.model.clone(),
+                instructions: "Route the user's request to the best specialist by calling \
+                               exactly one transfer tool. Do not answer the request yourself."
+                    .into(),
+                parallel_tool_calls: false,
+                ..Default::default()
+            },
+            |classifier, (agent, description)| {
+                classifier.with_tool(handoff_tool(agent, description))
+            },
+        );
+
+        // 2. Classify only the first user message, stopping right after the handoff
+        let first = messages
+            .iter()
+            .find(|message| matches!(message, ChatCompletionRequestMessage::User(_)))
+            .cloned()
+            .into_iter()
+            .collect();
+        let options = RunOptions {
+            context_variables: self.options.context_variables.clone(),
+            context: self.options.context.clone(),
+            debug: self.options.debug,
+            max_turns: Some(1),
+            ..Default::default()
+        };
+        let classification = self
+            .swarm
+            .run_with_options(classifier, first, options)
+            .await?;
+
+        // 3. The handoff leaves the specialist as the active agent
+        let specialist = classification
+            .agent
+            .clone()
+            .filter(|agent| {
+                self.routes
+                    .iter()
+                    .any(|(route, _)| route.name == agent.name)
+            })
+            .unwrap_or(fallback);
+        Ok((specialist, classification))
+    }
+}
@@ -0,0 +1,226 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use serde::Deserialize;
+
+use super::{author_name, final_text, run_for_json};
+use crate::error::Result;
+use crate::messages::{system_message, user_message};
+use crate::provider::{ChatProvider, OpenAIProvider};
+use crate::swarm::Swarm;
+use crate::types::{Agent, Response, RunOptions};
+
+// A sub-task the manager assigns to one worker
+#[derive(Debug, Clone, Deserialize)]
+pub struct Assignment {
+    pub worker: String,
+    pub task: String,
+}
+
+#[derive(Deserialize)]
+struct Plan {
+    tasks: Vec<Assignment>,
+}
+
+// Manager agent that splits a task across worker agents and synthesizes their results
+pub struct Supervisor<'a, P: ChatProvider = OpenAIProvider> {
+    swarm: &'a Swarm<P>,
+    manager: Agent,
+    workers: Vec<Agent>,
+    parallel: bool,
+    options: RunOptions,
+}
+
+impl<'a, P: ChatProvider> Supervisor<'a, P> {
+    pub fn new(swarm: &'a Swarm<P>, manager: Agent, workers: Vec<Agent>) -> Self {
+        Supervisor {
+            swarm,
+            manager,
+            workers,
+            parallel: true,
+            options: RunOptions::default(),
+        }
+    }
+
+    // Whether workers run concurrently (the default) or one after another
+    pub fn with_parallel(mut self, parallel: bool) -> Self {
+        self.parallel = parallel;
+        self
+    }
+
+    // Options applied to every manager and worker run
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    // Plans, dispatches and synthesizes; the response holds the manager's final answer
+    pub async fn run(&self, messages: Vec<ChatCompletionRequestMessage>) -> Result<Response> {
+        let mut total = Response {
+            context_variables: self.options.context_variables.clone(),
+            ..Response::empty()
+        };
+
+        // 1. Ask the manager to decompose the task
+        let assignments = self.plan(&messages, &mut total).await?;
+        if self.options.debug {
+            self.swarm
+                .debug_event("supervisor", format!("plan: {:?}", assignments));
+        }
+
+        // 2. Dispatch sub-tasks to workers
+        let results = if self.parallel {
+            futures::future::try_join_all(
+                assignments
+                    .iter()
+                    .map(|assignment| self.dispatch(assignment)),
+            )
+            .await?
+        } else {
+            let mut results = Vec::with_capacity(assignments.len());
+            for assignment in &assignments {
+                results.push(self.dispatch(assignment).await?);
+            }
+            results
+        };
+
+        // 3. Let the manager synthesize the worker results
+        let mut report = String::from("Results from your workers:\n");
+        for (assignment, result) in assignments.iter().zip(&results) {
+            if let Some(result) = result {
+                total.absorb(result);
+                report.push_str(&format!(
+                    "\n### {}: {}\n{}\n",
+                    assignment.worker,
+                    assignment.task,
+                    final_text(result)
+                ));
+            }
+        }
+        report.push_str("\nUsing these results, give the final answer to the original request.");
+        let mut history = messages;
+        history.push(system_message(&report));
+        let synthesis = self
+            .swarm
+            .run_with_options(self.manager.clone(), history, self.options.clone())
+            .await?;
+        total.absorb(&synthesis);
+        total.messages = synthesis.messages;
+        total.agent = synthesis.agent;
+        total.context_variables = synthesis.context_variables;
+        Ok(total)
+    }
+
+    // Asks the manager for a JSON plan, asking again per `SwarmConfig::output_retries` when
+    // it cannot be parsed and failing with `SwarmError::StructuredOutput` after that
+    async fn plan(
+        &self,
+        messages: &[ChatCompletionRequestMessage],
+        total: &mut Response,
+    ) -> Result<Vec<Assignment>> {
+        let roster = self
+            .workers
+            .iter()
+            .map(|worker| {
+                let description = worker
+                    .instructions
+                    .render(&self.options.context_variables, &self.options.context)?;
+                Ok(format!("- {}: {}", author_name(&worker.name), description))
+            })
+            .collect::<Result<Vec<_>>>()?
+            .join("\n");
+        let planner = Agent {
+            instructions: format!(
+                "{}\n\nBreak the user's request into sub-tasks for these workers:\n{}\n\n\
+                 Reply with JSON only, in the form \
+                 {{\"tasks\": [{{\"worker\": \"<worker>\", \"task\": \"<instructions>\"}}]}}.",
+                self.manager
+                    .instructions
+                    .render(&self.options.context_variables, &self.options.context)?,
+                roster
+            )
+            .into(),
+            tools: Vec::new(),
+            registry: None,
+            ..self.manager.clone()
+        };
+        let debug = self.options.debug.then_some("supervisor");
+        let plan: Plan = run_for_json(self.swarm, messages, total, debug, |history| {
+            self.swarm
+                .run_with_options(planner.clone(), history, self.options.clone())
+        })
+        .await?;
+        Ok(plan.tasks)
+    }
+
+    // Runs one assignment on its worker; unknown workers are skipped
+    async fn dispatch(&self, assignment: &Assignment) -> Result<Option<Response>> {
+        let Some(worker) = self.workers.iter().find(|worker| {
+            author_name(&worker.name).eq_ignore_ascii_case(&assignment.worker)
+                || worker.name.eq_ignore_ascii_case(&assignment.worker)
+        }) else {
+            if self.options.debug {
+                self.swarm.debug_event(
+                    "supervisor",
+                    format!("assigned unknown worker: {}", assignment.worker),
+                );
+            }
+            return Ok(None);
+        };
+        let response = self
+            .swarm
+            .run_with_options(
+                worker.clone(),
+                vec![user_message(&assignment.task)],
+                self.options.clone(),
+            )
+            .await?;
+        Ok(Some(response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SwarmConfig;
+    use crate::error::SwarmError;
+    use crate::messages::message_text;
+    use crate::provider::MockProvider;
+
+    fn agent(name: &str) -> Agent {
+        Agent {
+            name: name.to_string(),
+            ..Agent::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn asks_again_for_an_unparseable_plan() {
+        let provider = MockProvider::new()
+            .with_text("Sure, let me think about it.")
+            .with_text(r#"{"tasks": [{"worker": "Researcher", "task": "find facts"}]}"#)
+            .with_text("the facts")
+            .with_text("final answer");
+        let swarm = Swarm::with_provider(provider);
+        let response = Supervisor::new(&swarm, agent("Manager"), vec![agent("Researcher")])
+            .run(vec![user_message("write a report")])
+            .await
+            .unwrap();
+        assert_eq!(final_text(&response), "final answer");
+        let retry = &swarm.provider().requests()[1];
+        assert!(message_text(retry.messages.last().unwrap()).contains("could not be parsed"));
+    }
+
+    #[tokio::test]
+    async fn fails_when_the_plan_never_parses() {
+        let provider = MockProvider::new()
+            .with_text("no plan")
+            .with_text("still no plan");
+        let swarm =
+            Swarm::with_provider(provider).with_config(SwarmConfig::new().with_output_retries(1));
+        let error = Supervisor::new(&swarm, agent("Manager"), vec![agent("Researcher")])
+            .run(vec![user_message("write a report")])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SwarmError::StructuredOutput(_)));
+        assert_eq!(swarm.provider().remaining(), 0);
+    }
+}
@@ -0,0 +1,76 @@
+// OTLP export of the swarm's `tracing` spans. Spans carry the OpenTelemetry GenAI
+// semantic conventions (`gen_ai.operation.name`, `gen_ai.request.model`,
+// `gen_ai.usage.*`, `gen_ai.tool.name`, ...) so runs show up in trace backends as
+// agent invocations, chat calls and tool executions.
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::trace::TracerProvider;
+use opentelemetry_sdk::{runtime, Resource};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::error::{Result, SwarmError};
+
+// Configures and installs an OTLP span exporter as the global tracing subscriber
+#[derive(Debug, Clone)]
+pub struct OtlpExporter {
+    service_name: String,
+    endpoint: Option<String>,
+}
+
+impl OtlpExporter {
+    pub fn new(service_name: impl Into<String>) -> Self {
+        OtlpExporter {
+            service_name: service_name.into(),
+            endpoint: None,
+        }
+    }
+
+    // Collector gRPC endpoint; defaults to `OTEL_EXPORTER_OTLP_ENDPOINT` or localhost:4317
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = Some(endpoint.into());
+        self
+    }
+
+    // Installs the exporter; keep the guard alive for as long as spans should be exported
+    pub fn install(self) -> Result<OtelGuard> {
+        // 1. Build the OTLP span exporter
+        let mut exporter = opentelemetry_otlp::SpanExporter::builder().with_tonic();
+        if let Some(endpoint) = self.endpoint {
+            exporter = exporter.with_endpoint(endpoint);
+        }
+        let exporter = exporter
+            .build()
+            .map_err(|e| SwarmError::Telemetry(e.to_string()))?;
+
+        // 2. Batch spans on the tokio runtime under this service's resource
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new([KeyValue::new(
+                "service.name",
+                self.service_name,
+            )]))
+            .build();
+        let tracer = provider.tracer("swarm-rs");
+
+        // 3. Bridge `tracing` spans into OpenTelemetry
+        tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer))
+            .try_init()
+            .map_err(|e| SwarmError::Telemetry(e.to_string()))?;
+        Ok(OtelGuard { provider })
+    }
+}
+
+// Flushes pending spans and shuts the exporter down when dropped
+#[derive(Debug)]
+pub struct OtelGuard {
+    provider: TracerProvider,
+}
+
+impl Drop for OtelGuard {
+    fn drop(&mut self) {
+        let _ = self.provider.shutdown();
+    }
+}
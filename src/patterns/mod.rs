@@ -0,0 +1,10 @@
+// Reasoning patterns that drive an agent through structured, inspectable steps
+pub mod plan_execute;
+pub mod react;
+pub mod reflect;
+
+pub use plan_execute::{
+    PlanExecute, PlanExecuteResponse, PlanStep, StepResult, StepStatus, PLAN_STATUS,
+};
+pub use react::{React, ReactAction, ReactResponse, ReactStep};
+pub use reflect::{Critique, Reflect, ReflectResponse};
@@ -0,0 +1,327 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use futures::{channel::mpsc, FutureExt, Stream, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::Result;
+use crate::messages::{system_message, user_message};
+use crate::orchestration::{final_text, run_for_json};
+use crate::provider::{ChatProvider, OpenAIProvider};
+use crate::swarm::{emit, EventSender, Swarm};
+use crate::types::{Agent, ContextVariables, Response, RunOptions, RunState, SwarmEvent};
+
+// Context variable holding the status of every plan step while the executor runs
+pub const PLAN_STATUS: &str = "plan_status";
+
+// One step of the planner's plan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanStep {
+    // 1-based position in the plan, assigned after parsing
+    #[serde(default)]
+    pub id: usize,
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct StepResult {
+    pub step: PlanStep,
+    pub status: StepStatus,
+    // The executor's answer, or the error for a failed step
+    pub output: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlanExecuteResponse {
+    pub plan: Vec<PlanStep>,
+    pub results: Vec<StepResult>,
+    // The executor's conversation, with usage and cost of both phases
+    pub response: Response,
+}
+
+#[derive(Deserialize)]
+struct Plan {
+    steps: Vec<PlanStep>,
+}
+
+// Two-phase workflow: a planner writes typed steps, then an executor runs them one by one
+pub struct PlanExecute<'a, P: ChatProvider = OpenAIProvider> {
+    swarm: &'a Swarm<P>,
+    planner: Agent,
+    executor: Agent,
+    options: RunOptions,
+}
+
+impl<'a, P: ChatProvider> PlanExecute<'a, P> {
+    pub fn new(swarm: &'a Swarm<P>, planner: Agent, executor: Agent) -> Self {
+        PlanExecute {
+            swarm,
+            planner,
+            executor,
+            options: RunOptions::default(),
+        }
+    }
+
+    // Options applied to the planner and every executor step
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub async fn run(
+        &self,
+        messages: Vec<ChatCompletionRequestMessage>,
+    ) -> Result<PlanExecuteResponse> {
+        self.execute(messages, None).await
+    }
+
+    // Streams plan and step progress alongside the events of each underlying run
+    pub fn run_stream(
+        &'a self,
+        messages: Vec<ChatCompletionRequestMessage>,
+    ) -> impl Stream<Item = SwarmEvent> + 'a {
+        let (tx, rx) = mpsc::unbounded();
+
+        let driver = async move {
+            let event = match self.execute(messages, Some(&tx)).await {
+                Ok(result) => SwarmEvent::RunCompleted(result.response),
+                Err(e) => SwarmEvent::RunFailed(e),
+            };
+            let _ = tx.unbounded_send(event);
+        };
+
+        futures::stream::select(
+            rx,
+            driver
+                .into_stream()
+                .filter_map(|_| futures::future::ready(None)),
+        )
+    }
+
+    async fn execute(
+        &self,
+        messages: Vec<ChatCompletionRequestMessage>,
+        events: Option<&EventSender>,
+    ) -> Result<PlanExecuteResponse> {
+        let mut total = Response::empty();
+
+        // 1. Ask the planner for a typed plan
+        let plan = self.plan(&messages, &mut total, events).await?;
+        if self.options.debug {
+            self.swarm
+                .debug_event("plan_execute", format!("plan: {:?}", plan));
+        }
+        emit(
+            events,
+            SwarmEvent::PlanCreated {
+                steps: plan.clone(),
+            },
+        );
+
+        // 2. Run each step with the executor, tracking status in the context
+        let mut context_variables = self.options.context_variables.clone();
+        let mut statuses = vec![StepStatus::Pending; plan.len()];
+        record_status(&mut context_variables, &plan, &statuses);
+        let overview = plan
+            .iter()
+            .map(|step| format!("{}. {}", step.id, step.description))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut history = messages;
+        history.push(system_message(&format!(
+            "Carry out this plan one step at a time:\n{}",
+            overview
+        )));
+        let init_len = history.len();
+        let mut executor = self.executor.clone();
+        let mut results = Vec::with_capacity(plan.len());
+
+        for (index, step) in plan.iter().enumerate() {
+            statuses[index] = StepStatus::Running;
+            record_status(&mut context_variables, &plan, &statuses);
+            emit(
+                events,
+                SwarmEvent::StepStarted {
+                    step: step.id,
+                    description: step.description.clone(),
+                },
+            );
+
+            // 2.1 Execute the step with tool access
+            history.push(user_message(&format!(
+                "Execute step {}: {}",
+                step.id, step.description
+            )));
+            let options = RunOptions {
+                context_variables: context_variables.clone(),
+                ..self.options.clone()
+            };
+            let state = RunState::new(executor.clone(), history.clone(), context_variables.clone());
+            let (status, output) = match self.swarm.run_loop(state, options, events).await {
+                Ok(response) => {
+                    total.absorb(&response);
+                    let output = final_text(&response);
+                    history.extend(response.messages);
+                    context_variables = response.context_variables;
+                    if let Some(agent) = response.agent {
+                        executor = agent;
+                    }
+                    (StepStatus::Completed, output)
+                }
+                Err(e) => (StepStatus::Failed, e.to_string()),
+            };
+
+            // 2.2 Record the outcome; a failed step stops the plan
+            statuses[index] = status;
+            record_status(&mut context_variables, &plan, &statuses);
+            emit(
+                events,
+                SwarmEvent::StepFinished {
+                    step: step.id,
+                    status,
+                    output: output.clone(),
+                },
+            );
+            results.push(StepResult {
+                step: step.clone(),
+                status,
+                output,
+            });
+            if status == StepStatus::Failed {
+                break;
+            }
+        }
+
+        Ok(PlanExecuteResponse {
+            plan,
+            results,
+            response: Response {
+                messages: history[init_len..].to_vec(),
+                agent: Some(executor),
+                context_variables,
+                ..total
+            },
+        })
+    }
+
+    // Asks the planner for a JSON plan, asking again per `SwarmConfig::output_retries` when
+    // it cannot be parsed and failing with `SwarmError::StructuredOutput` after that
+    async fn plan(
+        &self,
+        messages: &[ChatCompletionRequestMessage],
+        total: &mut Response,
+        events: Option<&EventSender>,
+    ) -> Result<Vec<PlanStep>> {
+        let planner = Agent {
+            instructions: format!(
+                "{}\n\nWrite a step-by-step plan for the user's request. \
+                 Reply with JSON only, in the form \
+                 {{\"steps\": [{{\"description\": \"<what to do>\"}}]}}.",
+                self.planner
+                    .instructions
+                    .render(&self.options.context_variables, &self.options.context)?
+            )
+            .into(),
+            tools: Vec::new(),
+            registry: None,
+            ..self.planner.clone()
+        };
+        let debug = self.options.debug.then_some("plan_execute");
+        let plan: Plan = run_for_json(self.swarm, messages, total, debug, |history| {
+            let state = RunState::new(
+                planner.clone(),
+                history,
+                self.options.context_variables.clone(),
+            );
+            self.swarm.run_loop(state, self.options.clone(), events)
+        })
+        .await?;
+        Ok(plan
+            .steps
+            .into_iter()
+            .enumerate()
+            .map(|(index, step)| PlanStep {
+                id: index + 1,
+                ..step
+            })
+            .collect())
+    }
+}
+
+// Writes `[{"step", "description", "status"}]` under `PLAN_STATUS`
+fn record_status(
+    context_variables: &mut ContextVariables,
+    plan: &[PlanStep],
+    statuses: &[StepStatus],
+) {
+    let entries = plan
+        .iter()
+        .zip(statuses)
+        .map(|(step, status)| {
+            serde_json::json!({
+                "step": step.id,
+                "description": step.description,
+                "status": status,
+            })
+        })
+        .collect();
+    context_variables.insert(PLAN_STATUS.to_string(), Value::Array(entries));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::SwarmConfig;
+    use crate::error::SwarmError;
+    use crate::messages::message_text;
+    use crate::provider::MockProvider;
+
+    fn agent(name: &str) -> Agent {
+        Agent {
+            name: name.to_string(),
+            ..Agent::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn asks_again_for_an_unparseable_plan() {
+        let provider = MockProvider::new()
+            .with_text("Here is my plan: look it up.")
+            .with_text(r#"{"steps": [{"description": "look it up"}]}"#)
+            .with_text("found it");
+        let swarm = Swarm::with_provider(provider);
+        let result = PlanExecute::new(&swarm, agent("Planner"), agent("Executor"))
+            .run(vec![user_message("find the answer")])
+            .await
+            .unwrap();
+        assert_eq!(result.plan.len(), 1);
+        assert_eq!(result.plan[0].id, 1);
+        assert_eq!(result.results[0].status, StepStatus::Completed);
+        assert_eq!(result.results[0].output, "found it");
+        let retry = &swarm.provider().requests()[1];
+        assert!(message_text(retry.messages.last().unwrap()).contains("could not be parsed"));
+    }
+
+    #[tokio::test]
+    async fn fails_instead_of_running_an_empty_plan() {
+        let provider = MockProvider::new()
+            .with_text("no plan")
+            .with_text("still no plan");
+        let swarm =
+            Swarm::with_provider(provider).with_config(SwarmConfig::new().with_output_retries(1));
+        let error = PlanExecute::new(&swarm, agent("Planner"), agent("Executor"))
+            .run(vec![user_message("find the answer")])
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SwarmError::StructuredOutput(_)));
+        assert_eq!(swarm.provider().remaining(), 0);
+    }
+}
@@ -0,0 +1,256 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::error::Result;
+use crate::messages::{assistant_message, user_message};
+use crate::orchestration::final_text;
+use crate::provider::{ChatProvider, OpenAIProvider};
+use crate::swarm::Swarm;
+use crate::types::{Agent, ContextVariables, Response, RunOptions};
+
+const OBSERVATION: &str = "Observation:";
+
+// A tool invocation the model asked for in an `Action` line
+#[derive(Debug, Clone, Serialize)]
+pub struct ReactAction {
+    pub tool: String,
+    pub input: Value,
+}
+
+// One Thought -> Action -> Observation cycle; the last step carries no action
+#[derive(Debug, Clone, Serialize)]
+pub struct ReactStep {
+    pub thought: String,
+    pub action: Option<ReactAction>,
+    pub observation: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReactResponse {
+    // None when the step limit was reached before a final answer
+    pub answer: Option<String>,
+    pub steps: Vec<ReactStep>,
+    pub response: Response,
+}
+
+// Runs an agent as a text-based ReAct loop over its tools, keeping a trace of every step
+pub struct React<'a, P: ChatProvider = OpenAIProvider> {
+    swarm: &'a Swarm<P>,
+    agent: Agent,
+    max_steps: usize,
+    options: RunOptions,
+}
+
+impl<'a, P: ChatProvider> React<'a, P> {
+    pub fn new(swarm: &'a Swarm<P>, agent: Agent) -> Self {
+        React {
+            swarm,
+            agent,
+            max_steps: 10,
+            options: RunOptions::default(),
+        }
+    }
+
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    // Context variables, run context and debug output used by every step
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub async fn run(&self, messages: Vec<ChatCompletionRequestMessage>) -> Result<ReactResponse> {
+        let mut agent = self.agent.clone();
+        let mut context_variables = self.options.context_variables.clone();
+        let mut history = messages;
+        let init_len = history.len();
+        let mut steps = Vec::new();
+        let mut total = Response::empty();
+        let mut answer = None;
+
+        while steps.len() < self.max_steps {
+            // 1. Ask for the next thought, without native tool calling
+            let reasoner = self.reasoner(&agent, &context_variables)?;
+            let options = RunOptions {
+                context_variables: context_variables.clone(),
+                context: self.options.context.clone(),
+                debug: self.options.debug,
+                max_turns: Some(1),
+                ..Default::default()
+            };
+            let response = self
+                .swarm
+                .run_with_options(reasoner, history.clone(), options)
+                .await?;
+            total.absorb(&response);
+            let text = final_text(&response);
+            let text = text.split(OBSERVATION).next().unwrap_or_default().trim();
+            history.push(assistant_message(text));
+
+            // 2. A reply without an action is the final answer
+            let (thought, action, final_answer) = parse(text);
+            let Some(action) = action else {
+                answer = Some(final_answer.unwrap_or_else(|| text.to_string()));
+                steps.push(ReactStep {
+                    thought,
+                    action: None,
+                    observation: None,
+                });
+                break;
+            };
+
+            // 3. Run the tool and feed its output back as an observation
+            let result = self
+                .swarm
+                .call_tool(
+                    &agent,
+                    &action.tool,
+                    &action.input.to_string(),
+                    &context_variables,
+                    &self.options.context,
+                    self.options.debug,
+                )
+                .await;
+            let observation = match result {
+                Ok(result) => {
+                    total.usage.add(&result.usage);
+                    total.cost_usd += result.cost_usd;
+                    context_variables.extend(result.context_variables);
+                    if let Some(next) = result.agent {
+                        agent = next;
+                    }
+                    result.value
+                }
+                Err(e) => format!("Error: {}", e),
+            };
+            if self.options.debug {
+                self.swarm.debug_event(
+                    "react",
+                    format!("{}({}) -> {}", action.tool, action.input, observation),
+                );
+            }
+            history.push(user_message(&format!("{} {}", OBSERVATION, observation)));
+            steps.push(ReactStep {
+                thought,
+                action: Some(action),
+                observation: Some(observation),
+            });
+        }
+
+        Ok(ReactResponse {
+            answer,
+            steps,
+            response: Response {
+                messages: history[init_len..].to_vec(),
+                agent: Some(agent),
+                context_variables,
+                ..total
+            },
+        })
+    }
+
+    // The agent with its tools described in the prompt instead of sent as functions
+    fn reasoner(&self, agent: &Agent, context_variables: &ContextVariables) -> Result<Agent> {
+        let tools = agent
+            .tools
+            .iter()
+            .map(|tool| {
+                let tool = tool.without_context_variables();
+                format!(
+                    "- {}: {} Input schema: {}",
+                    tool.name, tool.description, tool.parameters
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let instructions = format!(
+            "{}\n\nYou can use these tools:\n{}\n\n\
+             Answer in this format, one action at a time:\n\
+             Thought: your reasoning about what to do next\n\
+             Action: the tool name\n\
+             Action Input: the tool input as a JSON object\n\n\
+             After each action you will receive an {} line with the result. \
+             When you know the answer, reply with:\n\
+             Thought: your final reasoning\n\
+             Final Answer: the answer",
+            agent
+                .instructions
+                .render(context_variables, &self.options.context)?,
+            tools,
+            OBSERVATION
+        );
+        let mut model_settings = agent.model_settings.clone();
+        model_settings
+            .stop
+            .get_or_insert_with(Vec::new)
+            .push(format!("\n{}", OBSERVATION));
+        Ok(Agent {
+            instructions: instructions.into(),
+            tools: Vec::new(),
+            registry: None,
+            model_settings,
+            ..agent.clone()
+        })
+    }
+}
+
+// Splits a reply into its thought, requested action and final answer
+fn parse(text: &str) -> (String, Option<ReactAction>, Option<String>) {
+    let mut thought = Vec::new();
+    let mut tool = None;
+    let mut input = Vec::new();
+    let mut final_answer = None::<Vec<&str>>;
+    let mut section = "thought";
+    for line in text.lines() {
+        let trimmed = line.trim_start();
+        if let Some(rest) = trimmed.strip_prefix("Thought:") {
+            section = "thought";
+            thought.push(rest.trim());
+        } else if let Some(rest) = trimmed.strip_prefix("Action Input:") {
+            section = "input";
+            input.push(rest.trim());
+        } else if let Some(rest) = trimmed.strip_prefix("Action:") {
+            section = "action";
+            tool = Some(rest.trim().to_string());
+        } else if let Some(rest) = trimmed.strip_prefix("Final Answer:") {
+            section = "answer";
+            final_answer = Some(vec![rest.trim()]);
+        } else {
+            match section {
+                "thought" => thought.push(line),
+                "input" => input.push(line),
+                "answer" => final_answer.get_or_insert_with(Vec::new).push(line),
+                _ => {}
+            }
+        }
+    }
+
+    let action = tool.filter(|tool| !tool.is_empty()).map(|tool| {
+        let input = input.join("\n");
+        let input = input
+            .trim()
+            .trim_matches('`')
+            .trim_start_matches("json")
+            .trim();
+        ReactAction {
+            tool,
+            input: match input {
+                "" => Value::Object(Default::default()),
+                input => {
+                    serde_json::from_str(input).unwrap_or_else(|_| Value::String(input.to_string()))
+                }
+            },
+        }
+    });
+    // A final answer ends the loop even if an action was also written
+    let action = if final_answer.is_some() { None } else { action };
+    (
+        thought.join("\n").trim().to_string(),
+        action,
+        final_answer.map(|lines| lines.join("\n").trim().to_string()),
+    )
+}
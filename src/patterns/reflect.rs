@@ -0,0 +1,151 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::messages::user_message;
+use crate::orchestration::final_text;
+use crate::provider::{ChatProvider, OpenAIProvider};
+use crate::swarm::Swarm;
+use crate::types::{Agent, Response, RunOptions};
+
+// The critic's review of one draft
+#[derive(Debug, Clone, Serialize)]
+pub struct Critique {
+    pub round: usize,
+    pub draft: String,
+    pub feedback: String,
+    pub approved: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReflectResponse {
+    // The last draft, approved or not
+    pub artifact: String,
+    pub approved: bool,
+    pub critiques: Vec<Critique>,
+    // The generator's conversation, with usage and cost of both agents
+    pub response: Response,
+}
+
+// Generator/critic loop that revises a draft until the critic approves it
+pub struct Reflect<'a, P: ChatProvider = OpenAIProvider> {
+    swarm: &'a Swarm<P>,
+    generator: Agent,
+    critic: Agent,
+    max_rounds: usize,
+    approval_marker: String,
+    options: RunOptions,
+}
+
+impl<'a, P: ChatProvider> Reflect<'a, P> {
+    pub fn new(swarm: &'a Swarm<P>, generator: Agent, critic: Agent) -> Self {
+        Reflect {
+            swarm,
+            generator,
+            critic,
+            max_rounds: 3,
+            approval_marker: "APPROVED".to_string(),
+            options: RunOptions::default(),
+        }
+    }
+
+    // Maximum number of drafts the generator writes
+    pub fn with_max_rounds(mut self, max_rounds: usize) -> Self {
+        self.max_rounds = max_rounds;
+        self
+    }
+
+    // Text the critic replies with to accept a draft; the reply must start with it
+    pub fn with_approval_marker(mut self, marker: impl Into<String>) -> Self {
+        self.approval_marker = marker.into();
+        self
+    }
+
+    // Options applied to every generator and critic run
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub async fn run(
+        &self,
+        messages: Vec<ChatCompletionRequestMessage>,
+    ) -> Result<ReflectResponse> {
+        let mut history = messages.clone();
+        let init_len = history.len();
+        let mut critiques = Vec::new();
+        let mut total = Response::empty();
+        let mut generator = self.generator.clone();
+        let mut context_variables = self.options.context_variables.clone();
+        let mut artifact = String::new();
+        let mut approved = false;
+
+        for round in 1..=self.max_rounds {
+            // 1. Write or revise the draft
+            let options = RunOptions {
+                context_variables: context_variables.clone(),
+                ..self.options.clone()
+            };
+            let draft = self
+                .swarm
+                .run_with_options(generator.clone(), history.clone(), options)
+                .await?;
+            total.absorb(&draft);
+            artifact = final_text(&draft);
+            history.extend(draft.messages);
+            context_variables = draft.context_variables;
+            if let Some(agent) = draft.agent {
+                generator = agent;
+            }
+
+            // 2. Have the critic review it against the original request
+            let mut review = messages.clone();
+            review.push(user_message(&format!(
+                "Review this draft of a response to the conversation above:\n\n{}\n\n\
+                 If it needs no changes, reply with {} only. \
+                 Otherwise give specific, actionable feedback.",
+                artifact, self.approval_marker
+            )));
+            let critique = self
+                .swarm
+                .run_with_options(self.critic.clone(), review, self.options.clone())
+                .await?;
+            total.absorb(&critique);
+            let feedback = final_text(&critique);
+            // Only a reply that opens with the marker approves, so "NOT APPROVED" does not
+            approved = feedback.trim_start().starts_with(&self.approval_marker);
+            if self.options.debug {
+                self.swarm
+                    .debug_event("reflect", format!("round {}: approved={}", round, approved));
+            }
+            critiques.push(Critique {
+                round,
+                draft: artifact.clone(),
+                feedback: feedback.clone(),
+                approved,
+            });
+            if approved {
+                break;
+            }
+
+            // 3. Feed the critique back to the generator
+            history.push(user_message(&format!(
+                "A reviewer gave this feedback on your response:\n\n{}\n\n\
+                 Revise your response to address it.",
+                feedback
+            )));
+        }
+
+        Ok(ReflectResponse {
+            artifact,
+            approved,
+            critiques,
+            response: Response {
+                messages: history[init_len..].to_vec(),
+                agent: Some(generator),
+                context_variables,
+                ..total
+            },
+        })
+    }
+}
@@ -0,0 +1,280 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::messages::map_message_text;
+
+// Extra check on a regex match, e.g. a checksum that rules out random digit runs
+pub type PiiValidator = fn(&str) -> bool;
+
+// A kind of personal data, found by a regex and replaced by a mask
+#[derive(Debug, Clone)]
+pub struct PiiPattern {
+    name: String,
+    regex: Regex,
+    mask: String,
+    validator: Option<PiiValidator>,
+}
+
+impl PiiPattern {
+    pub fn new(name: &str, regex: Regex, mask: &str) -> Self {
+        PiiPattern {
+            name: name.to_string(),
+            regex,
+            mask: mask.to_string(),
+            validator: None,
+        }
+    }
+
+    pub fn with_validator(mut self, validator: PiiValidator) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    pub fn email() -> Self {
+        PiiPattern::new(
+            "email",
+            Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").expect("valid regex"),
+            "[EMAIL]",
+        )
+    }
+
+    // Card numbers of 13 to 19 digits, optionally grouped, that pass the Luhn check
+    pub fn credit_card() -> Self {
+        PiiPattern::new(
+            "credit_card",
+            Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").expect("valid regex"),
+            "[CREDIT_CARD]",
+        )
+        .with_validator(luhn)
+    }
+
+    // International or national numbers such as +1 (555) 123-4567 or 030 1234 5678
+    pub fn phone() -> Self {
+        PiiPattern::new(
+            "phone",
+            Regex::new(
+                r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{2,4}\)|\b\d{2,4})[ .-]?\d{3,4}[ .-]?\d{3,4}\b",
+            )
+            .expect("valid regex"),
+            "[PHONE]",
+        )
+    }
+}
+
+// How many matches of a pattern were masked during a run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Redaction {
+    pub pattern: String,
+    pub count: usize,
+}
+
+// Masks personal data in the messages sent to the model and, optionally, in the
+// tool arguments the model writes
+#[derive(Debug, Clone)]
+pub struct PiiRedactor {
+    patterns: Vec<PiiPattern>,
+    tool_arguments: bool,
+}
+
+impl Default for PiiRedactor {
+    fn default() -> Self {
+        PiiRedactor {
+            patterns: vec![
+                PiiPattern::email(),
+                PiiPattern::credit_card(),
+                PiiPattern::phone(),
+            ],
+            tool_arguments: false,
+        }
+    }
+}
+
+impl PiiRedactor {
+    // Redacts emails, credit card and phone numbers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Starts from an empty pattern set
+    pub fn empty() -> Self {
+        PiiRedactor {
+            patterns: Vec::new(),
+            tool_arguments: false,
+        }
+    }
+
+    // Adds a pattern; patterns apply in order, so earlier masks are not matched again
+    pub fn with_pattern(mut self, pattern: PiiPattern) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    // Whether tool arguments are masked before the tool runs. Off by default, so tools get
+    // the values they were asked to act on; the model's copy is masked either way
+    pub fn with_tool_arguments(mut self, tool_arguments: bool) -> Self {
+        self.tool_arguments = tool_arguments;
+        self
+    }
+
+    // Masks every pattern in the text, adding what was masked to `redactions`
+    pub fn redact(&self, text: &str, redactions: &mut Vec<Redaction>) -> String {
+        let mut text = text.to_string();
+        for pattern in &self.patterns {
+            let mut count = 0;
+            text = pattern
+                .regex
+                .replace_all(&text, |captures: &regex::Captures| {
+                    let found = &captures[0];
+                    if pattern.validator.is_none_or(|validate| validate(found)) {
+                        count += 1;
+                        pattern.mask.clone()
+                    } else {
+                        found.to_string()
+                    }
+                })
+                .into_owned();
+            if count > 0 {
+                record(redactions, &pattern.name, count);
+            }
+        }
+        text
+    }
+
+    // Masks the text of messages, and the arguments of tool calls they carry
+    pub(crate) fn redact_messages(
+        &self,
+        messages: &mut [ChatCompletionRequestMessage],
+        redactions: &mut Vec<Redaction>,
+    ) {
+        for message in messages {
+            map_message_text(message, |text| self.redact(text, redactions));
+            if let ChatCompletionRequestMessage::Assistant(message) = message {
+                for tool_call in message.tool_calls.iter_mut().flatten() {
+                    tool_call.function.arguments =
+                        self.redact(&tool_call.function.arguments, redactions);
+                }
+            }
+        }
+    }
+
+    pub(crate) fn redacts_tool_arguments(&self) -> bool {
+        self.tool_arguments
+    }
+}
+
+// Adds the redactions of another run, keeping one entry per pattern
+pub fn merge_redactions(redactions: &mut Vec<Redaction>, other: &[Redaction]) {
+    for redaction in other {
+        record(redactions, &redaction.pattern, redaction.count);
+    }
+}
+
+// Adds a count to the pattern's entry, keeping one entry per pattern
+fn record(redactions: &mut Vec<Redaction>, pattern: &str, count: usize) {
+    match redactions.iter_mut().find(|r| r.pattern == pattern) {
+        Some(redaction) => redaction.count += count,
+        None => redactions.push(Redaction {
+            pattern: pattern.to_string(),
+            count,
+        }),
+    }
+}
+
+// Luhn checksum used by payment card numbers
+fn luhn(text: &str) -> bool {
+    let digits: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum % 10 == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redact(text: &str) -> (String, Vec<Redaction>) {
+        let mut redactions = Vec::new();
+        let text = PiiRedactor::new().redact(text, &mut redactions);
+        (text, redactions)
+    }
+
+    #[test]
+    fn luhn_accepts_valid_card_numbers() {
+        assert!(luhn("4111111111111111"));
+        assert!(luhn("4111 1111 1111 1111"));
+        assert!(luhn("5500-0000-0000-0004"));
+        assert!(luhn("378282246310005"));
+    }
+
+    #[test]
+    fn luhn_rejects_invalid_card_numbers() {
+        assert!(!luhn("4111111111111112"));
+        assert!(!luhn("1234567890123"));
+    }
+
+    #[test]
+    fn masks_emails() {
+        let (text, redactions) = redact("Write to jane.doe+news@example.co.uk today");
+        assert_eq!(text, "Write to [EMAIL] today");
+        assert_eq!(
+            redactions,
+            [Redaction {
+                pattern: "email".to_string(),
+                count: 1
+            }]
+        );
+    }
+
+    #[test]
+    fn masks_card_numbers_that_pass_luhn() {
+        let (text, _) = redact("Card 4111 1111 1111 1111, ref 4111111111111112");
+        assert!(text.starts_with("Card [CREDIT_CARD], ref "));
+        assert!(!text.contains("4111 1111"));
+        assert!(!text.ends_with("[CREDIT_CARD]"));
+    }
+
+    #[test]
+    fn masks_phone_numbers() {
+        let (text, _) = redact("Call +1 (555) 123-4567 or 030 1234 5678.");
+        assert_eq!(text, "Call [PHONE] or [PHONE].");
+    }
+
+    #[test]
+    fn leaves_plain_text_alone() {
+        let (text, redactions) = redact("Order 42 ships in 3 days");
+        assert_eq!(text, "Order 42 ships in 3 days");
+        assert!(redactions.is_empty());
+    }
+
+    #[test]
+    fn merges_redactions_per_pattern() {
+        let mut redactions = vec![Redaction {
+            pattern: "email".to_string(),
+            count: 1,
+        }];
+        merge_redactions(
+            &mut redactions,
+            &[
+                Redaction {
+                    pattern: "email".to_string(),
+                    count: 2,
+                },
+                Redaction {
+                    pattern: "phone".to_string(),
+                    count: 1,
+                },
+            ],
+        );
+        assert_eq!(redactions[0].count, 3);
+        assert_eq!(redactions[1].pattern, "phone");
+    }
+}
@@ -0,0 +1,68 @@
+use async_openai::{config::AzureConfig, Client};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::{ChatProvider, ChatRequest, ChatResponse, ChatStream, OpenAIProvider};
+use crate::error::Result;
+
+// Chat provider for Azure OpenAI, routing each model to a deployment.
+// Models without an explicit mapping are sent to a deployment of the same name.
+pub struct AzureOpenAIProvider {
+    config: AzureConfig,
+    deployments: HashMap<String, String>,
+    providers: Mutex<HashMap<String, OpenAIProvider<AzureConfig>>>,
+}
+
+impl AzureOpenAIProvider {
+    pub fn new(config: AzureConfig) -> Self {
+        AzureOpenAIProvider {
+            config,
+            deployments: HashMap::new(),
+            providers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    // Maps an agent model name to an Azure deployment ID
+    pub fn with_deployment(mut self, model: &str, deployment_id: &str) -> Self {
+        self.deployments
+            .insert(model.to_string(), deployment_id.to_string());
+        self
+    }
+
+    pub fn deployment_for<'a>(&'a self, model: &'a str) -> &'a str {
+        self.deployments
+            .get(model)
+            .map(String::as_str)
+            .unwrap_or(model)
+    }
+
+    // Returns a provider bound to the deployment serving the model
+    fn provider_for(&self, model: &str) -> OpenAIProvider<AzureConfig> {
+        let deployment_id = self.deployment_for(model);
+        self.providers
+            .lock()
+            .unwrap()
+            .entry(deployment_id.to_string())
+            .or_insert_with(|| {
+                let config = self.config.clone().with_deployment_id(deployment_id);
+                OpenAIProvider::new(Client::with_config(config))
+            })
+            .clone()
+    }
+}
+
+#[async_trait]
+impl ChatProvider for AzureOpenAIProvider {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
+        self.provider_for(&request.model).chat(request).await
+    }
+
+    async fn chat_stream(&self, request: ChatRequest) -> Result<ChatStream> {
+        self.provider_for(&request.model).chat_stream(request).await
+    }
+
+    fn system(&self) -> &'static str {
+        "az.ai.openai"
+    }
+}
@@ -0,0 +1,131 @@
+use async_openai::types::{
+    ChatCompletionMessageToolCall, ChatCompletionResponseMessage, ChatCompletionToolType,
+    FinishReason, FunctionCall, Role,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use super::{single_chunk, ChatProvider, ChatRequest, ChatResponse, ChatStream};
+use crate::error::{Result, SwarmError};
+use crate::types::Usage;
+
+// Scripted provider for tests: returns queued replies in order and keeps every request
+// it received, so agents and tools can be exercised without network access
+#[derive(Debug, Default)]
+pub struct MockProvider {
+    responses: Mutex<VecDeque<ChatResponse>>,
+    requests: Mutex<Vec<ChatRequest>>,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        MockProvider::default()
+    }
+
+    // Queues a plain-text assistant reply
+    pub fn with_text(self, text: impl Into<String>) -> Self {
+        self.with_message(assistant(Some(text.into()), None))
+    }
+
+    // Queues a text reply the model stopped at `max_tokens`
+    pub fn with_cut_off_text(self, text: impl Into<String>) -> Self {
+        self.with_response(ChatResponse {
+            message: assistant(Some(text.into()), None),
+            usage: Some(Usage::default()),
+            system_fingerprint: None,
+            finish_reason: Some(FinishReason::Length),
+        })
+    }
+
+    // Queues a reply calling a single tool
+    pub fn with_tool_call(self, name: &str, arguments: Value) -> Self {
+        self.with_tool_calls(vec![(name, arguments)])
+    }
+
+    // Queues a reply calling several tools at once
+    pub fn with_tool_calls(self, calls: Vec<(&str, Value)>) -> Self {
+        let calls = calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, (name, arguments))| ChatCompletionMessageToolCall {
+                id: format!("call_{}", index),
+                r#type: ChatCompletionToolType::Function,
+                function: FunctionCall {
+                    name: name.to_string(),
+                    arguments: arguments.to_string(),
+                },
+            })
+            .collect();
+        self.with_message(assistant(None, Some(calls)))
+    }
+
+    pub fn with_message(self, message: ChatCompletionResponseMessage) -> Self {
+        self.with_response(ChatResponse {
+            message,
+            usage: Some(Usage::default()),
+            system_fingerprint: None,
+            finish_reason: None,
+        })
+    }
+
+    // Sets the token usage reported by the reply queued last
+    pub fn with_usage(self, usage: Usage) -> Self {
+        if let Some(response) = self.responses.lock().unwrap().back_mut() {
+            response.usage = Some(usage);
+        }
+        self
+    }
+
+    pub fn with_response(self, response: ChatResponse) -> Self {
+        self.responses.lock().unwrap().push_back(response);
+        self
+    }
+
+    // Requests received so far, in order
+    pub fn requests(&self) -> Vec<ChatRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    // Number of queued replies not yet returned
+    pub fn remaining(&self) -> usize {
+        self.responses.lock().unwrap().len()
+    }
+
+    fn next(&self, request: ChatRequest) -> Result<ChatResponse> {
+        self.requests.lock().unwrap().push(request);
+        self.responses.lock().unwrap().pop_front().ok_or_else(|| {
+            SwarmError::Provider("mock provider has no scripted responses left".to_string())
+        })
+    }
+}
+
+#[async_trait]
+impl ChatProvider for MockProvider {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
+        self.next(request)
+    }
+
+    async fn chat_stream(&self, request: ChatRequest) -> Result<ChatStream> {
+        self.next(request).map(single_chunk)
+    }
+
+    fn system(&self) -> &'static str {
+        "mock"
+    }
+}
+
+fn assistant(
+    content: Option<String>,
+    tool_calls: Option<Vec<ChatCompletionMessageToolCall>>,
+) -> ChatCompletionResponseMessage {
+    #[allow(deprecated)]
+    ChatCompletionResponseMessage {
+        content,
+        refusal: None,
+        tool_calls,
+        role: Role::Assistant,
+        function_call: None,
+    }
+}
@@ -0,0 +1,112 @@
+use async_openai::types::{
+    ChatCompletionMessageToolCallChunk, ChatCompletionRequestMessage,
+    ChatCompletionResponseMessage, ChatCompletionStreamResponseDelta, FinishReason,
+    FunctionCallStream,
+};
+use async_trait::async_trait;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+
+use crate::error::Result;
+use crate::types::{ModelSettings, Tool, Usage};
+
+mod azure;
+mod mock;
+mod ollama;
+mod openai;
+mod vcr;
+
+pub use azure::AzureOpenAIProvider;
+pub use mock::MockProvider;
+pub use ollama::{OllamaProvider, ToolCallingMode, DEFAULT_OLLAMA_URL};
+pub use openai::OpenAIProvider;
+pub use vcr::{Interaction, RecordedResponse, RecordingProvider, ReplayProvider};
+
+// Provider-agnostic chat request assembled by the run loop
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatRequest {
+    pub model: String,
+    pub messages: Vec<ChatCompletionRequestMessage>,
+    pub tools: Vec<Tool>,
+    pub parallel_tool_calls: bool,
+    pub settings: ModelSettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatResponse {
+    pub message: ChatCompletionResponseMessage,
+    pub usage: Option<Usage>,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+    #[serde(default)]
+    pub finish_reason: Option<FinishReason>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatChunk {
+    pub delta: Option<ChatCompletionStreamResponseDelta>,
+    pub usage: Option<Usage>,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+    // Set on the chunk that ends the choice
+    #[serde(default)]
+    pub finish_reason: Option<FinishReason>,
+}
+
+pub type ChatStream = BoxStream<'static, Result<ChatChunk>>;
+
+// Backend capable of serving chat completions for the swarm
+#[async_trait]
+pub trait ChatProvider: Send + Sync {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse>;
+
+    async fn chat_stream(&self, request: ChatRequest) -> Result<ChatStream>;
+
+    // Identifies the backend in telemetry, as the GenAI `gen_ai.system` attribute
+    fn system(&self) -> &'static str {
+        "openai"
+    }
+}
+
+// Surfaces a complete response as a stream of one chunk
+pub(crate) fn single_chunk(response: ChatResponse) -> ChatStream {
+    let ChatResponse {
+        message,
+        usage,
+        system_fingerprint,
+        finish_reason,
+    } = response;
+    let tool_calls = message.tool_calls.map(|calls| {
+        calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| ChatCompletionMessageToolCallChunk {
+                index: index as i32,
+                id: Some(call.id),
+                r#type: Some(call.r#type),
+                function: Some(FunctionCallStream {
+                    name: Some(call.function.name),
+                    arguments: Some(call.function.arguments),
+                }),
+            })
+            .collect()
+    });
+    #[allow(deprecated)]
+    let delta = ChatCompletionStreamResponseDelta {
+        content: message.content,
+        function_call: None,
+        tool_calls,
+        role: Some(message.role),
+        refusal: message.refusal,
+    };
+    futures::stream::once(async move {
+        Ok(ChatChunk {
+            delta: Some(delta),
+            usage,
+            system_fingerprint,
+            finish_reason,
+        })
+    })
+    .boxed()
+}
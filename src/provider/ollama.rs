@@ -0,0 +1,246 @@
+use async_openai::{
+    config::OpenAIConfig,
+    error::OpenAIError,
+    types::{
+        ChatCompletionMessageToolCall, ChatCompletionRequestAssistantMessage,
+        ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestMessage,
+        ChatCompletionRequestSystemMessage, ChatCompletionRequestSystemMessageContent,
+        ChatCompletionRequestToolMessageContent, ChatCompletionRequestUserMessage,
+        ChatCompletionRequestUserMessageContent, ChatCompletionToolType, FunctionCall,
+    },
+    Client,
+};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use super::{single_chunk, ChatProvider, ChatRequest, ChatResponse, ChatStream, OpenAIProvider};
+use crate::error::{Result, SwarmError};
+use crate::schema::strip_code_fence;
+
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434/v1";
+
+// How tool definitions are passed to the local model
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCallingMode {
+    // Always use the native `tools` request field
+    Native,
+    // Always describe tools in the prompt and parse JSON replies
+    Emulated,
+    // Try native calling and fall back to emulation per model when rejected
+    Auto,
+}
+
+// Chat provider for Ollama's OpenAI-compatible endpoint
+pub struct OllamaProvider {
+    inner: OpenAIProvider,
+    mode: ToolCallingMode,
+    // Models that rejected native tool calling in Auto mode
+    emulated_models: Mutex<HashSet<String>>,
+    call_counter: AtomicUsize,
+}
+
+impl Default for OllamaProvider {
+    fn default() -> Self {
+        Self::new(DEFAULT_OLLAMA_URL)
+    }
+}
+
+impl OllamaProvider {
+    pub fn new(base_url: &str) -> Self {
+        // Ollama ignores the key, but the client requires one to be set
+        let config = OpenAIConfig::new()
+            .with_api_base(base_url)
+            .with_api_key("ollama");
+        OllamaProvider {
+            inner: OpenAIProvider::new(Client::with_config(config)),
+            mode: ToolCallingMode::Auto,
+            emulated_models: Mutex::new(HashSet::new()),
+            call_counter: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn with_tool_calling_mode(mut self, mode: ToolCallingMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    // Decides whether the request should use prompt-based tool emulation
+    fn should_emulate(&self, request: &ChatRequest) -> bool {
+        if request.tools.is_empty() {
+            return false;
+        }
+        match self.mode {
+            ToolCallingMode::Native => false,
+            ToolCallingMode::Emulated => true,
+            ToolCallingMode::Auto => self
+                .emulated_models
+                .lock()
+                .unwrap()
+                .contains(&request.model),
+        }
+    }
+
+    // Records a model as lacking native tools if the error says so
+    fn fall_back_on(&self, request: &ChatRequest, error: &SwarmError) -> bool {
+        let unsupported = self.mode == ToolCallingMode::Auto
+            && !request.tools.is_empty()
+            && matches!(
+                error,
+                SwarmError::Api(OpenAIError::ApiError(e)) if e.message.contains("does not support tools")
+            );
+        if unsupported {
+            self.emulated_models
+                .lock()
+                .unwrap()
+                .insert(request.model.clone());
+        }
+        unsupported
+    }
+
+    async fn chat_emulated(&self, request: ChatRequest) -> Result<ChatResponse> {
+        let mut response = self.inner.chat(emulate_tools(request)).await?;
+        let message = &mut response.message;
+        if let Some(tool_calls) = message
+            .content
+            .as_deref()
+            .and_then(|content| self.parse_tool_calls(content))
+        {
+            message.content = None;
+            message.tool_calls = Some(tool_calls);
+        }
+        Ok(response)
+    }
+
+    // Extracts `{"tool_calls": [...]}` replies produced under emulation
+    fn parse_tool_calls(&self, content: &str) -> Option<Vec<ChatCompletionMessageToolCall>> {
+        let parsed: Value = serde_json::from_str(strip_code_fence(content)).ok()?;
+        let calls = parsed.get("tool_calls")?.as_array()?;
+
+        calls
+            .iter()
+            .map(|call| {
+                let name = call.get("name")?.as_str()?.to_string();
+                let arguments = call.get("arguments").cloned().unwrap_or(json!({}));
+                let id = self.call_counter.fetch_add(1, Ordering::Relaxed);
+                Some(ChatCompletionMessageToolCall {
+                    id: format!("call_{}", id),
+                    r#type: ChatCompletionToolType::Function,
+                    function: FunctionCall {
+                        name,
+                        arguments: arguments.to_string(),
+                    },
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl ChatProvider for OllamaProvider {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
+        if self.should_emulate(&request) {
+            return self.chat_emulated(request).await;
+        }
+        match self.inner.chat(request.clone()).await {
+            Err(e) if self.fall_back_on(&request, &e) => self.chat_emulated(request).await,
+            result => result,
+        }
+    }
+
+    async fn chat_stream(&self, request: ChatRequest) -> Result<ChatStream> {
+        if !self.should_emulate(&request) {
+            match self.inner.chat_stream(request.clone()).await {
+                Err(e) if self.fall_back_on(&request, &e) => {}
+                result => return result,
+            }
+        }
+
+        // Emulated replies must be parsed whole, so surface them as a single chunk
+        let response = self.chat_emulated(request).await?;
+        Ok(single_chunk(response))
+    }
+
+    fn system(&self) -> &'static str {
+        "ollama"
+    }
+}
+
+// Rewrites a request so tools are described in the prompt instead of the API
+fn emulate_tools(mut request: ChatRequest) -> ChatRequest {
+    // 1. Describe the available tools in a system message
+    let catalog: Vec<Value> = request
+        .tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "name": tool.name,
+                "description": tool.description,
+                "parameters": tool.parameters,
+            })
+        })
+        .collect();
+    let prompt = format!(
+        "You can call the following tools:\n{}\n\n\
+         To call tools, reply with only a JSON object of the form \
+         {{\"tool_calls\": [{{\"name\": \"<tool name>\", \"arguments\": {{...}}}}]}}. \
+         Otherwise reply normally.",
+        Value::Array(catalog)
+    );
+    let mut messages = vec![ChatCompletionRequestMessage::System(
+        ChatCompletionRequestSystemMessage {
+            content: ChatCompletionRequestSystemMessageContent::Text(prompt),
+            name: None,
+        },
+    )];
+
+    // 2. Replay earlier tool traffic as plain text the model understands
+    for message in request.messages.drain(..) {
+        messages.push(match message {
+            ChatCompletionRequestMessage::Assistant(assistant)
+                if assistant.tool_calls.is_some() =>
+            {
+                let calls: Vec<Value> = assistant
+                    .tool_calls
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|call| {
+                        json!({
+                            "name": call.function.name,
+                            "arguments": serde_json::from_str::<Value>(&call.function.arguments)
+                                .unwrap_or(Value::String(call.function.arguments)),
+                        })
+                    })
+                    .collect();
+                ChatCompletionRequestMessage::Assistant(ChatCompletionRequestAssistantMessage {
+                    content: Some(ChatCompletionRequestAssistantMessageContent::Text(
+                        json!({ "tool_calls": calls }).to_string(),
+                    )),
+                    ..Default::default()
+                })
+            }
+            ChatCompletionRequestMessage::Tool(tool) => {
+                let content = match tool.content {
+                    ChatCompletionRequestToolMessageContent::Text(text) => text,
+                    ChatCompletionRequestToolMessageContent::Array(parts) => {
+                        serde_json::to_string(&parts).unwrap_or_default()
+                    }
+                };
+                ChatCompletionRequestMessage::User(ChatCompletionRequestUserMessage {
+                    content: ChatCompletionRequestUserMessageContent::Text(format!(
+                        "Result of tool call {}: {}",
+                        tool.tool_call_id, content
+                    )),
+                    name: None,
+                })
+            }
+            other => other,
+        });
+    }
+
+    request.messages = messages;
+    request.tools.clear();
+    request
+}
@@ -0,0 +1,133 @@
+use async_openai::{
+    config::{Config, OpenAIConfig},
+    types::{
+        ChatCompletionStreamOptions, ChatCompletionTool, ChatCompletionToolArgs,
+        ChatCompletionToolType, CreateChatCompletionRequest, CreateChatCompletionRequestArgs,
+        FunctionObjectArgs, Stop,
+    },
+    Client,
+};
+use async_trait::async_trait;
+use futures::StreamExt;
+
+use super::{ChatChunk, ChatProvider, ChatRequest, ChatResponse, ChatStream};
+use crate::error::{Result, SwarmError};
+use crate::types::Usage;
+
+// Chat provider backed by the OpenAI API via async_openai
+#[derive(Debug, Clone, Default)]
+pub struct OpenAIProvider<C: Config = OpenAIConfig> {
+    client: Client<C>,
+}
+
+impl<C: Config> OpenAIProvider<C> {
+    pub fn new(client: Client<C>) -> Self {
+        OpenAIProvider { client }
+    }
+
+    pub fn client(&self) -> &Client<C> {
+        &self.client
+    }
+}
+
+// Converts a provider-agnostic request into an OpenAI request
+pub(crate) fn build_openai_request(request: ChatRequest) -> Result<CreateChatCompletionRequest> {
+    // 1. Convert agent tools to ChatCompletionTool format
+    let tools = request
+        .tools
+        .iter()
+        .map(|f| {
+            let mut function = FunctionObjectArgs::default();
+            function
+                .name(&f.name)
+                .description(&f.description)
+                .parameters(f.parameters.clone());
+            if f.strict {
+                function.strict(true);
+            }
+            ChatCompletionToolArgs::default()
+                .r#type(ChatCompletionToolType::Function)
+                .function(function.build()?)
+                .build()
+        })
+        .collect::<std::result::Result<Vec<ChatCompletionTool>, _>>()?;
+
+    // 2. Build chat completion request based on tools presence
+    let mut args = CreateChatCompletionRequestArgs::default();
+    args.model(request.model).messages(request.messages);
+    if !tools.is_empty() {
+        args.tools(tools)
+            .parallel_tool_calls(request.parallel_tool_calls);
+    }
+
+    // 3. Forward the agent's model settings
+    let settings = request.settings;
+    if let Some(max_tokens) = settings.max_tokens {
+        args.max_tokens(max_tokens);
+    }
+    if let Some(temperature) = settings.temperature {
+        args.temperature(temperature);
+    }
+    if let Some(top_p) = settings.top_p {
+        args.top_p(top_p);
+    }
+    if let Some(stop) = settings.stop {
+        args.stop(Stop::StringArray(stop));
+    }
+    if let Some(presence_penalty) = settings.presence_penalty {
+        args.presence_penalty(presence_penalty);
+    }
+    if let Some(frequency_penalty) = settings.frequency_penalty {
+        args.frequency_penalty(frequency_penalty);
+    }
+    if let Some(seed) = settings.seed {
+        args.seed(seed);
+    }
+    if let Some(response_format) = settings.response_format {
+        args.response_format(response_format);
+    }
+    Ok(args.build()?)
+}
+
+#[async_trait]
+impl<C: Config + Send + Sync> ChatProvider for OpenAIProvider<C> {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
+        let request = build_openai_request(request)?;
+
+        // Send request and return first choice message
+        let response = self.client.chat().create(request).await?;
+        let choice = response
+            .choices
+            .into_iter()
+            .next()
+            .ok_or(SwarmError::EmptyChoices)?;
+        Ok(ChatResponse {
+            message: choice.message,
+            usage: response.usage.map(Usage::from),
+            system_fingerprint: response.system_fingerprint,
+            finish_reason: choice.finish_reason,
+        })
+    }
+
+    async fn chat_stream(&self, request: ChatRequest) -> Result<ChatStream> {
+        let mut request = build_openai_request(request)?;
+        request.stream_options = Some(ChatCompletionStreamOptions {
+            include_usage: true,
+        });
+        let stream = self.client.chat().create_stream(request).await?;
+
+        // Keep the first choice of every chunk; the last one only carries usage
+        Ok(stream
+            .map(|chunk| {
+                let chunk = chunk?;
+                let choice = chunk.choices.into_iter().next();
+                Ok(ChatChunk {
+                    finish_reason: choice.as_ref().and_then(|choice| choice.finish_reason),
+                    delta: choice.map(|choice| choice.delta),
+                    usage: chunk.usage.map(Usage::from),
+                    system_fingerprint: chunk.system_fingerprint,
+                })
+            })
+            .boxed())
+    }
+}
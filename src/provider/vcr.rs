@@ -0,0 +1,179 @@
+use async_trait::async_trait;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{ChatChunk, ChatProvider, ChatRequest, ChatResponse, ChatStream};
+use crate::error::{Result, SwarmError};
+
+// One recorded provider call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: Value,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordedResponse {
+    Chat(ChatResponse),
+    Stream(Vec<ChatChunk>),
+}
+
+// Wraps a provider and saves every request/response pair to a cassette file
+pub struct RecordingProvider<P: ChatProvider> {
+    inner: P,
+    path: PathBuf,
+    interactions: tokio::sync::Mutex<Vec<Interaction>>,
+}
+
+impl<P: ChatProvider> RecordingProvider<P> {
+    // Starts a new cassette at `path`, replacing any existing recording
+    pub fn new(inner: P, path: impl Into<PathBuf>) -> Self {
+        RecordingProvider {
+            inner,
+            path: path.into(),
+            interactions: tokio::sync::Mutex::new(Vec::new()),
+        }
+    }
+
+    // Appends the interaction and rewrites the cassette
+    async fn record(&self, request: &ChatRequest, response: RecordedResponse) -> Result<()> {
+        let mut interactions = self.interactions.lock().await;
+        interactions.push(Interaction {
+            request: request_key(request)?,
+            response,
+        });
+        let json = serde_json::to_vec_pretty(&*interactions)
+            .map_err(|e| SwarmError::Provider(format!("failed to encode cassette: {}", e)))?;
+        tokio::fs::write(&self.path, json).await.map_err(|e| {
+            SwarmError::Provider(format!(
+                "failed to write cassette {}: {}",
+                self.path.display(),
+                e
+            ))
+        })
+    }
+}
+
+#[async_trait]
+impl<P: ChatProvider> ChatProvider for RecordingProvider<P> {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
+        let response = self.inner.chat(request.clone()).await?;
+        self.record(&request, RecordedResponse::Chat(response.clone()))
+            .await?;
+        Ok(response)
+    }
+
+    // Buffers the whole stream so it can be recorded before being replayed to the caller
+    async fn chat_stream(&self, request: ChatRequest) -> Result<ChatStream> {
+        let chunks: Vec<Result<ChatChunk>> = self
+            .inner
+            .chat_stream(request.clone())
+            .await?
+            .collect()
+            .await;
+        if chunks.iter().all(|chunk| chunk.is_ok()) {
+            let recorded = chunks
+                .iter()
+                .filter_map(|chunk| chunk.as_ref().ok().cloned())
+                .collect();
+            self.record(&request, RecordedResponse::Stream(recorded))
+                .await?;
+        }
+        Ok(futures::stream::iter(chunks).boxed())
+    }
+
+    fn system(&self) -> &'static str {
+        self.inner.system()
+    }
+}
+
+// Serves responses from a cassette instead of calling an API. Each recorded
+// interaction answers one identical request, so runs are deterministic even with
+// concurrent calls; a request that was never recorded fails.
+pub struct ReplayProvider {
+    path: PathBuf,
+    interactions: Mutex<Vec<Option<Interaction>>>,
+}
+
+impl ReplayProvider {
+    pub fn new(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let json = std::fs::read(&path).map_err(|e| {
+            SwarmError::Provider(format!("failed to read cassette {}: {}", path.display(), e))
+        })?;
+        let interactions: Vec<Interaction> = serde_json::from_slice(&json).map_err(|e| {
+            SwarmError::Provider(format!("invalid cassette {}: {}", path.display(), e))
+        })?;
+        Ok(ReplayProvider {
+            path,
+            interactions: Mutex::new(interactions.into_iter().map(Some).collect()),
+        })
+    }
+
+    // Number of recorded interactions not yet replayed
+    pub fn remaining(&self) -> usize {
+        self.interactions
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|interaction| interaction.is_some())
+            .count()
+    }
+
+    // Takes the first unused interaction recorded for this request
+    fn take(&self, request: &ChatRequest) -> Result<RecordedResponse> {
+        let key = request_key(request)?;
+        self.interactions
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|interaction| {
+                interaction
+                    .as_ref()
+                    .is_some_and(|interaction| interaction.request == key)
+            })
+            .and_then(Option::take)
+            .map(|interaction| interaction.response)
+            .ok_or_else(|| {
+                SwarmError::Provider(format!(
+                    "no recorded interaction in {} matches the request to {}",
+                    self.path.display(),
+                    request.model
+                ))
+            })
+    }
+}
+
+#[async_trait]
+impl ChatProvider for ReplayProvider {
+    async fn chat(&self, request: ChatRequest) -> Result<ChatResponse> {
+        match self.take(&request)? {
+            RecordedResponse::Chat(response) => Ok(response),
+            RecordedResponse::Stream(_) => Err(SwarmError::Provider(
+                "recorded interaction was streamed, replay it with streaming enabled".to_string(),
+            )),
+        }
+    }
+
+    async fn chat_stream(&self, request: ChatRequest) -> Result<ChatStream> {
+        match self.take(&request)? {
+            RecordedResponse::Stream(chunks) => {
+                Ok(futures::stream::iter(chunks.into_iter().map(Ok)).boxed())
+            }
+            RecordedResponse::Chat(_) => Err(SwarmError::Provider(
+                "recorded interaction was not streamed, replay it with streaming disabled"
+                    .to_string(),
+            )),
+        }
+    }
+}
+
+// Requests are matched by their JSON form
+fn request_key(request: &ChatRequest) -> Result<Value> {
+    serde_json::to_value(request)
+        .map_err(|e| SwarmError::Provider(format!("failed to encode request: {}", e)))
+}
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+// Per-minute quotas for provider calls
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u32>,
+}
+
+// Sliding-window limiter; callers wait for capacity instead of failing
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    window: Mutex<Window>,
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    requests: VecDeque<Instant>,
+    tokens: VecDeque<(Instant, u32)>,
+    token_total: u64,
+}
+
+impl Window {
+    // Drops entries that have left the one-minute window
+    fn prune(&mut self, now: Instant) {
+        while self
+            .requests
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= WINDOW)
+        {
+            self.requests.pop_front();
+        }
+        while let Some((at, tokens)) = self.tokens.front().copied() {
+            if now.duration_since(at) < WINDOW {
+                break;
+            }
+            self.token_total -= tokens as u64;
+            self.tokens.pop_front();
+        }
+    }
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        RateLimiter {
+            limit,
+            window: Mutex::new(Window::default()),
+        }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    // Waits until both request and token quotas have room, then claims a request slot
+    pub async fn acquire(&self) {
+        loop {
+            let now = Instant::now();
+            let mut window = self.window.lock().await;
+            window.prune(now);
+
+            let mut wait_until: Option<Instant> = None;
+            if let Some(rpm) = self.limit.requests_per_minute {
+                if window.requests.len() >= rpm as usize {
+                    wait_until = window.requests.front().map(|at| *at + WINDOW);
+                }
+            }
+            if let Some(tpm) = self.limit.tokens_per_minute {
+                if window.token_total >= tpm as u64 {
+                    let expiry = window.tokens.front().map(|(at, _)| *at + WINDOW);
+                    wait_until = wait_until.max(expiry);
+                }
+            }
+
+            match wait_until {
+                None => {
+                    window.requests.push_back(now);
+                    return;
+                }
+                Some(deadline) => {
+                    drop(window);
+                    tokio::time::sleep_until(deadline).await;
+                }
+            }
+        }
+    }
+
+    // Counts tokens consumed by a completed call against the token quota
+    pub async fn record_tokens(&self, tokens: u32) {
+        if self.limit.tokens_per_minute.is_none() || tokens == 0 {
+            return;
+        }
+        let mut window = self.window.lock().await;
+        window.tokens.push_back((Instant::now(), tokens));
+        window.token_total += tokens as u64;
+    }
+}
@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+
+use crate::definitions::SwarmDefinition;
+use crate::error::{Result, SwarmError};
+use crate::types::{Agent, Instructions, ModelSettings};
+
+// The parts of an agent that can change while runs are in flight
+#[derive(Clone)]
+struct AgentPatch {
+    model: String,
+    instructions: Instructions,
+    model_settings: ModelSettings,
+}
+
+// Model, instructions and model settings per agent name, applied to the active agent
+// at the start of every turn. Updates swap the whole set at once, so a turn never
+// sees half of a reload. Tools and handoffs are fixed when agents are built.
+#[derive(Clone, Default)]
+pub struct LiveAgents {
+    patches: Arc<RwLock<Arc<HashMap<String, AgentPatch>>>>,
+}
+
+impl LiveAgents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Starts from the agents in a config file, see `SwarmDefinition`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let live = LiveAgents::new();
+        live.reload(path)?;
+        Ok(live)
+    }
+
+    // Replaces the current settings with those in the file; on error nothing changes
+    pub fn reload(&self, path: impl AsRef<Path>) -> Result<()> {
+        let definition = SwarmDefinition::from_file(path)?;
+        // An empty file is most likely caught mid-write
+        if definition.agents.is_empty() {
+            return Err(SwarmError::Config("no agents defined".to_string()));
+        }
+        self.update(&definition);
+        Ok(())
+    }
+
+    pub fn update(&self, definition: &SwarmDefinition) {
+        let patches = definition
+            .agents
+            .iter()
+            .map(|agent| {
+                let patch = AgentPatch {
+                    model: agent.model.clone(),
+                    instructions: agent.instructions(),
+                    model_settings: agent.model_settings.clone(),
+                };
+                (agent.name.clone(), patch)
+            })
+            .collect();
+        *self.patches.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(patches);
+    }
+
+    // Brings the agent up to date if the current settings name it
+    pub(crate) fn apply(&self, agent: &mut Agent) {
+        let patches = self
+            .patches
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        if let Some(patch) = patches.get(&agent.name) {
+            agent.model = patch.model.clone();
+            agent.instructions = patch.instructions.clone();
+            agent.model_settings = patch.model_settings.clone();
+        }
+    }
+
+    // Reloads the file whenever it changes until the watcher is dropped. The outcome
+    // of every reload goes to `on_reload`; a failed reload keeps the previous settings.
+    #[cfg(feature = "hot-reload")]
+    pub fn watch(
+        &self,
+        path: impl AsRef<Path>,
+        on_reload: impl Fn(Result<()>) + Send + 'static,
+    ) -> Result<ConfigWatcher> {
+        use notify::{RecursiveMode, Watcher};
+
+        let path = path.as_ref().to_path_buf();
+        let live = self.clone();
+        let watched = path.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+                Ok(event)
+                    if (event.kind.is_modify() || event.kind.is_create())
+                        && event
+                            .paths
+                            .iter()
+                            .any(|changed| changed.file_name() == watched.file_name()) =>
+                {
+                    on_reload(live.reload(&watched))
+                }
+                Ok(_) => {}
+                Err(e) => on_reload(Err(watch_error(e))),
+            })
+            .map_err(watch_error)?;
+        // Watch the directory, since editors often replace the file rather than write to it
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => std::path::PathBuf::from("."),
+        };
+        watcher
+            .watch(&directory, RecursiveMode::NonRecursive)
+            .map_err(watch_error)?;
+        Ok(ConfigWatcher { _watcher: watcher })
+    }
+}
+
+impl std::fmt::Debug for LiveAgents {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let patches = self
+            .patches
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone();
+        let mut names: Vec<&String> = patches.keys().collect();
+        names.sort_unstable();
+        f.debug_struct("LiveAgents")
+            .field("agents", &names)
+            .finish()
+    }
+}
+
+// Keeps a config file watched; dropping it stops the reloads
+#[cfg(feature = "hot-reload")]
+pub struct ConfigWatcher {
+    _watcher: notify::RecommendedWatcher,
+}
+
+#[cfg(feature = "hot-reload")]
+fn watch_error(error: notify::Error) -> SwarmError {
+    SwarmError::Config(format!("watching config file: {}", error))
+}
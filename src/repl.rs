@@ -0,0 +1,249 @@
+use async_openai::types::{ChatCompletionMessageToolCall, ChatCompletionRequestMessage};
+use futures::StreamExt;
+use std::collections::HashMap;
+use std::io::Write;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, BufReader};
+
+use crate::error::{Result, SwarmError};
+use crate::messages::{message_text, user_message};
+use crate::provider::ChatProvider;
+use crate::swarm::Swarm;
+use crate::types::{Agent, ContextVariables, Response, RunOptions, SwarmEvent};
+
+const USER_COLOR: &str = "\x1b[90m";
+const AGENT_COLOR: &str = "\x1b[94m";
+const TOOL_COLOR: &str = "\x1b[95m";
+const RESET: &str = "\x1b[0m";
+
+// Chats with a swarm in the terminal, keeping the conversation across turns.
+// Besides messages, it accepts:
+// - `/reset` to start over with the starting agent
+// - `/agent <name>` to switch to a registered agent
+// - `/exit` to quit
+pub struct Repl<'a, P: ChatProvider> {
+    swarm: &'a Swarm<P>,
+    agent: Agent,
+    agents: HashMap<String, Agent>,
+    context_variables: ContextVariables,
+    stream: bool,
+}
+
+impl<'a, P: ChatProvider> Repl<'a, P> {
+    pub fn new(swarm: &'a Swarm<P>, agent: Agent) -> Self {
+        Repl {
+            swarm,
+            agents: HashMap::from([(agent.name.clone(), agent.clone())]),
+            agent,
+            context_variables: ContextVariables::new(),
+            stream: true,
+        }
+    }
+
+    // Makes an agent available to `/agent`
+    pub fn with_agent(mut self, agent: Agent) -> Self {
+        self.agents.insert(agent.name.clone(), agent);
+        self
+    }
+
+    pub fn with_context_variables(mut self, context_variables: ContextVariables) -> Self {
+        self.context_variables = context_variables;
+        self
+    }
+
+    // Prints whole messages once the run ends instead of tokens as they arrive
+    pub fn with_stream(mut self, stream: bool) -> Self {
+        self.stream = stream;
+        self
+    }
+
+    // Reads stdin until `/exit` or end of input
+    pub async fn run(self) -> Result<()> {
+        let input = BufReader::new(tokio::io::stdin());
+        self.run_with(input, std::io::stdout()).await
+    }
+
+    // Reads lines from `input` and writes the conversation to `output`
+    pub async fn run_with(
+        self,
+        input: impl AsyncBufRead + Unpin,
+        mut output: impl Write,
+    ) -> Result<()> {
+        let mut lines = input.lines();
+        let mut history: Vec<ChatCompletionRequestMessage> = Vec::new();
+        let mut agent = self.agent.clone();
+        let mut context_variables = self.context_variables.clone();
+        writeln!(
+            output,
+            "Starting swarm-rs REPL. /reset, /agent <name> or /exit."
+        )?;
+        loop {
+            write!(output, "{}User{}: ", USER_COLOR, RESET)?;
+            output.flush()?;
+            let Some(line) = lines.next_line().await? else {
+                break;
+            };
+            let line = line.trim();
+
+            // 1. Commands
+            match line
+                .split_once(' ')
+                .map_or((line, ""), |(c, a)| (c, a.trim()))
+            {
+                ("", _) => continue,
+                ("/exit", _) => break,
+                ("/reset", _) => {
+                    history.clear();
+                    agent = self.agent.clone();
+                    context_variables = self.context_variables.clone();
+                    writeln!(output, "Conversation reset.")?;
+                    continue;
+                }
+                ("/agent", name) => {
+                    match self.agents.get(name) {
+                        Some(next) => {
+                            agent = next.clone();
+                            writeln!(output, "Switched to {}.", agent.name)?;
+                        }
+                        None => {
+                            let mut names: Vec<&str> =
+                                self.agents.keys().map(String::as_str).collect();
+                            names.sort();
+                            writeln!(output, "Unknown agent. Agents: {}", names.join(", "))?;
+                        }
+                    }
+                    continue;
+                }
+                _ => {}
+            }
+
+            // 2. Run on the history so far, printing as the run goes
+            let mut messages = history.clone();
+            messages.push(user_message(line));
+            let options = RunOptions {
+                context_variables: context_variables.clone(),
+                ..Default::default()
+            };
+            let result = if self.stream {
+                self.stream_turn(agent.clone(), messages.clone(), options, &mut output)
+                    .await?
+            } else {
+                let result = self
+                    .swarm
+                    .run_with_options(agent.clone(), messages.clone(), options)
+                    .await;
+                if let Ok(response) = &result {
+                    print_messages(&response.messages, &agent.name, &mut output)?;
+                }
+                result
+            };
+
+            // 3. Keep the exchange, or drop the message if the run failed
+            match result {
+                Ok(response) => {
+                    history = messages;
+                    history.extend(response.messages);
+                    if let Some(next) = response.agent {
+                        agent = next;
+                    }
+                    context_variables = response.context_variables;
+                }
+                Err(e) => writeln!(output, "Error: {}", e)?,
+            }
+        }
+        Ok(())
+    }
+
+    // Prints tokens behind the name of the agent writing them
+    async fn stream_turn(
+        &self,
+        agent: Agent,
+        messages: Vec<ChatCompletionRequestMessage>,
+        options: RunOptions,
+        output: &mut impl Write,
+    ) -> Result<Result<Response>> {
+        let mut events = Box::pin(self.swarm.run_stream(agent, messages, options));
+        // Agent whose reply is being printed on the current line
+        let mut writing: Option<String> = None;
+        while let Some(event) = events.next().await {
+            match event {
+                SwarmEvent::TokenDelta { agent, content } => {
+                    if writing.as_deref() != Some(agent.as_str()) {
+                        end_line(&mut writing, output)?;
+                        write!(output, "{}{}{}: ", AGENT_COLOR, agent, RESET)?;
+                        writing = Some(agent);
+                    }
+                    write!(output, "{}", content)?;
+                    output.flush()?;
+                }
+                SwarmEvent::ToolCallRequested { agent, tool_call } => {
+                    end_line(&mut writing, output)?;
+                    print_tool_call(&agent, &tool_call, output)?;
+                }
+                SwarmEvent::RunCompleted(response) => {
+                    end_line(&mut writing, output)?;
+                    return Ok(Ok(response));
+                }
+                SwarmEvent::RunFailed(e) => {
+                    end_line(&mut writing, output)?;
+                    return Ok(Err(e));
+                }
+                _ => {}
+            }
+        }
+        Ok(Err(SwarmError::Provider(
+            "run ended without a result".to_string(),
+        )))
+    }
+}
+
+// Chats with `agent` on stdin and stdout, like Swarm's Python `run_demo_loop`
+pub async fn run_demo_loop<P: ChatProvider>(swarm: &Swarm<P>, agent: Agent) -> Result<()> {
+    Repl::new(swarm, agent).run().await
+}
+
+fn end_line(writing: &mut Option<String>, output: &mut impl Write) -> std::io::Result<()> {
+    if writing.take().is_some() {
+        writeln!(output)?;
+    }
+    Ok(())
+}
+
+fn print_tool_call(
+    agent: &str,
+    tool_call: &ChatCompletionMessageToolCall,
+    output: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(
+        output,
+        "{}{}{}: {}{}{}({})",
+        AGENT_COLOR,
+        agent,
+        RESET,
+        TOOL_COLOR,
+        tool_call.function.name,
+        RESET,
+        tool_call.function.arguments
+    )
+}
+
+// Prints the assistant replies and tool calls of a finished run
+fn print_messages(
+    messages: &[ChatCompletionRequestMessage],
+    agent: &str,
+    output: &mut impl Write,
+) -> std::io::Result<()> {
+    for message in messages {
+        let ChatCompletionRequestMessage::Assistant(assistant) = message else {
+            continue;
+        };
+        let agent = assistant.name.as_deref().unwrap_or(agent);
+        let text = message_text(message);
+        if !text.is_empty() {
+            writeln!(output, "{}{}{}: {}", AGENT_COLOR, agent, RESET, text)?;
+        }
+        for tool_call in assistant.tool_calls.iter().flatten() {
+            print_tool_call(agent, tool_call, output)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::sync::Arc;
+
+use crate::embeddings::Embedder;
+use crate::error::Result;
+use crate::vector_store::{ScoredRecord, VectorRecord, VectorStore};
+
+// Payload key holding a document's text in the vector store
+pub const TEXT_KEY: &str = "text";
+
+// Texts are embedded this many at a time while ingesting
+const INGEST_BATCH_SIZE: usize = 64;
+
+// A piece of text to make searchable; ingesting an existing id replaces it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Document {
+    pub id: String,
+    pub text: String,
+    #[serde(default)]
+    pub metadata: Map<String, Value>,
+}
+
+impl Document {
+    pub fn new(id: impl Into<String>, text: impl Into<String>) -> Self {
+        Document {
+            id: id.into(),
+            text: text.into(),
+            metadata: Map::new(),
+        }
+    }
+
+    pub fn with_metadata(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.metadata.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+// Documents searchable by meaning: an embedder paired with the vector store it fills
+#[derive(Clone)]
+pub struct KnowledgeBase {
+    store: Arc<dyn VectorStore>,
+    embedder: Arc<dyn Embedder>,
+    top_k: usize,
+}
+
+impl KnowledgeBase {
+    pub fn new(store: impl VectorStore + 'static, embedder: impl Embedder + 'static) -> Self {
+        Self::from_arcs(Arc::new(store), Arc::new(embedder))
+    }
+
+    // Shares a store or embedder with other components
+    pub fn from_arcs(store: Arc<dyn VectorStore>, embedder: Arc<dyn Embedder>) -> Self {
+        KnowledgeBase {
+            store,
+            embedder,
+            top_k: 4,
+        }
+    }
+
+    // Results returned when a search does not ask for a number
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k;
+        self
+    }
+
+    pub fn top_k(&self) -> usize {
+        self.top_k
+    }
+
+    pub fn store(&self) -> &Arc<dyn VectorStore> {
+        &self.store
+    }
+
+    // Embeds and stores the documents, returning how many were stored
+    pub async fn ingest(&self, documents: Vec<Document>) -> Result<usize> {
+        let mut stored = 0;
+        for batch in documents.chunks(INGEST_BATCH_SIZE) {
+            let texts = batch.iter().map(|document| document.text.clone()).collect();
+            let vectors = self.embedder.embed(texts).await?;
+            let records: Vec<VectorRecord> = batch
+                .iter()
+                .zip(vectors)
+                .map(|(document, vector)| {
+                    let mut payload = document.metadata.clone();
+                    payload.insert(TEXT_KEY.to_string(), Value::String(document.text.clone()));
+                    VectorRecord {
+                        id: document.id.clone(),
+                        vector,
+                        payload,
+                    }
+                })
+                .collect();
+            stored += records.len();
+            self.store.upsert(records).await?;
+        }
+        Ok(stored)
+    }
+
+    // Documents closest to the query, best first; `top_k` defaults to the configured one
+    pub async fn search(&self, query: &str, top_k: Option<usize>) -> Result<Vec<ScoredRecord>> {
+        let vector = self
+            .embedder
+            .embed(vec![query.to_string()])
+            .await?
+            .pop()
+            .unwrap_or_default();
+        self.store.query(&vector, top_k.unwrap_or(self.top_k)).await
+    }
+
+    pub async fn delete(&self, ids: &[String]) -> Result<()> {
+        self.store.delete(ids).await
+    }
+}
+
+impl std::fmt::Debug for KnowledgeBase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KnowledgeBase")
+            .field("top_k", &self.top_k)
+            .finish_non_exhaustive()
+    }
+}
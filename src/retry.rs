@@ -0,0 +1,114 @@
+use async_openai::error::OpenAIError;
+use rand::Rng;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::{Result, SwarmError};
+
+// Retry behaviour for provider calls that fail transiently
+#[derive(Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub jitter: bool,
+    pub retry_on: Arc<dyn Fn(&SwarmError) -> bool + Send + Sync>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: true,
+            retry_on: Arc::new(is_transient),
+        }
+    }
+}
+
+impl std::fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("initial_backoff", &self.initial_backoff)
+            .field("max_backoff", &self.max_backoff)
+            .field("multiplier", &self.multiplier)
+            .field("jitter", &self.jitter)
+            .finish()
+    }
+}
+
+impl RetryPolicy {
+    // Policy that makes a single attempt
+    pub fn none() -> Self {
+        RetryPolicy {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn retry_on(
+        mut self,
+        classify: impl Fn(&SwarmError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retry_on = Arc::new(classify);
+        self
+    }
+
+    // Delay before the given retry (1-based), capped and optionally jittered
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.powi(retry.saturating_sub(1) as i32);
+        let delay = self.initial_backoff.mul_f64(factor).min(self.max_backoff);
+        if self.jitter {
+            // Equal jitter: keep half the delay, randomize the rest
+            let half = delay / 2;
+            half + half.mul_f64(rand::thread_rng().gen::<f64>())
+        } else {
+            delay
+        }
+    }
+
+    // Runs the operation, retrying failures the policy classifies as transient
+    pub async fn run<T, F, Fut>(&self, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match operation().await {
+                Err(e) if attempt < self.max_attempts && (self.retry_on)(&e) => {
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+// Default classification: rate limits, server errors, timeouts and dropped connections
+pub fn is_transient(error: &SwarmError) -> bool {
+    match error {
+        SwarmError::Api(OpenAIError::Reqwest(e)) => {
+            e.is_timeout()
+                || e.is_connect()
+                || e.status()
+                    .is_some_and(|status| status.as_u16() == 429 || status.is_server_error())
+        }
+        SwarmError::Api(OpenAIError::ApiError(e)) => {
+            let kind = e.r#type.as_deref().unwrap_or_default();
+            let code = e.code.as_deref().unwrap_or_default();
+            kind == "server_error"
+                || kind == "requests"
+                || kind == "tokens"
+                || code == "rate_limit_exceeded"
+        }
+        SwarmError::Api(OpenAIError::StreamError(_)) => true,
+        _ => false,
+    }
+}
@@ -0,0 +1,61 @@
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+
+// Typed application state (DB pools, user info, ...) shared with context-aware tools
+// and instructions during a run. Clones share the same state, so the caller can keep
+// a handle and inspect what tools changed once the run completes.
+#[derive(Clone, Default)]
+pub struct RunContext {
+    inner: Option<Arc<dyn Any + Send + Sync>>,
+    limits: RunLimits,
+}
+
+// Limits of the run calling a tool, for tools that start runs of their own
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RunLimits {
+    pub max_turns: Option<usize>,
+    // What is left of the calling run's budget when the tool starts
+    pub max_cost_usd: Option<f64>,
+    pub max_total_tokens: Option<u32>,
+}
+
+impl RunContext {
+    pub fn new<Ctx: Send + 'static>(ctx: Ctx) -> Self {
+        RunContext {
+            inner: Some(Arc::new(Mutex::new(ctx))),
+            limits: RunLimits::default(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_none()
+    }
+
+    // Limits a run started by the current tool should stay within
+    pub fn limits(&self) -> RunLimits {
+        self.limits
+    }
+
+    pub(crate) fn with_limits(mut self, limits: RunLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    // Runs `f` with exclusive access to the state; None if it is absent or of another type
+    pub fn with<Ctx: Send + 'static, R>(&self, f: impl FnOnce(&mut Ctx) -> R) -> Option<R> {
+        let mutex = self.inner.as_ref()?.downcast_ref::<Mutex<Ctx>>()?;
+        let mut ctx = mutex
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        Some(f(&mut ctx))
+    }
+}
+
+impl std::fmt::Debug for RunContext {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunContext")
+            .field("is_empty", &self.is_empty())
+            .field("limits", &self.limits)
+            .finish()
+    }
+}
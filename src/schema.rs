@@ -0,0 +1,143 @@
+use async_openai::types::{ResponseFormat, ResponseFormatJsonSchema};
+use schemars::{gen::SchemaSettings, JsonSchema};
+use serde_json::Value;
+
+// Generates the JSON schema of a type in the shape tool parameters expect
+pub fn schema_for<T: JsonSchema>() -> Value {
+    // 1. Inline nested types so the schema is self-contained
+    let generator = SchemaSettings::draft07()
+        .with(|settings| {
+            settings.inline_subschemas = true;
+            settings.meta_schema = None;
+        })
+        .into_generator();
+    let schema = generator.into_root_schema_for::<T>();
+
+    // 2. Drop the type's title, which the model never needs
+    let mut value = serde_json::to_value(schema).unwrap_or(Value::Null);
+    if let Value::Object(obj) = &mut value {
+        obj.remove("title");
+    }
+    value
+}
+
+// Closes every object in a schema as strict function calling requires: no
+// additional properties, and every property listed as required, with formerly
+// optional ones made nullable instead
+pub fn strict_schema(mut schema: Value) -> Value {
+    close_objects(&mut schema);
+    schema
+}
+
+fn close_objects(schema: &mut Value) {
+    match schema {
+        Value::Object(obj) => {
+            // 1. Close this level if it describes an object
+            let is_object = obj.get("type").is_some_and(|t| {
+                t == "object"
+                    || t.as_array()
+                        .is_some_and(|types| types.iter().any(|t| t == "object"))
+            }) || obj.contains_key("properties");
+            if is_object {
+                let required = obj
+                    .get("required")
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default();
+                let mut names = Vec::new();
+                if let Some(properties) = obj.get_mut("properties").and_then(Value::as_object_mut) {
+                    for (name, property) in properties.iter_mut() {
+                        let name = Value::String(name.clone());
+                        if !required.contains(&name) {
+                            make_nullable(property);
+                        }
+                        names.push(name);
+                    }
+                }
+                obj.insert("required".to_string(), Value::Array(names));
+                obj.insert("additionalProperties".to_string(), Value::Bool(false));
+            }
+
+            // 2. Descend into nested schemas
+            for (key, value) in obj.iter_mut() {
+                match key.as_str() {
+                    "properties" | "definitions" | "$defs" => {
+                        if let Value::Object(children) = value {
+                            children.values_mut().for_each(close_objects);
+                        }
+                    }
+                    "items" | "anyOf" | "oneOf" | "allOf" => close_objects(value),
+                    _ => {}
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(close_objects),
+        _ => {}
+    }
+}
+
+// Lets a schema also accept `null`
+fn make_nullable(schema: &mut Value) {
+    let null = Value::String("null".to_string());
+    match schema.get_mut("type") {
+        Some(Value::Array(types)) => {
+            if !types.contains(&null) {
+                types.push(null);
+            }
+        }
+        Some(single) if *single != null => *single = Value::Array(vec![single.take(), null]),
+        Some(_) => {}
+        None => *schema = serde_json::json!({ "anyOf": [schema.take(), { "type": "null" }] }),
+    }
+}
+
+// Checks a value against a schema, describing each violation with the path it
+// occurred at. Schemas that fail to compile accept everything.
+pub fn validate(schema: &Value, value: &Value) -> Vec<String> {
+    let Ok(validator) = jsonschema::validator_for(schema) else {
+        return Vec::new();
+    };
+    validator
+        .iter_errors(value)
+        .map(|error| {
+            let path = error.instance_path().to_string();
+            if path.is_empty() {
+                error.to_string()
+            } else {
+                format!("{}: {}", path, error)
+            }
+        })
+        .collect()
+}
+
+// Builds a `json_schema` response format from the schema of a type
+pub fn response_format_for<T: JsonSchema>(name: &str) -> ResponseFormat {
+    ResponseFormat::JsonSchema {
+        json_schema: ResponseFormatJsonSchema {
+            description: None,
+            name: name.to_string(),
+            schema: Some(schema_for::<T>()),
+            strict: None,
+        },
+    }
+}
+
+// Removes a markdown code fence around model-written JSON. The closing fence is optional,
+// as output may be cut off before it
+pub(crate) fn strip_code_fence(text: &str) -> &str {
+    let text = text.trim();
+    text.strip_prefix("```json")
+        .or_else(|| text.strip_prefix("```"))
+        .map(|inner| inner.trim_end().strip_suffix("```").unwrap_or(inner))
+        .unwrap_or(text)
+        .trim()
+}
+
+// Narrows a reply to the JSON object inside it, from its first `{` to its last `}`,
+// tolerating prose or code fences around it; the whole text if it has no object
+pub(crate) fn extract_json_object(text: &str) -> &str {
+    match (text.find('{'), text.rfind('}')) {
+        (Some(start), Some(end)) if start < end => &text[start..=end],
+        _ => text,
+    }
+}
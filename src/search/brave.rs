@@ -0,0 +1,95 @@
+use async_trait::async_trait;
+use regex::Regex;
+use serde_json::Value;
+use std::sync::LazyLock;
+
+use super::{send, SearchProvider, SearchResult};
+use crate::error::Result;
+
+// Most results Brave returns for one request
+const MAX_COUNT: usize = 20;
+
+// Brave marks matched words with HTML tags
+static TAG: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"<[^>]+>").expect("valid regex"));
+
+// Search through the Brave Search API
+#[derive(Clone)]
+pub struct BraveSearch {
+    http: reqwest::Client,
+    api_key: String,
+    base_url: String,
+    country: Option<String>,
+}
+
+impl BraveSearch {
+    pub fn new(api_key: &str) -> Self {
+        BraveSearch {
+            http: reqwest::Client::new(),
+            api_key: api_key.to_string(),
+            base_url: "https://api.search.brave.com".to_string(),
+            country: None,
+        }
+    }
+
+    // Points at a proxy or compatible server
+    pub fn with_base_url(mut self, url: &str) -> Self {
+        self.base_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    // Two-letter country code the results are localized for
+    pub fn with_country(mut self, country: &str) -> Self {
+        self.country = Some(country.to_string());
+        self
+    }
+}
+
+impl std::fmt::Debug for BraveSearch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BraveSearch")
+            .field("base_url", &self.base_url)
+            .field("country", &self.country)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl SearchProvider for BraveSearch {
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let mut params = vec![
+            ("q", query.to_string()),
+            ("count", limit.min(MAX_COUNT).to_string()),
+        ];
+        params.extend(self.country.clone().map(|country| ("country", country)));
+        let request = self
+            .http
+            .get(format!("{}/res/v1/web/search", self.base_url))
+            .header("X-Subscription-Token", &self.api_key)
+            .header("Accept", "application/json")
+            .query(&params);
+        let response = send(request).await?;
+        let results = response
+            .pointer("/web/results")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|result| SearchResult {
+                title: text(result, "title"),
+                url: text(result, "url"),
+                snippet: text(result, "description"),
+                published: result
+                    .get("page_age")
+                    .or_else(|| result.get("age"))
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            })
+            .take(limit)
+            .collect();
+        Ok(results)
+    }
+}
+
+fn text(result: &Value, key: &str) -> String {
+    let text = result.get(key).and_then(Value::as_str).unwrap_or_default();
+    TAG.replace_all(text, "").into_owned()
+}
@@ -0,0 +1,64 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::error::Result;
+
+#[cfg(feature = "brave")]
+mod brave;
+#[cfg(feature = "serpapi")]
+mod serpapi;
+#[cfg(feature = "tavily")]
+mod tavily;
+
+#[cfg(feature = "brave")]
+pub use self::brave::BraveSearch;
+#[cfg(feature = "serpapi")]
+pub use self::serpapi::SerpApiSearch;
+#[cfg(feature = "tavily")]
+pub use self::tavily::TavilySearch;
+
+// One web page found for a query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchResult {
+    pub title: String,
+    pub url: String,
+    pub snippet: String,
+    // Publication date as the provider reports it, if known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub published: Option<String>,
+}
+
+// A web search API
+#[async_trait]
+pub trait SearchProvider: Send + Sync {
+    // Returns up to `limit` results, best first
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>>;
+}
+
+#[async_trait]
+impl<T: SearchProvider + ?Sized> SearchProvider for Arc<T> {
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        (**self).search(query, limit).await
+    }
+}
+
+// Sends a request and returns the JSON body, turning error statuses into errors
+#[cfg(any(feature = "brave", feature = "serpapi", feature = "tavily"))]
+async fn send(request: reqwest::RequestBuilder) -> Result<serde_json::Value> {
+    use crate::error::SwarmError;
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| SwarmError::Search(e.to_string()))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(SwarmError::Search(format!("{}: {}", status, body)));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| SwarmError::Search(e.to_string()))
+}
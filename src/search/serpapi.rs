@@ -0,0 +1,88 @@
+use async_trait::async_trait;
+use serde_json::Value;
+
+use super::{send, SearchProvider, SearchResult};
+use crate::error::Result;
+
+// Search through SerpApi, which returns the organic results of a search engine
+#[derive(Clone)]
+pub struct SerpApiSearch {
+    http: reqwest::Client,
+    api_key: String,
+    base_url: String,
+    engine: String,
+}
+
+impl SerpApiSearch {
+    // Searches Google
+    pub fn new(api_key: &str) -> Self {
+        SerpApiSearch {
+            http: reqwest::Client::new(),
+            api_key: api_key.to_string(),
+            base_url: "https://serpapi.com".to_string(),
+            engine: "google".to_string(),
+        }
+    }
+
+    // Points at a proxy or compatible server
+    pub fn with_base_url(mut self, url: &str) -> Self {
+        self.base_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    // Another engine with organic results, e.g. "bing" or "duckduckgo"
+    pub fn with_engine(mut self, engine: &str) -> Self {
+        self.engine = engine.to_string();
+        self
+    }
+}
+
+impl std::fmt::Debug for SerpApiSearch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SerpApiSearch")
+            .field("base_url", &self.base_url)
+            .field("engine", &self.engine)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl SearchProvider for SerpApiSearch {
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let request = self
+            .http
+            .get(format!("{}/search.json", self.base_url))
+            .query(&[
+                ("engine", self.engine.as_str()),
+                ("q", query),
+                ("num", &limit.to_string()),
+                ("api_key", &self.api_key),
+            ]);
+        let response = send(request).await?;
+        let results = response
+            .get("organic_results")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|result| SearchResult {
+                title: text(result, "title"),
+                url: text(result, "link"),
+                snippet: text(result, "snippet"),
+                published: result
+                    .get("date")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            })
+            .take(limit)
+            .collect();
+        Ok(results)
+    }
+}
+
+fn text(result: &Value, key: &str) -> String {
+    result
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
@@ -0,0 +1,88 @@
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use super::{send, SearchProvider, SearchResult};
+use crate::error::Result;
+
+// Search through the Tavily API, which is built for LLM agents
+#[derive(Clone)]
+pub struct TavilySearch {
+    http: reqwest::Client,
+    api_key: String,
+    base_url: String,
+    advanced: bool,
+}
+
+impl TavilySearch {
+    pub fn new(api_key: &str) -> Self {
+        TavilySearch {
+            http: reqwest::Client::new(),
+            api_key: api_key.to_string(),
+            base_url: "https://api.tavily.com".to_string(),
+            advanced: false,
+        }
+    }
+
+    // Points at a proxy or compatible server
+    pub fn with_base_url(mut self, url: &str) -> Self {
+        self.base_url = url.trim_end_matches('/').to_string();
+        self
+    }
+
+    // Advanced search returns more relevant snippets and costs more credits
+    pub fn with_advanced_depth(mut self) -> Self {
+        self.advanced = true;
+        self
+    }
+}
+
+impl std::fmt::Debug for TavilySearch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TavilySearch")
+            .field("base_url", &self.base_url)
+            .field("advanced", &self.advanced)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl SearchProvider for TavilySearch {
+    async fn search(&self, query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+        let body = json!({
+            "query": query,
+            "max_results": limit,
+            "search_depth": if self.advanced { "advanced" } else { "basic" },
+        });
+        let request = self
+            .http
+            .post(format!("{}/search", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&body);
+        let response = send(request).await?;
+        let results = response
+            .get("results")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .map(|result| SearchResult {
+                title: text(result, "title"),
+                url: text(result, "url"),
+                snippet: text(result, "content"),
+                published: result
+                    .get("published_date")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            })
+            .take(limit)
+            .collect();
+        Ok(results)
+    }
+}
+
+fn text(result: &Value, key: &str) -> String {
+    result
+        .get(key)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
@@ -0,0 +1,77 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+use crate::error::Result;
+use crate::types::ContextVariables;
+
+#[cfg(feature = "redis")]
+mod redis;
+
+#[cfg(feature = "redis")]
+pub use self::redis::RedisSessionStore;
+
+// Persisted conversation state for a single session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub messages: Vec<ChatCompletionRequestMessage>,
+    pub context_variables: ContextVariables,
+}
+
+// Storage backend for conversation history across runs
+#[async_trait]
+pub trait SessionStore: Send + Sync {
+    async fn save(
+        &self,
+        session_id: &str,
+        history: &[ChatCompletionRequestMessage],
+        context_variables: &ContextVariables,
+    ) -> Result<()>;
+
+    async fn load(&self, session_id: &str) -> Result<Option<Session>>;
+
+    async fn list(&self) -> Result<Vec<String>>;
+}
+
+// Process-local session store, lost on restart
+#[derive(Debug, Default)]
+pub struct InMemorySessionStore {
+    sessions: RwLock<HashMap<String, Session>>,
+}
+
+impl InMemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl SessionStore for InMemorySessionStore {
+    async fn save(
+        &self,
+        session_id: &str,
+        history: &[ChatCompletionRequestMessage],
+        context_variables: &ContextVariables,
+    ) -> Result<()> {
+        self.sessions.write().await.insert(
+            session_id.to_string(),
+            Session {
+                messages: history.to_vec(),
+                context_variables: context_variables.clone(),
+            },
+        );
+        Ok(())
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<Session>> {
+        Ok(self.sessions.read().await.get(session_id).cloned())
+    }
+
+    async fn list(&self) -> Result<Vec<String>> {
+        let mut ids: Vec<String> = self.sessions.read().await.keys().cloned().collect();
+        ids.sort();
+        Ok(ids)
+    }
+}
@@ -0,0 +1,146 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use async_trait::async_trait;
+use redis::{aio::ConnectionManager, AsyncCommands};
+use std::time::Duration;
+
+use super::{Session, SessionStore};
+use crate::error::{Result, SwarmError};
+use crate::types::ContextVariables;
+
+// Session store shared by several processes through Redis
+#[derive(Clone)]
+pub struct RedisSessionStore {
+    connection: ConnectionManager,
+    prefix: String,
+    ttl: Option<Duration>,
+}
+
+impl RedisSessionStore {
+    pub async fn new(client: redis::Client) -> Result<Self> {
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(session_error)?;
+        Ok(RedisSessionStore {
+            connection,
+            prefix: "swarm:session:".to_string(),
+            ttl: None,
+        })
+    }
+
+    pub async fn from_url(url: &str) -> Result<Self> {
+        Self::new(redis::Client::open(url).map_err(session_error)?).await
+    }
+
+    // Namespace for session keys, e.g. per application
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    // Default expiry, refreshed every time a session is saved
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn key(&self, session_id: &str) -> String {
+        format!("{}{}", self.prefix, session_id)
+    }
+
+    // Saves a session with its own expiry instead of the store default
+    pub async fn save_with_ttl(
+        &self,
+        session_id: &str,
+        history: &[ChatCompletionRequestMessage],
+        context_variables: &ContextVariables,
+        ttl: Option<Duration>,
+    ) -> Result<()> {
+        let session = Session {
+            messages: history.to_vec(),
+            context_variables: context_variables.clone(),
+        };
+        let payload =
+            serde_json::to_string(&session).map_err(|e| SwarmError::Session(e.to_string()))?;
+
+        let mut connection = self.connection.clone();
+        let key = self.key(session_id);
+        match ttl {
+            Some(ttl) => connection
+                .set_ex::<_, _, ()>(key, payload, ttl.as_secs().max(1))
+                .await
+                .map_err(session_error),
+            None => connection
+                .set::<_, _, ()>(key, payload)
+                .await
+                .map_err(session_error),
+        }
+    }
+
+    // Changes the expiry of an existing session
+    pub async fn expire(&self, session_id: &str, ttl: Duration) -> Result<()> {
+        let mut connection = self.connection.clone();
+        connection
+            .expire::<_, ()>(self.key(session_id), ttl.as_secs().max(1) as i64)
+            .await
+            .map_err(session_error)
+    }
+
+    pub async fn delete(&self, session_id: &str) -> Result<()> {
+        let mut connection = self.connection.clone();
+        connection
+            .del::<_, ()>(self.key(session_id))
+            .await
+            .map_err(session_error)
+    }
+}
+
+#[async_trait]
+impl SessionStore for RedisSessionStore {
+    async fn save(
+        &self,
+        session_id: &str,
+        history: &[ChatCompletionRequestMessage],
+        context_variables: &ContextVariables,
+    ) -> Result<()> {
+        self.save_with_ttl(session_id, history, context_variables, self.ttl)
+            .await
+    }
+
+    async fn load(&self, session_id: &str) -> Result<Option<Session>> {
+        let mut connection = self.connection.clone();
+        let payload: Option<String> = connection
+            .get(self.key(session_id))
+            .await
+            .map_err(session_error)?;
+        payload
+            .map(|payload| {
+                serde_json::from_str(&payload).map_err(|e| SwarmError::Session(e.to_string()))
+            })
+            .transpose()
+    }
+
+    async fn list(&self) -> Result<Vec<String>> {
+        let mut connection = self.connection.clone();
+        let keys: Vec<String> = {
+            let mut iter = connection
+                .scan_match::<_, String>(format!("{}*", self.prefix))
+                .await
+                .map_err(session_error)?;
+            let mut keys = Vec::new();
+            while let Some(key) = iter.next_item().await {
+                keys.push(key);
+            }
+            keys
+        };
+        let mut ids: Vec<String> = keys
+            .into_iter()
+            .filter_map(|key| key.strip_prefix(&self.prefix).map(String::from))
+            .collect();
+        ids.sort();
+        Ok(ids)
+    }
+}
+
+fn session_error(error: redis::RedisError) -> SwarmError {
+    SwarmError::Session(error.to_string())
+}
@@ -0,0 +1,444 @@
+use base64::Engine;
+use futures::{FutureExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sqlx::{Column, Executor, Row, Statement, TypeInfo, ValueRef};
+use std::sync::Arc;
+
+use crate::error::{Result, SwarmError};
+use crate::types::{Tool, ToolDefinition, ToolFunction, ToolOutput};
+
+#[cfg(feature = "postgres")]
+use sqlx::postgres::{PgPool, PgRow};
+#[cfg(feature = "sqlite")]
+use sqlx::sqlite::{SqliteConnectOptions, SqliteConnection, SqlitePool, SqliteRow};
+
+// A connection pool of one of the supported databases
+#[derive(Debug, Clone)]
+pub enum SqlDatabase {
+    #[cfg(feature = "postgres")]
+    Postgres(PgPool),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqlitePool),
+}
+
+impl SqlDatabase {
+    // Connects to a `postgres://` or `sqlite:` URL
+    pub async fn connect(url: &str) -> Result<Self> {
+        #[cfg(feature = "postgres")]
+        if url.starts_with("postgres://") || url.starts_with("postgresql://") {
+            return Ok(SqlDatabase::Postgres(
+                PgPool::connect(url).await.map_err(database_error)?,
+            ));
+        }
+        #[cfg(feature = "sqlite")]
+        if url.starts_with("sqlite:") {
+            return Ok(SqlDatabase::Sqlite(
+                SqlitePool::connect(url).await.map_err(database_error)?,
+            ));
+        }
+        Err(SwarmError::Config(format!(
+            "unsupported database URL {}",
+            url
+        )))
+    }
+
+    fn dialect(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "postgres")]
+            SqlDatabase::Postgres(_) => "PostgreSQL",
+            #[cfg(feature = "sqlite")]
+            SqlDatabase::Sqlite(_) => "SQLite",
+        }
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<PgPool> for SqlDatabase {
+    fn from(pool: PgPool) -> Self {
+        SqlDatabase::Postgres(pool)
+    }
+}
+
+#[cfg(feature = "sqlite")]
+impl From<SqlitePool> for SqlDatabase {
+    fn from(pool: SqlitePool) -> Self {
+        SqlDatabase::Sqlite(pool)
+    }
+}
+
+// A table or view and its columns, as found by introspection
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableSchema {
+    pub name: String,
+    pub columns: Vec<ColumnSchema>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    pub data_type: String,
+    pub nullable: bool,
+}
+
+// Describes tables one per line, e.g. `users(id integer not null, email text)`
+pub fn describe_tables(tables: &[TableSchema]) -> String {
+    tables
+        .iter()
+        .map(|table| {
+            let columns = table
+                .columns
+                .iter()
+                .map(|column| match column.nullable {
+                    true => format!("{} {}", column.name, column.data_type),
+                    false => format!("{} {} not null", column.name, column.data_type),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            format!("{}({})", table.name, columns)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// Settings of the `sql_query` tool
+#[derive(Debug, Clone)]
+pub struct SqlQuery {
+    database: SqlDatabase,
+    read_only: bool,
+    max_rows: usize,
+    schema: Option<String>,
+    // SQLite runs several `;`-separated statements at once, so read-only mode uses
+    // connections opened read-only rather than settings a query could undo. In-memory
+    // databases cannot be reopened read-only and have none
+    #[cfg(feature = "sqlite")]
+    read_only_pool: Option<SqlitePool>,
+}
+
+impl SqlQuery {
+    // Read-only, returning at most 100 rows per query
+    pub fn new(database: impl Into<SqlDatabase>) -> Self {
+        let database = database.into();
+        #[cfg(feature = "sqlite")]
+        let read_only_pool = match &database {
+            SqlDatabase::Sqlite(pool) if !in_memory(&pool.connect_options()) => Some(
+                SqlitePool::connect_lazy_with((*pool.connect_options()).clone().read_only(true)),
+            ),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        };
+        SqlQuery {
+            database,
+            read_only: true,
+            max_rows: 100,
+            schema: None,
+            #[cfg(feature = "sqlite")]
+            read_only_pool,
+        }
+    }
+
+    // With `false`, statements that modify the database run and are committed
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn with_max_rows(mut self, rows: usize) -> Self {
+        self.max_rows = rows;
+        self
+    }
+
+    // Table descriptions included in the tool description
+    pub fn with_schema_description(mut self, description: &str) -> Self {
+        self.schema = Some(description.to_string());
+        self
+    }
+
+    // Introspects the database and includes every table in the tool description
+    pub async fn with_schema(self) -> Result<Self> {
+        let tables = self.tables().await?;
+        let description = describe_tables(&tables);
+        Ok(self.with_schema_description(&description))
+    }
+
+    // Tables and views outside the system schemas, with their columns
+    pub async fn tables(&self) -> Result<Vec<TableSchema>> {
+        let columns: Vec<(String, String, String, bool)> = match &self.database {
+            #[cfg(feature = "postgres")]
+            SqlDatabase::Postgres(pool) => sqlx::query_as(
+                "SELECT CASE WHEN table_schema = 'public' THEN table_name \
+                 ELSE table_schema || '.' || table_name END, \
+                 column_name, data_type, is_nullable = 'YES' \
+                 FROM information_schema.columns \
+                 WHERE table_schema NOT IN ('pg_catalog', 'information_schema') \
+                 ORDER BY table_schema, table_name, ordinal_position",
+            )
+            .fetch_all(pool)
+            .await
+            .map_err(database_error)?,
+            #[cfg(feature = "sqlite")]
+            SqlDatabase::Sqlite(pool) => sqlx::query_as(
+                "SELECT m.name, p.name, p.type, p.\"notnull\" = 0 AND p.pk = 0 \
+                 FROM sqlite_master m JOIN pragma_table_info(m.name) p \
+                 WHERE m.type IN ('table', 'view') AND m.name NOT LIKE 'sqlite_%' \
+                 ORDER BY m.name, p.cid",
+            )
+            .fetch_all(pool)
+            .await
+            .map_err(database_error)?,
+        };
+        let mut tables: Vec<TableSchema> = Vec::new();
+        for (table, name, data_type, nullable) in columns {
+            let column = ColumnSchema {
+                name,
+                data_type: data_type.to_lowercase(),
+                nullable,
+            };
+            match tables.last_mut() {
+                Some(last) if last.name == table => last.columns.push(column),
+                _ => tables.push(TableSchema {
+                    name: table,
+                    columns: vec![column],
+                }),
+            }
+        }
+        Ok(tables)
+    }
+
+    // Runs one statement. Queries return `{"columns", "rows", "truncated"}` with rows as
+    // arrays; other statements return `{"rows_affected"}`
+    pub async fn execute(&self, sql: &str) -> Result<Value> {
+        match &self.database {
+            #[cfg(feature = "postgres")]
+            SqlDatabase::Postgres(pool) => {
+                // 1. Run inside a transaction, which read-only mode never commits
+                let mut transaction = pool.begin().await.map_err(database_error)?;
+                if self.read_only {
+                    transaction
+                        .execute("SET TRANSACTION READ ONLY")
+                        .await
+                        .map_err(database_error)?;
+                }
+                // 2. Prepared statements hold a single statement, so this is the only one
+                let statement = (&mut *transaction)
+                    .prepare(sql)
+                    .await
+                    .map_err(database_error)?;
+                let columns = column_names(statement.columns());
+                let result = if columns.is_empty() {
+                    let done = statement
+                        .query()
+                        .execute(&mut *transaction)
+                        .await
+                        .map_err(database_error)?;
+                    json!({ "rows_affected": done.rows_affected() })
+                } else {
+                    let rows = statement
+                        .query()
+                        .fetch(&mut *transaction)
+                        .take(self.max_rows + 1)
+                        .map(|row| row.map(|row| postgres_row(&row)))
+                        .collect::<Vec<_>>()
+                        .await;
+                    self.rows(columns, rows)?
+                };
+                if !self.read_only {
+                    transaction.commit().await.map_err(database_error)?;
+                }
+                Ok(result)
+            }
+            #[cfg(feature = "sqlite")]
+            SqlDatabase::Sqlite(pool) => {
+                let pool = match (self.read_only, &self.read_only_pool) {
+                    (true, Some(read_only_pool)) => read_only_pool,
+                    _ => pool,
+                };
+                // Without a read-only pool, refuse writes on the shared connection for the
+                // duration of the statement. A second statement could turn that off again,
+                // so only one is accepted
+                let query_only = self.read_only && self.read_only_pool.is_none();
+                if query_only && sql.trim().trim_end_matches(';').contains(';') {
+                    return Err(SwarmError::Database(
+                        "read-only mode runs a single statement".to_string(),
+                    ));
+                }
+                let mut connection = pool.acquire().await.map_err(database_error)?;
+                if query_only {
+                    connection
+                        .execute("PRAGMA query_only = ON")
+                        .await
+                        .map_err(database_error)?;
+                }
+                let result = self.execute_sqlite(&mut connection, sql).await;
+                if query_only {
+                    connection
+                        .execute("PRAGMA query_only = OFF")
+                        .await
+                        .map_err(database_error)?;
+                }
+                result
+            }
+        }
+    }
+
+    #[cfg(feature = "sqlite")]
+    async fn execute_sqlite(&self, connection: &mut SqliteConnection, sql: &str) -> Result<Value> {
+        let statement = (&mut *connection)
+            .prepare(sql)
+            .await
+            .map_err(database_error)?;
+        let columns = column_names(statement.columns());
+        if columns.is_empty() {
+            let done = statement
+                .query()
+                .execute(&mut *connection)
+                .await
+                .map_err(database_error)?;
+            return Ok(json!({ "rows_affected": done.rows_affected() }));
+        }
+        let rows = statement
+            .query()
+            .fetch(&mut *connection)
+            .take(self.max_rows + 1)
+            .map(|row| row.map(|row| sqlite_row(&row)))
+            .collect::<Vec<_>>()
+            .await;
+        self.rows(columns, rows)
+    }
+
+    fn rows(
+        &self,
+        columns: Vec<String>,
+        rows: Vec<std::result::Result<Vec<Value>, sqlx::Error>>,
+    ) -> Result<Value> {
+        let mut rows = rows
+            .into_iter()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(database_error)?;
+        let truncated = rows.len() > self.max_rows;
+        rows.truncate(self.max_rows);
+        Ok(json!({ "columns": columns, "rows": rows, "truncated": truncated }))
+    }
+
+    // The model passes a `query`; database errors come back as tool errors so it can fix them
+    pub fn tool(self) -> ToolDefinition {
+        let mut description = format!(
+            "Runs one SQL statement against the {} database and returns the result.",
+            self.database.dialect()
+        );
+        if self.read_only {
+            description.push_str(" The database is read-only.");
+        }
+        description.push_str(&format!(" At most {} rows are returned.", self.max_rows));
+        if let Some(schema) = &self.schema {
+            description.push_str(&format!("\n\nTables:\n{}", schema));
+        }
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "query": { "type": "string", "description": "A single SQL statement" },
+            },
+            "required": ["query"],
+        });
+        let settings = Arc::new(self);
+        let function = move |args: Value| {
+            let settings = settings.clone();
+            async move {
+                let Some(query) = args.get("query").and_then(Value::as_str) else {
+                    return ToolOutput::Error("missing query".to_string());
+                };
+                match settings.execute(query).await {
+                    Ok(result) => ToolOutput::Json(result),
+                    Err(e) => ToolOutput::Error(e.to_string()),
+                }
+            }
+            .boxed()
+        };
+        ToolDefinition::new(
+            Tool::new("sql_query", &description, parameters),
+            ToolFunction::Async(Arc::new(function)),
+        )
+    }
+}
+
+fn database_error(error: sqlx::Error) -> SwarmError {
+    SwarmError::Database(error.to_string())
+}
+
+fn column_names<C: Column>(columns: &[C]) -> Vec<String> {
+    columns
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect()
+}
+
+fn unsupported(type_name: &str) -> Value {
+    Value::String(format!("<{}: cast to text to read>", type_name))
+}
+
+#[cfg(feature = "postgres")]
+fn postgres_row(row: &PgRow) -> Vec<Value> {
+    (0..row.len())
+        .map(|index| {
+            let type_name = row.column(index).type_info().name().to_string();
+            let value = match type_name.as_str() {
+                "BOOL" => row.try_get::<Option<bool>, _>(index).map(|v| json!(v)),
+                "INT2" => row.try_get::<Option<i16>, _>(index).map(|v| json!(v)),
+                "INT4" => row.try_get::<Option<i32>, _>(index).map(|v| json!(v)),
+                "INT8" => row.try_get::<Option<i64>, _>(index).map(|v| json!(v)),
+                "FLOAT4" => row.try_get::<Option<f32>, _>(index).map(|v| json!(v)),
+                "FLOAT8" => row.try_get::<Option<f64>, _>(index).map(|v| json!(v)),
+                "TEXT" | "VARCHAR" | "BPCHAR" | "CHAR" | "NAME" => {
+                    row.try_get::<Option<String>, _>(index).map(|v| json!(v))
+                }
+                "JSON" | "JSONB" => row
+                    .try_get::<Option<Value>, _>(index)
+                    .map(|v| v.unwrap_or(Value::Null)),
+                "BYTEA" => row
+                    .try_get::<Option<Vec<u8>>, _>(index)
+                    .map(|v| json!(v.map(|bytes| base64_text(&bytes)))),
+                _ => match row.try_get_raw(index) {
+                    Ok(raw) if raw.is_null() => Ok(Value::Null),
+                    _ => Ok(unsupported(&type_name)),
+                },
+            };
+            value.unwrap_or_else(|_| unsupported(&type_name))
+        })
+        .collect()
+}
+
+// `sqlite::memory:` and `mode=memory` URLs, and the `:memory:` file name. sqlx keeps
+// its in-memory flag private, so it is read from the debug output
+#[cfg(feature = "sqlite")]
+fn in_memory(options: &SqliteConnectOptions) -> bool {
+    options.get_filename() == std::path::Path::new(":memory:")
+        || format!("{:?}", options).contains("in_memory: true")
+}
+
+#[cfg(feature = "sqlite")]
+fn sqlite_row(row: &SqliteRow) -> Vec<Value> {
+    (0..row.len())
+        .map(|index| {
+            // SQLite is dynamically typed, so go by the type of each value
+            let type_name = match row.try_get_raw(index) {
+                Ok(raw) if raw.is_null() => return Value::Null,
+                Ok(raw) => raw.type_info().name().to_string(),
+                Err(_) => return Value::Null,
+            };
+            let value = match type_name.as_str() {
+                "INTEGER" => row.try_get_unchecked::<i64, _>(index).map(|v| json!(v)),
+                "REAL" => row.try_get_unchecked::<f64, _>(index).map(|v| json!(v)),
+                "BOOLEAN" => row.try_get_unchecked::<bool, _>(index).map(|v| json!(v)),
+                "BLOB" => row
+                    .try_get_unchecked::<Vec<u8>, _>(index)
+                    .map(|v| json!(base64_text(&v))),
+                _ => row.try_get_unchecked::<String, _>(index).map(|v| json!(v)),
+            };
+            value.unwrap_or_else(|_| unsupported(&type_name))
+        })
+        .collect()
+}
+
+fn base64_text(bytes: &[u8]) -> String {
+    base64::engine::general_purpose::STANDARD.encode(bytes)
+}
@@ -1,37 +1,277 @@
 use async_openai::{
-    config::OpenAIConfig,
+    config::{AzureConfig, OpenAIConfig},
     types::{
-        ChatCompletionMessageToolCall, ChatCompletionRequestAssistantMessage,
-        ChatCompletionRequestAssistantMessageContent, ChatCompletionRequestMessage,
-        ChatCompletionRequestToolMessage, ChatCompletionRequestToolMessageContent,
-        ChatCompletionResponseMessage, ChatCompletionTool, ChatCompletionToolArgs,
-        ChatCompletionToolType, CreateChatCompletionRequestArgs, FunctionObjectArgs,
+        ChatCompletionMessageToolCall, ChatCompletionMessageToolCallChunk,
+        ChatCompletionRequestAssistantMessage, ChatCompletionRequestAssistantMessageContent,
+        ChatCompletionRequestMessage, ChatCompletionRequestSystemMessage,
+        ChatCompletionRequestSystemMessageContent, ChatCompletionRequestToolMessage,
+        ChatCompletionRequestToolMessageContent, ChatCompletionResponseMessage,
+        ChatCompletionToolType, FinishReason, FunctionCall, Role,
     },
     Client,
 };
+use futures::{channel::mpsc, future::BoxFuture, FutureExt, Stream, StreamExt};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Serialize};
 use serde_json::Value;
+use std::any::Any;
 use std::collections::HashMap;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use std::sync::Arc;
+use tokio::time::Instant;
 
-use crate::types::{Agent, Response, ToolRegistry, ToolResult};
+use crate::approval::{Approval, ApprovalHandler};
+use crate::audio::Synthesizer;
+use crate::config::{LoopAction, SwarmConfig};
+use crate::context::ContextPolicy;
+use crate::cost::PricingTable;
+use crate::debug::{DebugEvent, DebugSink};
+use crate::error::{Result, SwarmError};
+use crate::guardrails::{
+    GuardrailDecision, GuardrailOutcome, InputGuardrail, OutputDecision, OutputGuardrail,
+};
+use crate::history::History;
+use crate::hooks::{DebugHooks, SwarmHooks};
+use crate::memory::{AgentMemory, SemanticMemory};
+use crate::messages::{message_text, user_message};
+use crate::metrics::SwarmMetrics;
+use crate::middleware::{BeforeCall, ToolMiddleware};
+use crate::moderation::Moderation;
+use crate::pii::PiiRedactor;
+use crate::provider::{
+    AzureOpenAIProvider, ChatProvider, ChatRequest, ChatResponse, OpenAIProvider,
+};
+use crate::rate_limit::RateLimiter;
+use crate::reload::LiveAgents;
+use crate::retrieval::{Document, KnowledgeBase};
+use crate::run_context::{RunContext, RunLimits};
+use crate::schema;
+use crate::session::SessionStore;
+use crate::telemetry;
+use crate::types::{
+    Agent, ContextVariables, Response, RunOptions, RunState, RunStatus, SwarmEvent, Tool,
+    ToolDefinition, ToolFunction, ToolOutput, ToolRegistry, ToolResult, Turn, TypedResponse, Usage,
+    CONTEXT_VARIABLES,
+};
 
 // Main struct for managing AI swarm interactions
-pub struct Swarm {
-    client: Client<OpenAIConfig>,
+pub struct Swarm<P: ChatProvider = OpenAIProvider> {
+    provider: P,
     registry: ToolRegistry,
+    pricing: PricingTable,
+    config: SwarmConfig,
+    rate_limiter: Option<RateLimiter>,
+    context_policy: Option<Arc<dyn ContextPolicy>>,
+    session_store: Option<Arc<dyn SessionStore>>,
+    middleware: Vec<Arc<dyn ToolMiddleware>>,
+    hooks: Vec<Arc<dyn SwarmHooks>>,
+    metrics: Option<Arc<SwarmMetrics>>,
+    debug_hooks: DebugHooks,
+    moderation: Option<Arc<Moderation>>,
+    input_guardrails: Vec<Arc<dyn InputGuardrail>>,
+    output_guardrails: Vec<Arc<dyn OutputGuardrail>>,
+    pii: Option<Arc<PiiRedactor>>,
+    live_agents: Option<LiveAgents>,
+    memory: Option<Arc<SemanticMemory>>,
+    agent_memory: Option<Arc<AgentMemory>>,
+    knowledge_base: Option<KnowledgeBase>,
 }
 
 impl Swarm {
     // Creates a new Swarm instance with optional OpenAI client
     pub fn new(client: Option<Client<OpenAIConfig>>) -> Self {
+        Swarm::with_provider(OpenAIProvider::new(client.unwrap_or_default()))
+    }
+}
+
+impl Swarm<AzureOpenAIProvider> {
+    // Creates a new Swarm instance against Azure OpenAI deployments
+    pub fn new_azure(config: AzureConfig) -> Self {
+        Swarm::with_provider(AzureOpenAIProvider::new(config))
+    }
+
+    // Maps an agent model name to an Azure deployment ID
+    pub fn with_deployment(mut self, model: &str, deployment_id: &str) -> Self {
+        self.provider = self.provider.with_deployment(model, deployment_id);
+        self
+    }
+}
+
+impl<P: ChatProvider> Swarm<P> {
+    // Creates a new Swarm instance backed by the given chat provider
+    pub fn with_provider(provider: P) -> Self {
         Swarm {
-            client: client.unwrap_or_default(),
+            provider,
             registry: ToolRegistry::new(),
+            pricing: PricingTable::default(),
+            config: SwarmConfig::default(),
+            rate_limiter: None,
+            context_policy: None,
+            session_store: None,
+            middleware: Vec::new(),
+            hooks: Vec::new(),
+            metrics: None,
+            debug_hooks: DebugHooks::default(),
+            moderation: None,
+            input_guardrails: Vec::new(),
+            output_guardrails: Vec::new(),
+            pii: None,
+            live_agents: None,
+            memory: None,
+            agent_memory: None,
+            knowledge_base: None,
         }
     }
 
+    // Applies reloadable agent settings at the start of every turn
+    pub fn with_live_agents(mut self, live_agents: LiveAgents) -> Self {
+        self.live_agents = Some(live_agents);
+        self
+    }
+
+    // Stores every completed exchange and recalls related ones into later turns
+    pub fn with_memory(mut self, memory: Arc<SemanticMemory>) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    // Remembers facts about each run's `user_id` and recalls them into later runs
+    pub fn with_agent_memory(mut self, memory: Arc<AgentMemory>) -> Self {
+        self.agent_memory = Some(memory);
+        self
+    }
+
+    // Installs the knowledge base filled by ingest_documents
+    pub fn with_knowledge_base(mut self, knowledge_base: KnowledgeBase) -> Self {
+        self.knowledge_base = Some(knowledge_base);
+        self
+    }
+
+    pub fn knowledge_base(&self) -> Option<&KnowledgeBase> {
+        self.knowledge_base.as_ref()
+    }
+
+    // Embeds documents into the knowledge base, returning how many were stored
+    pub async fn ingest_documents(&self, documents: Vec<Document>) -> Result<usize> {
+        let knowledge_base = self
+            .knowledge_base
+            .as_ref()
+            .ok_or_else(|| SwarmError::Config("no knowledge base configured".to_string()))?;
+        knowledge_base.ingest(documents).await
+    }
+
+    // Installs the store backing run_session
+    pub fn with_session_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.session_store = Some(store);
+        self
+    }
+
+    pub fn session_store(&self) -> Option<&Arc<dyn SessionStore>> {
+        self.session_store.as_ref()
+    }
+
+    // Appends a middleware wrapped around every tool call
+    pub fn with_tool_middleware(mut self, middleware: impl ToolMiddleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    // Appends lifecycle hooks called on every run
+    pub fn with_hooks(mut self, hooks: impl SwarmHooks + 'static) -> Self {
+        self.hooks.push(Arc::new(hooks));
+        self
+    }
+
+    // Routes the diagnostics of runs with `debug` set; they go to stdout by default
+    pub fn with_debug_sink(mut self, sink: impl DebugSink + 'static) -> Self {
+        self.debug_hooks = DebugHooks::new(Arc::new(sink));
+        self
+    }
+
+    // Installed hooks, followed by the debug sink when `debug` is set
+    fn hooks(&self, debug: bool) -> impl Iterator<Item = &dyn SwarmHooks> {
+        self.hooks
+            .iter()
+            .map(|hooks| hooks.as_ref())
+            .chain(debug.then_some(&self.debug_hooks as &dyn SwarmHooks))
+    }
+
+    // Sends a pattern's progress to the debug sink
+    pub(crate) fn debug_event(&self, pattern: &'static str, message: String) {
+        self.debug_hooks
+            .sink()
+            .record(DebugEvent::Pattern { pattern, message });
+    }
+
+    // Records run, turn, tool, token and provider error metrics into `metrics`
+    pub fn with_metrics(mut self, metrics: Arc<SwarmMetrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    pub fn metrics(&self) -> Option<&Arc<SwarmMetrics>> {
+        self.metrics.as_ref()
+    }
+
+    // Checks run input, and optionally replies, against the Moderations endpoint
+    pub fn with_moderation(mut self, moderation: Moderation) -> Self {
+        self.moderation = Some(Arc::new(moderation));
+        self
+    }
+
+    // Appends a guardrail checked, alongside the others, before a run's first model call
+    pub fn with_input_guardrail(mut self, guardrail: impl InputGuardrail + 'static) -> Self {
+        self.input_guardrails.push(Arc::new(guardrail));
+        self
+    }
+
+    // Appends a guardrail applied, in install order, to the final message of every run
+    pub fn with_output_guardrail(mut self, guardrail: impl OutputGuardrail + 'static) -> Self {
+        self.output_guardrails.push(Arc::new(guardrail));
+        self
+    }
+
+    // Masks personal data in everything sent to the model
+    pub fn with_pii_redaction(mut self, redactor: PiiRedactor) -> Self {
+        self.pii = Some(Arc::new(redactor));
+        self
+    }
+
+    // Installs a policy that fits history into the model's context window
+    pub fn with_context_policy(mut self, policy: impl ContextPolicy + 'static) -> Self {
+        self.context_policy = Some(Arc::new(policy));
+        self
+    }
+
+    // Replaces the swarm-wide configuration
+    pub fn with_config(mut self, config: SwarmConfig) -> Self {
+        self.rate_limiter = config.rate_limit.map(RateLimiter::new);
+        self.config = config;
+        self
+    }
+
+    pub fn config(&self) -> &SwarmConfig {
+        &self.config
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    // Replaces the pricing table used for cost accounting
+    pub fn with_pricing(mut self, pricing: PricingTable) -> Self {
+        self.pricing = pricing;
+        self
+    }
+
+    pub fn pricing(&self) -> &PricingTable {
+        &self.pricing
+    }
+
     // Registers a new tool with the swarm
     pub fn register_tool(
-        &mut self,
+        &self,
         name: &str,
         description: &str,
         parameters: Value,
@@ -41,184 +281,734 @@ impl Swarm {
             .register_tool(name, description, parameters, function);
     }
 
+    // Registers a tool whose parameters schema is generated from `Args`
+    pub fn register_tool_typed<Args, Output>(
+        &self,
+        name: &str,
+        description: &str,
+        function: impl Fn(Args) -> Output + Send + Sync + 'static,
+    ) where
+        Args: JsonSchema + DeserializeOwned,
+        Output: Serialize,
+    {
+        self.registry
+            .register_tool_typed(name, description, function);
+    }
+
+    // Registers a typed tool whose function is awaited when called
+    pub fn register_async_tool_typed<Args, Output, Fut>(
+        &self,
+        name: &str,
+        description: &str,
+        function: impl Fn(Args) -> Fut + Send + Sync + 'static,
+    ) where
+        Args: JsonSchema + DeserializeOwned,
+        Output: Serialize,
+        Fut: Future<Output = Output> + Send + 'static,
+    {
+        self.registry
+            .register_async_tool_typed(name, description, function);
+    }
+
+    // Registers a typed tool that gets mutable access to the run's typed context
+    pub fn register_context_tool<Ctx, Args, Output>(
+        &self,
+        name: &str,
+        description: &str,
+        function: impl Fn(&mut Ctx, Args) -> Output + Send + Sync + 'static,
+    ) where
+        Ctx: Send + 'static,
+        Args: JsonSchema + DeserializeOwned,
+        Output: Serialize,
+    {
+        self.registry
+            .register_context_tool(name, description, function);
+    }
+
+    // Tools registered with the swarm, e.g. to bind agents loaded from a file
+    pub fn registry(&self) -> &ToolRegistry {
+        &self.registry
+    }
+
+    // Registers a tool definition, e.g. one generated by `#[tool]`
+    pub fn register(&self, definition: ToolDefinition) {
+        self.registry.register(definition);
+    }
+
+    // Removes a registered tool; agents that list it fail with `UnboundTool` when resolved
+    pub fn deregister_tool(&self, name: &str) -> Option<ToolDefinition> {
+        self.registry.deregister_tool(name)
+    }
+
+    // Registers a new tool whose function is awaited when called
+    pub fn register_async_tool(
+        &self,
+        name: &str,
+        description: &str,
+        parameters: Value,
+        function: Box<dyn Fn(Value) -> BoxFuture<'static, Value> + Send + Sync>,
+    ) {
+        self.registry
+            .register_async_tool(name, description, parameters, function);
+    }
+
+    // Builds a chat request for the given agent and history
+    fn build_request(
+        &self,
+        agent: &Agent,
+        history: &[ChatCompletionRequestMessage],
+        context_variables: &ContextVariables,
+        context: &RunContext,
+        model_override: Option<&str>,
+    ) -> Result<ChatRequest> {
+        // 1. Prepend the active agent's instructions as a system message
+        let mut messages = Vec::with_capacity(history.len() + 1);
+        let instructions = agent.instructions.render(context_variables, context)?;
+        if !instructions.is_empty() {
+            messages.push(ChatCompletionRequestMessage::System(
+                ChatCompletionRequestSystemMessage {
+                    content: ChatCompletionRequestSystemMessageContent::Text(instructions),
+                    name: None,
+                },
+            ));
+        }
+        messages.extend_from_slice(history);
+
+        // 2. Carry over the agent's tools and model settings
+        Ok(ChatRequest {
+            model: model_override.unwrap_or(&agent.model).to_string(),
+            messages,
+            tools: agent
+                .tools
+                .iter()
+                .map(Tool::without_context_variables)
+                .collect(),
+            parallel_tool_calls: agent.parallel_tool_calls,
+            settings: agent.model_settings.clone(),
+        })
+    }
+
+    // Builds a request, adds the user's recalled memories and applies the context policy,
+    // if any
+    #[allow(clippy::too_many_arguments)]
+    async fn prepare_request(
+        &self,
+        agent: &Agent,
+        history: &[ChatCompletionRequestMessage],
+        context_variables: &ContextVariables,
+        context: &RunContext,
+        model_override: Option<&str>,
+        user_id: Option<&str>,
+        memory_context: Option<&str>,
+    ) -> Result<ChatRequest> {
+        let mut request =
+            self.build_request(agent, history, context_variables, context, model_override)?;
+        if let Some(memory) = &self.memory {
+            memory.inject(user_id, &mut request).await?;
+        }
+        if let Some(memory_context) = memory_context {
+            AgentMemory::inject(&mut request, memory_context);
+        }
+        match &self.context_policy {
+            Some(policy) => policy.apply(request).await,
+            None => Ok(request),
+        }
+    }
+
     // Gets chat completion from OpenAI API
     pub async fn get_chat_completion(
         &self,
         agent: &Agent,
         history: &[ChatCompletionRequestMessage],
-    ) -> Result<ChatCompletionResponseMessage, Box<dyn std::error::Error>> {
-        // 1. Convert agent tools to ChatCompletionTool format
-        let tools: Vec<ChatCompletionTool> = agent
+        context_variables: &ContextVariables,
+    ) -> Result<ChatCompletionResponseMessage> {
+        let response = self
+            .create_completion(
+                agent,
+                history,
+                context_variables,
+                &RunContext::default(),
+                None,
+                None,
+                None,
+            )
+            .await?;
+        Ok(response.message)
+    }
+
+    // Gets chat completion, optionally overriding the agent's model
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "swarm.completion",
+            skip_all,
+            fields(
+                agent = %agent.name,
+                model = model_override.unwrap_or(&agent.model),
+                stream = false,
+                prompt_tokens = tracing::field::Empty,
+                completion_tokens = tracing::field::Empty,
+                total_tokens = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                otel.name = format!("chat {}", model_override.unwrap_or(&agent.model)),
+                otel.kind = "client",
+                gen_ai.operation.name = "chat",
+                gen_ai.system = self.provider.system(),
+                gen_ai.request.model = model_override.unwrap_or(&agent.model),
+                gen_ai.agent.name = %agent.name,
+                gen_ai.usage.input_tokens = tracing::field::Empty,
+                gen_ai.usage.output_tokens = tracing::field::Empty,
+            )
+        )
+    )]
+    async fn create_completion(
+        &self,
+        agent: &Agent,
+        history: &[ChatCompletionRequestMessage],
+        context_variables: &ContextVariables,
+        context: &RunContext,
+        model_override: Option<&str>,
+        user_id: Option<&str>,
+        memory_context: Option<&str>,
+    ) -> Result<ChatResponse> {
+        let started = std::time::Instant::now();
+        let request = self
+            .prepare_request(
+                agent,
+                history,
+                context_variables,
+                context,
+                model_override,
+                user_id,
+                memory_context,
+            )
+            .await?;
+        let response = self
+            .config
+            .retry
+            .run(|| async {
+                self.throttle().await;
+                let response = self.provider.chat(request.clone()).await;
+                self.record_provider_result(&response);
+                response
+            })
+            .await?;
+        if let Some(usage) = &response.usage {
+            self.record_usage(usage).await;
+        }
+        telemetry::record_latency(started);
+        self.check_finish_reason(agent, &response)?;
+        Ok(response)
+    }
+
+    // Runs the input guardrails concurrently, failing on the first tripwire in install order
+    async fn check_input_guardrails(
+        &self,
+        messages: &[ChatCompletionRequestMessage],
+    ) -> Result<()> {
+        let decisions = futures::future::join_all(
+            self.input_guardrails
+                .iter()
+                .map(|guardrail| guardrail.check(messages)),
+        )
+        .await;
+        for (guardrail, decision) in self.input_guardrails.iter().zip(decisions) {
+            if let GuardrailDecision::Tripwire(reason) = decision {
+                return Err(SwarmError::GuardrailTripped {
+                    guardrail: guardrail.name().to_string(),
+                    reason,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    // Passes the final assistant message through the output guardrails, recording
+    // every decision other than `Pass`
+    async fn check_output_guardrails(&self, mut response: Response) -> Result<Response> {
+        let Some(ChatCompletionRequestMessage::Assistant(message)) = response.messages.last_mut()
+        else {
+            return Ok(response);
+        };
+        if self.output_guardrails.is_empty() || message.tool_calls.is_some() {
+            return Ok(response);
+        }
+        let mut text = message_text(&ChatCompletionRequestMessage::Assistant(message.clone()));
+        for guardrail in &self.output_guardrails {
+            let decision = guardrail.check(&text).await;
+            let blocked = matches!(decision, OutputDecision::Block(_));
+            match &decision {
+                OutputDecision::Pass => continue,
+                OutputDecision::Block(replacement) | OutputDecision::Rewrite(replacement) => {
+                    text = replacement.clone();
+                    message.content = Some(ChatCompletionRequestAssistantMessageContent::Text(
+                        text.clone(),
+                    ));
+                }
+                OutputDecision::Annotate(_) => {}
+            }
+            response.guardrail_outcomes.push(GuardrailOutcome {
+                guardrail: guardrail.name().to_string(),
+                decision,
+            });
+            if blocked {
+                break;
+            }
+        }
+        Ok(response)
+    }
+
+    // Fails completions the content filter stopped; truncated ones are surfaced
+    // through `Response::finish_reason` instead
+    fn check_finish_reason(&self, agent: &Agent, response: &ChatResponse) -> Result<()> {
+        match response.finish_reason {
+            Some(FinishReason::ContentFilter) => {
+                Err(SwarmError::ContentFiltered(agent.name.clone()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Waits for rate limit capacity before a provider call
+    async fn throttle(&self) {
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.acquire().await;
+        }
+    }
+
+    // Counts failed provider calls, including ones that are retried
+    fn record_provider_result<T>(&self, result: &Result<T>) {
+        if let (Err(_), Some(metrics)) = (result, &self.metrics) {
+            metrics.provider_error();
+        }
+    }
+
+    // Counts consumed tokens against the rate limit
+    async fn record_usage(&self, usage: &Usage) {
+        telemetry::record_usage(usage);
+        if let Some(metrics) = &self.metrics {
+            metrics.add_tokens(usage);
+        }
+        if let Some(limiter) = &self.rate_limiter {
+            limiter.record_tokens(usage.total_tokens).await;
+        }
+    }
+
+    // Executes a single tool call and returns its result
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "swarm.tool",
+            skip_all,
+            fields(
+                agent = %agent.name,
+                tool = %tool_call.function.name,
+                latency_ms = tracing::field::Empty,
+                otel.name = format!("execute_tool {}", tool_call.function.name),
+                gen_ai.operation.name = "execute_tool",
+                gen_ai.tool.name = %tool_call.function.name,
+                gen_ai.tool.call.id = %tool_call.id,
+            )
+        )
+    )]
+    async fn handle_tool_call(
+        &self,
+        agent: &Agent,
+        tool_call: &ChatCompletionMessageToolCall,
+        context_variables: &ContextVariables,
+        context: &RunContext,
+        deadline: Option<Instant>,
+        debug: bool,
+    ) -> Result<ToolResult> {
+        // 1. Get function from the agent's registry, falling back to the swarm's
+        let name = &self.resolve_tool_name(agent, &tool_call.function.name);
+        let function = agent
+            .registry
+            .as_ref()
+            .and_then(|registry| registry.get_function(name))
+            .or_else(|| self.registry.get_function(name));
+        let tool = agent
             .tools
             .iter()
-            .map(|f| {
-                ChatCompletionToolArgs::default()
-                    .r#type(ChatCompletionToolType::Function)
-                    .function(
-                        FunctionObjectArgs::default()
-                            .name(&f.name)
-                            .description(&f.description)
-                            .parameters(f.parameters.clone())
-                            .build()
-                            .unwrap(),
-                    )
-                    .build()
-                    .unwrap()
+            .find(|tool| tool.name == *name)
+            .cloned()
+            .or_else(|| {
+                agent
+                    .registry
+                    .as_ref()
+                    .and_then(|registry| registry.get_tool(name))
             })
-            .collect();
+            .or_else(|| self.registry.get_tool(name));
+        let declares_context_variables =
+            tool.as_ref().is_some_and(Tool::declares_context_variables);
+        let Some(func) = function else {
+            let result = ToolResult {
+                value: format!("error: tool {} not found.", name),
+                agent: None,
+                context_variables: ContextVariables::new(),
+                usage: Usage::default(),
+                cost_usd: 0.0,
+            };
+            for hooks in self.hooks(debug) {
+                hooks.on_tool_end(agent, name, &result);
+            }
+            return Ok(result);
+        };
 
-        // 2. Build chat completion request based on tools presence
-        let request = if tools.is_empty() {
-            CreateChatCompletionRequestArgs::default()
-                .max_tokens(512u32)
-                .model(agent.model.clone())
-                .messages(history.to_vec())
-                .build()?
-        } else {
-            CreateChatCompletionRequestArgs::default()
-                .max_tokens(512u32)
-                .model(agent.model.clone())
-                .messages(history.to_vec())
-                .tools(tools)
-                .build()?
+        // 2. Parse arguments, repairing near-misses and reporting the rest to the model
+        let mut args = match parse_arguments(&tool_call.function.arguments) {
+            Ok(args) => args,
+            Err(e) => {
+                if debug {
+                    self.debug_event("tool", format!("malformed arguments for {}: {}", name, e));
+                }
+                let result = ToolResult::from(ToolOutput::Error(format!(
+                    "could not parse arguments for tool {}: {}. Call it again with a valid JSON object.",
+                    name, e
+                )));
+                for hooks in self.hooks(debug) {
+                    hooks.on_tool_end(agent, name, &result);
+                }
+                return Ok(result);
+            }
         };
 
-        // 3. Send request and return first choice message
-        let response_message = self
-            .client
-            .chat()
-            .create(request)
-            .await?
-            .choices
-            .first()
-            .unwrap()
-            .message
-            .clone();
-        Ok(response_message)
-    }
-
-    // Processes function result into ToolResult format
-    fn handle_function_result(&self, raw_result: Value, debug: bool) -> ToolResult {
-        // 1. Handle object with 'value' key
-        match raw_result {
-            Value::Object(obj) if obj.contains_key("value") => {
-                let obj_clone = obj.clone();
-                serde_json::from_value(Value::Object(obj)).unwrap_or_else(|e| {
-                    if debug {
-                        println!("Error parsing Result: {}", e);
-                    }
-                    ToolResult {
-                        value: obj_clone["value"].as_str().unwrap_or("").to_string(),
-                        agent: None,
-                        context_variables: HashMap::new(),
-                    }
-                })
+        for hooks in self.hooks(debug) {
+            hooks.on_tool_start(agent, name, &args);
+        }
+
+        // 2.1 Let the model correct arguments that do not match the schema
+        if let Some(tool) = tool.filter(|_| self.config.validate_tool_arguments) {
+            let violations = schema::validate(&tool.without_context_variables().parameters, &args);
+            if !violations.is_empty() {
+                let result = ToolResult::from(ToolOutput::Error(
+                    serde_json::json!({
+                        "error": format!("invalid arguments for tool {}", name),
+                        "violations": violations,
+                    })
+                    .to_string(),
+                ));
+                for hooks in self.hooks(debug) {
+                    hooks.on_tool_end(agent, name, &result);
+                }
+                return Ok(result);
             }
-            // 2. Handle object with 'assistant' key
-            Value::Object(obj) if obj.contains_key("assistant") => ToolResult {
-                value: serde_json::to_string(&obj).unwrap(),
-                agent: Some(serde_json::from_value(Value::Object(obj)).unwrap()),
-                context_variables: HashMap::new(),
-            },
-            // 3. Handle other cases
-            _ => {
-                let value = raw_result.as_str().map(String::from).unwrap_or_else(|| {
-                    if debug {
-                        println!("Failed to cast response to string: {:?}", raw_result);
-                    }
-                    raw_result.to_string()
-                });
-                ToolResult {
-                    value,
-                    agent: None,
-                    context_variables: HashMap::new(),
+        }
+
+        // 3. Run middleware before hooks, which may rewrite arguments or answer directly
+        let mut response = None;
+        let mut entered = 0;
+        for middleware in &self.middleware {
+            entered += 1;
+            match middleware.before_call(name, args).await {
+                BeforeCall::Proceed(rewritten) => args = rewritten,
+                BeforeCall::Respond(output) => {
+                    response = Some(output);
+                    args = Value::Null;
+                    break;
                 }
             }
         }
+
+        // 4. Execute function unless a middleware already answered
+        let started = std::time::Instant::now();
+        let mut output = match response {
+            Some(output) => output,
+            None => {
+                self.execute_tool(
+                    &func,
+                    name,
+                    args,
+                    declares_context_variables.then_some(context_variables),
+                    context,
+                    deadline,
+                )
+                .await?
+            }
+        };
+        for middleware in self.middleware[..entered].iter().rev() {
+            output = middleware.after_call(name, output).await;
+        }
+        telemetry::record_latency(started);
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_tool(name, started.elapsed());
+        }
+
+        // 5. Process result
+        let result = ToolResult::from(output);
+        for hooks in self.hooks(debug) {
+            hooks.on_tool_end(agent, name, &result);
+        }
+        Ok(result)
     }
 
-    // Processes tool calls and returns response
-    fn handle_tool_calls(
+    // Name of the tool a call refers to. Exact names win over a namespaced tool the model
+    // called with a dot or without its namespace, see `ToolRegistry::resolve_name`
+    fn resolve_tool_name(&self, agent: &Agent, name: &str) -> String {
+        let registries: Vec<&ToolRegistry> = agent
+            .registry
+            .as_deref()
+            .into_iter()
+            .chain([&self.registry])
+            .collect();
+        if agent.tools.iter().any(|tool| tool.name == name)
+            || registries.iter().any(|registry| registry.contains(name))
+        {
+            return name.to_string();
+        }
+        registries
+            .iter()
+            .find_map(|registry| registry.resolve_name(name))
+            .unwrap_or_else(|| name.to_string())
+    }
+
+    // Executes a tool by name outside the model's native tool calling, e.g. for text-based
+    // agents. Unlike a model's tool call, an unknown tool or malformed arguments fail with
+    // `ToolNotFound` or `ToolArguments` instead of becoming the tool's reply
+    pub(crate) async fn call_tool(
         &self,
-        tool_calls: &Vec<ChatCompletionMessageToolCall>,
-        context_variables: &mut HashMap<String, String>,
+        agent: &Agent,
+        name: &str,
+        arguments: &str,
+        context_variables: &ContextVariables,
+        context: &RunContext,
         debug: bool,
-    ) -> Response {
-        let mut partial_response = Response {
-            messages: Vec::new(),
-            agent: None,
-            context_variables: HashMap::new(),
+    ) -> Result<ToolResult> {
+        let name = &self.resolve_tool_name(agent, name);
+        let registered = agent
+            .registry
+            .as_ref()
+            .is_some_and(|registry| registry.get_function(name).is_some())
+            || self.registry.get_function(name).is_some();
+        if !registered {
+            return Err(SwarmError::ToolNotFound(name.clone()));
+        }
+        parse_arguments(arguments).map_err(|source| SwarmError::ToolArguments {
+            name: name.clone(),
+            source,
+        })?;
+
+        let tool_call = ChatCompletionMessageToolCall {
+            id: String::new(),
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionCall {
+                name: name.to_string(),
+                arguments: arguments.to_string(),
+            },
         };
+        let deadline = self
+            .config
+            .turn_timeout
+            .map(|timeout| Instant::now() + timeout);
+        self.handle_tool_call(
+            agent,
+            &tool_call,
+            context_variables,
+            context,
+            deadline,
+            debug,
+        )
+        .await
+    }
 
-        // Process each tool call sequentially
-        for tool_call in tool_calls {
-            let name = &tool_call.function.name;
+    // Runs a tool function, within time limits and catching panics. Context variables
+    // are only passed to tools whose schema declares them.
+    async fn execute_tool(
+        &self,
+        func: &ToolFunction,
+        name: &str,
+        args: Value,
+        context_variables: Option<&ContextVariables>,
+        context: &RunContext,
+        deadline: Option<Instant>,
+    ) -> Result<ToolOutput> {
+        // 1. Add context variables to arguments if the tool declares them
+        let mut args_with_context = match args {
+            Value::Object(map) => map,
+            _ => {
+                return Ok(ToolOutput::Error(format!(
+                    "arguments for tool {} must be a JSON object",
+                    name
+                )))
+            }
+        };
+        if let Some(context_variables) = context_variables {
+            args_with_context.insert(
+                CONTEXT_VARIABLES.to_string(),
+                Value::Object(context_variables.clone()),
+            );
+        }
 
-            // 1. Get function from registry
-            if let Some(func) = self.registry.get_function(name) {
-                // 2. Parse arguments
-                let args: Value = serde_json::from_str(&tool_call.function.arguments)
-                    .expect("Failed to parse arguments");
+        // 2. Execute function within the tool and turn time limits, catching panics
+        let tool_deadline = self
+            .config
+            .tool_timeout_for(name)
+            .map(|timeout| Instant::now() + timeout);
+        let call = AssertUnwindSafe(func.call(Value::Object(args_with_context), context))
+            .catch_unwind()
+            .map(|result| {
+                result.unwrap_or_else(|panic| {
+                    ToolOutput::Error(format!("tool {} panicked: {}", name, panic_message(&panic)))
+                })
+            });
+        let output = match tool_deadline.into_iter().chain(deadline).min() {
+            Some(deadline) => match tokio::time::timeout_at(deadline, call).await {
+                Ok(output) => output,
+                Err(_) => ToolOutput::Error(format!("tool {} timed out.", name)),
+            },
+            None => call.await,
+        };
+        Ok(output)
+    }
 
-                if debug {
-                    println!("processing tool call: {} with arguments {:?}", name, args);
+    // Executes an approved tool call or reports why it was denied
+    async fn handle_reviewed_tool_call(
+        &self,
+        agent: &Agent,
+        reviewed: &std::result::Result<
+            ChatCompletionMessageToolCall,
+            (&ChatCompletionMessageToolCall, String),
+        >,
+        context_variables: &ContextVariables,
+        context: &RunContext,
+        deadline: Option<Instant>,
+        debug: bool,
+    ) -> Result<ToolResult> {
+        match reviewed {
+            Ok(tool_call) => {
+                self.handle_tool_call(
+                    agent,
+                    tool_call,
+                    context_variables,
+                    context,
+                    deadline,
+                    debug,
+                )
+                .await
+            }
+            Err((tool_call, reason)) => {
+                let result =
+                    ToolResult::from(ToolOutput::Error(format!("tool call denied: {}", reason)));
+                for hooks in self.hooks(debug) {
+                    hooks.on_tool_end(agent, &tool_call.function.name, &result);
                 }
+                Ok(result)
+            }
+        }
+    }
 
-                // 3. Add context variables to arguments
-                let mut args_with_context = args.as_object().unwrap().clone();
-                args_with_context.insert(
-                    "context_variables".to_string(),
-                    serde_json::to_value(&context_variables).unwrap(),
-                );
+    // Processes tool calls and returns response
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "swarm.tool_calls",
+            skip_all,
+            fields(agent = %agent.name, count = tool_calls.len())
+        )
+    )]
+    async fn handle_tool_calls(
+        &self,
+        agent: &Agent,
+        tool_calls: &[ChatCompletionMessageToolCall],
+        context_variables: &ContextVariables,
+        approval_handler: Option<&dyn ApprovalHandler>,
+        context: &RunContext,
+        debug: bool,
+        events: Option<&EventSender>,
+    ) -> Result<Response> {
+        let mut partial_response = Response::empty();
 
-                // 4. Execute function and process result
-                let raw_result = func(Value::Object(args_with_context));
-                if debug {
-                    println!("raw result: {:?}", raw_result);
-                }
-                let result = self.handle_function_result(raw_result, debug);
-                if debug {
-                    println!("tool result: {:?}", result);
+        // 1. Review tool calls one at a time before any of them runs
+        let mut reviewed = Vec::with_capacity(tool_calls.len());
+        for tool_call in tool_calls {
+            let approval = match approval_handler {
+                Some(handler) => handler.review(tool_call).await,
+                None => Approval::Approve,
+            };
+            reviewed.push(match approval {
+                Approval::Approve => Ok(tool_call.clone()),
+                Approval::Deny(reason) => Err((tool_call, reason)),
+                Approval::Edit(args) => {
+                    let mut tool_call = tool_call.clone();
+                    tool_call.function.arguments = args.to_string();
+                    Ok(tool_call)
                 }
+            });
+        }
 
-                // 5. Update response with results
-                partial_response
-                    .messages
-                    .push(ChatCompletionRequestMessage::Tool(
-                        ChatCompletionRequestToolMessage {
-                            content: ChatCompletionRequestToolMessageContent::Text(result.value),
-                            tool_call_id: tool_call.id.clone(),
-                        },
-                    ));
+        // 2. Execute approved tool calls, concurrently if the agent allows it
+        let deadline = self
+            .config
+            .turn_timeout
+            .map(|timeout| Instant::now() + timeout);
+        let results = if agent.parallel_tool_calls {
+            futures::future::try_join_all(reviewed.iter().map(|reviewed| {
+                self.handle_reviewed_tool_call(
+                    agent,
+                    reviewed,
+                    context_variables,
+                    context,
+                    deadline,
+                    debug,
+                )
+            }))
+            .await?
+        } else {
+            let mut results = Vec::with_capacity(reviewed.len());
+            for reviewed in &reviewed {
+                results.push(
+                    self.handle_reviewed_tool_call(
+                        agent,
+                        reviewed,
+                        context_variables,
+                        context,
+                        deadline,
+                        debug,
+                    )
+                    .await?,
+                );
+            }
+            results
+        };
 
-                partial_response
-                    .context_variables
-                    .extend(result.context_variables);
-                if let Some(agent) = result.agent {
-                    partial_response.agent = Some(agent);
-                }
-            } else {
-                if debug {
-                    println!("tool {} not found in function map.", name);
-                }
-                partial_response
-                    .messages
-                    .push(ChatCompletionRequestMessage::Tool(
-                        ChatCompletionRequestToolMessage {
-                            content: ChatCompletionRequestToolMessageContent::Text(format!(
-                                "error: tool {} not found.",
-                                name
-                            )),
-                            tool_call_id: tool_call.id.clone(),
-                        },
-                    ));
+        // 3. Update response with results in tool call order
+        for (tool_call, result) in tool_calls.iter().zip(results) {
+            if events.is_some() {
+                emit(
+                    events,
+                    SwarmEvent::ToolResult {
+                        tool_call_id: tool_call.id.clone(),
+                        name: tool_call.function.name.clone(),
+                        content: result.value.clone(),
+                    },
+                );
+            }
+            partial_response
+                .messages
+                .push(ChatCompletionRequestMessage::Tool(
+                    ChatCompletionRequestToolMessage {
+                        content: ChatCompletionRequestToolMessageContent::Text(result.value),
+                        tool_call_id: tool_call.id.clone(),
+                    },
+                ));
+
+            partial_response
+                .context_variables
+                .extend(result.context_variables);
+            partial_response.usage.add(&result.usage);
+            partial_response.cost_usd += result.cost_usd;
+            if let Some(agent) = result.agent {
+                partial_response.agent = Some(agent);
             }
         }
 
-        partial_response
+        Ok(partial_response)
     }
 
     // Main execution loop for the swarm
+    #[allow(clippy::too_many_arguments)]
     pub async fn run(
         &self,
         agent: Agent,
@@ -229,39 +1019,537 @@ impl Swarm {
         debug: bool,
         max_turns: Option<usize>,
         execute_tools: bool,
-    ) -> Result<Response, Box<dyn std::error::Error>> {
-        // 1. Handle streaming request
-        if stream {
-            return self.run_and_stream(
-                agent,
-                messages,
-                context_variables,
-                model_override,
-                debug,
-                max_turns,
-                execute_tools,
-            );
+    ) -> Result<Response> {
+        let options = RunOptions {
+            context_variables: string_context(context_variables.unwrap_or_default()),
+            model_override,
+            stream,
+            debug,
+            max_turns,
+            execute_tools,
+            ..Default::default()
+        };
+        self.run_with_options(agent, messages, options).await
+    }
+
+    // Starts a run configured through chained builder methods
+    pub fn runner(
+        &self,
+        agent: Agent,
+        messages: Vec<ChatCompletionRequestMessage>,
+    ) -> RunBuilder<'_, P> {
+        RunBuilder {
+            swarm: self,
+            agent,
+            messages,
+            options: RunOptions::default(),
         }
+    }
+
+    // Main execution loop driven by RunOptions
+    pub async fn run_with_options(
+        &self,
+        agent: Agent,
+        messages: Vec<ChatCompletionRequestMessage>,
+        options: RunOptions,
+    ) -> Result<Response> {
+        let state = RunState::new(agent, messages, options.context_variables.clone());
+        self.run_loop(state, options, None).await
+    }
 
-        // 2. Initialize execution context
-        let mut active_agent = agent;
-        let mut context_variables = context_variables.unwrap_or_default();
-        let mut history = messages.clone();
-        let init_len = messages.len();
+    // Runs with the JSON schema of `T` as the response format and parses the final
+    // reply into `T`, asking the model again up to `output_retries` times on failure
+    pub async fn run_typed<T: JsonSchema + DeserializeOwned>(
+        &self,
+        agent: Agent,
+        messages: Vec<ChatCompletionRequestMessage>,
+        mut options: RunOptions,
+    ) -> Result<TypedResponse<T>> {
+        // 1. Constrain replies to the schema of `T`
+        let response_format = schema::response_format_for::<T>(&schema_name::<T>());
+        let mut agent = agent;
+        let mut history = messages;
+        let init_len = history.len();
+        let mut usage = Usage::default();
+        let mut cost_usd = 0.0;
+        let mut attempt = 0;
+
+        loop {
+            agent.model_settings.response_format = Some(response_format.clone());
+            let mut response = self
+                .run_with_options(agent.clone(), history.clone(), options.clone())
+                .await?;
+            usage.add(&response.usage);
+            cost_usd += response.cost_usd;
+            history.extend(response.messages.iter().cloned());
+            if response.status == RunStatus::TurnLimitReached {
+                return Err(SwarmError::MaxTurnsExceeded(
+                    options.max_turns.unwrap_or(usize::MAX),
+                ));
+            }
+
+            // 2. Parse the last assistant message, tolerating code fences
+            let text = response
+                .messages
+                .iter()
+                .rev()
+                .find(|message| matches!(message, ChatCompletionRequestMessage::Assistant(_)))
+                .map(message_text)
+                .unwrap_or_default();
+            match parse_structured::<T>(&text) {
+                Ok(value) => {
+                    response.messages = history.split_off(init_len);
+                    response.usage = usage;
+                    response.cost_usd = cost_usd;
+                    return Ok(TypedResponse { value, response });
+                }
+                Err(e) if attempt < self.config.output_retries => {
+                    // 2.1 Point out the error and continue from where the run ended
+                    if options.debug {
+                        self.debug_event("run_typed", format!("failed to parse output: {}", e));
+                    }
+                    history.push(user_message(&format!(
+                        "Your reply could not be parsed: {}. Reply again with only JSON matching the required schema.",
+                        e
+                    )));
+                    if let Some(last) = response.agent {
+                        agent = last;
+                    }
+                    options.context_variables = response.context_variables;
+                    attempt += 1;
+                }
+                Err(e) => return Err(SwarmError::StructuredOutput(e)),
+            }
+        }
+    }
+
+    // Executes independent runs concurrently, up to the configured limit, returning
+    // each run's outcome in input order
+    pub async fn run_parallel(
+        &self,
+        runs: Vec<(Agent, Vec<ChatCompletionRequestMessage>)>,
+    ) -> Vec<Result<Response>> {
+        let limit = self.config.max_parallel_runs.unwrap_or(runs.len()).max(1);
+        futures::stream::iter(runs)
+            .map(|(agent, messages)| self.run_with_options(agent, messages, RunOptions::default()))
+            .buffered(limit)
+            .collect()
+            .await
+    }
+
+    // Continues a run from a checkpointed state
+    pub async fn resume(&self, mut state: RunState, options: RunOptions) -> Result<Response> {
+        if options.execute_tools {
+            state.active_agent = self.resolve_agent(state.active_agent)?;
+        }
+        self.run_loop(state, options, None).await
+    }
+
+    // Checks that a deserialized agent's tools are registered with the agent or the swarm;
+    // the swarm's functions are looked up at each call, so they are not copied
+    pub fn resolve_agent(&self, agent: Agent) -> Result<Agent> {
+        agent.resolve_tools(&self.registry)
+    }
+
+    // Runs against a stored session, loading prior history and saving the result
+    pub async fn run_session(
+        &self,
+        session_id: &str,
+        agent: Agent,
+        messages: Vec<ChatCompletionRequestMessage>,
+        mut options: RunOptions,
+    ) -> Result<Response> {
+        let store = self
+            .session_store
+            .as_ref()
+            .ok_or_else(|| SwarmError::Session("no session store configured".to_string()))?;
+
+        // 1. Prepend stored history; explicit context variables win over stored ones
+        let session = store.load(session_id).await?.unwrap_or_default();
+        let mut history = session.messages;
+        history.extend(messages);
+        let mut context_variables = session.context_variables;
+        context_variables.extend(options.context_variables);
+        options.context_variables = context_variables;
+        if options.user_id.is_none() {
+            options.user_id = Some(session_id.to_string());
+        }
+
+        // 2. Run and persist the extended history
+        let response = self
+            .run_with_options(agent, history.clone(), options)
+            .await?;
+        history.extend(response.messages.iter().cloned());
+        store
+            .save(session_id, &history, &response.context_variables)
+            .await?;
+        Ok(response)
+    }
+
+    // Continues a history branch and appends the run's messages to it
+    pub async fn run_history(
+        &self,
+        agent: Agent,
+        history: &mut History,
+        options: RunOptions,
+    ) -> Result<Response> {
+        let response = self
+            .run_with_options(agent, history.messages().to_vec(), options)
+            .await?;
+        history.record(&response);
+        Ok(response)
+    }
+
+    // Streams run events for every turn, ending with the final response
+    #[allow(clippy::too_many_arguments)]
+    pub fn run_and_stream<'a>(
+        &'a self,
+        agent: Agent,
+        messages: Vec<ChatCompletionRequestMessage>,
+        context_variables: Option<HashMap<String, String>>,
+        model_override: Option<String>,
+        debug: bool,
+        max_turns: Option<usize>,
+        execute_tools: bool,
+    ) -> impl Stream<Item = SwarmEvent> + 'a {
+        let options = RunOptions {
+            context_variables: string_context(context_variables.unwrap_or_default()),
+            model_override,
+            stream: true,
+            debug,
+            max_turns,
+            execute_tools,
+            ..Default::default()
+        };
+        self.run_stream(agent, messages, options)
+    }
+
+    // Streams typed run events as configured by RunOptions
+    pub fn run_stream<'a>(
+        &'a self,
+        agent: Agent,
+        messages: Vec<ChatCompletionRequestMessage>,
+        mut options: RunOptions,
+    ) -> impl Stream<Item = SwarmEvent> + 'a {
+        options.stream = true;
+        let (tx, rx) = mpsc::unbounded();
+
+        // Drive the turn loop alongside the receiver; it yields no items itself
+        let driver = async move {
+            let state = RunState::new(agent, messages, options.context_variables.clone());
+            let event = match self.run_loop(state, options, Some(&tx)).await {
+                Ok(response) => SwarmEvent::RunCompleted(response),
+                Err(e) => SwarmEvent::RunFailed(e),
+            };
+            let _ = tx.unbounded_send(event);
+        };
+
+        futures::stream::select(
+            rx,
+            driver
+                .into_stream()
+                .filter_map(|_| futures::future::ready(None)),
+        )
+    }
+
+    // Execution loop shared by the blocking, streaming and resumed entry points
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "swarm.run",
+            skip_all,
+            fields(
+                agent = %state.active_agent.name,
+                model = %state.active_agent.model,
+                prompt_tokens = tracing::field::Empty,
+                completion_tokens = tracing::field::Empty,
+                total_tokens = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                otel.name = format!("invoke_agent {}", state.active_agent.name),
+                gen_ai.operation.name = "invoke_agent",
+                gen_ai.system = self.provider.system(),
+                gen_ai.agent.name = %state.active_agent.name,
+                gen_ai.request.model = %state.active_agent.model,
+                gen_ai.usage.input_tokens = tracing::field::Empty,
+                gen_ai.usage.output_tokens = tracing::field::Empty,
+            )
+        )
+    )]
+    pub(crate) async fn run_loop(
+        &self,
+        state: RunState,
+        options: RunOptions,
+        events: Option<&EventSender>,
+    ) -> Result<Response> {
+        let debug = options.debug;
+        let started = std::time::Instant::now();
+        for hooks in self.hooks(debug) {
+            hooks.on_run_start(&state.active_agent, &state.history);
+        }
+        if let Some(metrics) = &self.metrics {
+            metrics.run_started();
+        }
+        let speech = options.speech.clone();
+        let user_id = options.user_id.clone();
+        let input =
+            (self.memory.is_some() || self.agent_memory.is_some()).then(|| state.history.clone());
+        let mut result = match self.run_turns(state, options, events).await {
+            Ok(response) => self.check_output_guardrails(response).await,
+            Err(e) => Err(e),
+        };
+        // Remember the exchange and speak the reply as they stand after the guardrails. The
+        // run has finished either way, so a failure to remember is reported, not returned
+        if let (Some(memory), Some(input), Ok(response)) = (&self.memory, &input, &mut result) {
+            if let Err(e) = memory
+                .record(user_id.as_deref(), input, &response.messages)
+                .await
+            {
+                response.memory_error = Some(e.to_string());
+            }
+        }
+        if let (Some(memory), Some(user_id), Some(input), Ok(response)) =
+            (&self.agent_memory, user_id, input, &result)
+        {
+            let mut transcript = input;
+            transcript.extend(response.messages.iter().cloned());
+            memory.extract_in_background(user_id, transcript);
+        }
+        if let (Some(synthesizer), Ok(response)) = (&speech, &mut result) {
+            if let Err(e) = synthesizer.speak(response).await {
+                response.speech_error = Some(e.to_string());
+            }
+        }
+        if let Ok(response) = &result {
+            telemetry::record_usage(&response.usage);
+        }
+        telemetry::record_latency(started);
+        if let Some(metrics) = &self.metrics {
+            metrics.run_finished(result.is_ok());
+        }
+        for hooks in self.hooks(debug) {
+            hooks.on_run_end(&result);
+        }
+        result
+    }
+
+    async fn run_turns(
+        &self,
+        state: RunState,
+        options: RunOptions,
+        events: Option<&EventSender>,
+    ) -> Result<Response> {
+        let RunOptions {
+            model_override,
+            stream,
+            debug,
+            max_turns,
+            max_cost_usd,
+            max_total_tokens,
+            execute_tools,
+            on_checkpoint,
+            stop_when,
+            on_token,
+            approval_handler,
+            context,
+            user_id,
+            ..
+        } = options;
+        let checkpoint = |state: &RunState| {
+            if let Some(on_checkpoint) = &on_checkpoint {
+                on_checkpoint(state);
+            }
+        };
+
+        // Tools that start runs of their own get what is left of this run's limits
+        let tool_context = |state: &RunState| {
+            context.clone().with_limits(RunLimits {
+                max_turns,
+                max_cost_usd: max_cost_usd.map(|limit| (limit - state.cost_usd).max(0.0)),
+                max_total_tokens: max_total_tokens
+                    .map(|limit| limit.saturating_sub(state.usage.total_tokens)),
+            })
+        };
+
+        // 1. Initialize execution context, finishing tool calls left by a checkpoint
+        //    and checking the new input
+        let mut state = state;
         let max_turns = max_turns.unwrap_or(usize::MAX);
+        if !state.pending_tool_calls.is_empty() {
+            let tool_context = tool_context(&state);
+            self.execute_pending_tool_calls(
+                &mut state,
+                approval_handler.as_deref(),
+                &tool_context,
+                debug,
+                events,
+            )
+            .await?;
+            checkpoint(&state);
+        }
+        if let Some(moderation) = &self.moderation {
+            moderation.moderate_input(&mut state.history).await?;
+        }
+        if state.turn == 0 {
+            self.check_input_guardrails(&state.history).await?;
+        }
+        let memory_context = match (&self.agent_memory, &user_id) {
+            (Some(memory), Some(user_id)) => memory.context_message(user_id).await?,
+            _ => None,
+        };
+
+        // 2. Main execution loop
+        let mut redacted = 0;
+        let mut repeated: Option<(String, Value)> = None;
+        let mut repeats = 0;
+        loop {
+            if state.turn >= max_turns {
+                state.status = RunStatus::TurnLimitReached;
+                break;
+            }
+            if max_cost_usd.is_some_and(|limit| state.cost_usd >= limit)
+                || max_total_tokens.is_some_and(|limit| state.usage.total_tokens >= limit)
+            {
+                state.status = RunStatus::BudgetExceeded;
+                break;
+            }
+            if let Some(pii) = &self.pii {
+                pii.redact_messages(&mut state.history[redacted..], &mut state.redactions);
+                redacted = state.history.len();
+            }
+            if let Some(live_agents) = &self.live_agents {
+                live_agents.apply(&mut state.active_agent);
+            }
+            emit(
+                events,
+                SwarmEvent::TurnStarted {
+                    turn: state.turn,
+                    agent: state.active_agent.name.clone(),
+                },
+            );
+            for hooks in self.hooks(debug) {
+                hooks.on_turn_start(state.turn, &state.active_agent);
+            }
+            let active_agent = &state.active_agent;
+            let span = telemetry::turn_span(
+                state.turn,
+                &active_agent.name,
+                model_override.as_deref().unwrap_or(&active_agent.model),
+            );
+            state.turn += 1;
 
-        // 3. Main execution loop
-        while history.len() - init_len < max_turns {
-            // 3.1 Get completion
-            let completion: ChatCompletionResponseMessage =
-                self.get_chat_completion(&active_agent, &history).await?;
+            // 2.1 Get completion, token by token when streaming
+            let response = if stream || on_token.is_some() {
+                telemetry::in_span(
+                    self.stream_completion(
+                        active_agent,
+                        &state.history,
+                        &state.context_variables,
+                        &context,
+                        model_override.as_deref(),
+                        user_id.as_deref(),
+                        memory_context.as_deref(),
+                        events,
+                        on_token.as_deref(),
+                    ),
+                    &span,
+                )
+                .await?
+            } else {
+                let response = telemetry::in_span(
+                    self.create_completion(
+                        active_agent,
+                        &state.history,
+                        &state.context_variables,
+                        &context,
+                        model_override.as_deref(),
+                        user_id.as_deref(),
+                        memory_context.as_deref(),
+                    ),
+                    &span,
+                )
+                .await?;
+                if let Some(content) = &response.message.content {
+                    emit(
+                        events,
+                        SwarmEvent::TokenDelta {
+                            agent: active_agent.name.clone(),
+                            content: content.clone(),
+                        },
+                    );
+                }
+                response
+            };
+            if let Some(turn_usage) = &response.usage {
+                let model = model_override.as_deref().unwrap_or(&active_agent.model);
+                state.usage.add(turn_usage);
+                state.cost_usd += self.pricing.cost(model, turn_usage);
+                emit(
+                    events,
+                    SwarmEvent::UsageUpdated {
+                        usage: state.usage,
+                        cost_usd: state.cost_usd,
+                    },
+                );
+            }
+            for hooks in self.hooks(debug) {
+                hooks.on_llm_response(active_agent, &response.message, response.usage.as_ref());
+            }
+            if response.system_fingerprint.is_some() {
+                state.system_fingerprint = response.system_fingerprint;
+            }
+            state.finish_reason = response.finish_reason;
+            let mut completion = response.message;
+            if let Some(moderation) = &self.moderation {
+                moderation.moderate_output(&mut completion.content).await?;
+            }
+            if let Some(pii) = self.pii.as_ref().filter(|pii| pii.redacts_tool_arguments()) {
+                for tool_call in completion.tool_calls.iter_mut().flatten() {
+                    tool_call.function.arguments =
+                        pii.redact(&tool_call.function.arguments, &mut state.redactions);
+                }
+            }
 
-            if debug {
-                println!("Received completion: {:?}", completion);
+            // 2.2 Catch the same tool call repeating with the same arguments
+            let mut nudge = None;
+            if let Some(detection) = &self.config.loop_detection {
+                for tool_call in completion.tool_calls.iter().flatten() {
+                    let call = (
+                        tool_call.function.name.clone(),
+                        serde_json::from_str(&tool_call.function.arguments).unwrap_or_else(|_| {
+                            Value::String(tool_call.function.arguments.clone())
+                        }),
+                    );
+                    if repeated.as_ref() == Some(&call) {
+                        repeats += 1;
+                    } else {
+                        repeated = Some(call);
+                        repeats = 1;
+                    }
+                }
+                if repeats >= detection.threshold {
+                    let name = repeated
+                        .as_ref()
+                        .map(|(name, _)| name.clone())
+                        .unwrap_or_default();
+                    match &detection.action {
+                        LoopAction::Error => return Err(SwarmError::ToolLoop { name, repeats }),
+                        LoopAction::Stop => {
+                            state.status = RunStatus::ToolLoopDetected;
+                            break;
+                        }
+                        LoopAction::Nudge(message) => {
+                            nudge = Some(message.clone());
+                            repeated = None;
+                            repeats = 0;
+                        }
+                    }
+                }
             }
 
-            // 3.2 Add assistant message to history
-            history.push(ChatCompletionRequestMessage::Assistant(
+            // 2.3 Add assistant message to history
+            let turn_start = state.history.len();
+            let turn_agent = state.active_agent.name.clone();
+            state.history.push(ChatCompletionRequestMessage::Assistant(
                 ChatCompletionRequestAssistantMessage {
                     content: completion
                         .content
@@ -272,47 +1560,1328 @@ impl Swarm {
                 },
             ));
 
-            // 3.3 Break if no tool calls
-            if completion.tool_calls.is_none() {
-                if debug {
-                    println!("Ending turn.");
-                }
+            // 2.4 Break if no tool calls or tool execution is disabled
+            let Some(tool_calls) = completion.tool_calls.filter(|_| execute_tools) else {
+                checkpoint(&state);
                 break;
+            };
+
+            // 2.5 Handle tool calls and update state
+            for tool_call in &tool_calls {
+                emit(
+                    events,
+                    SwarmEvent::ToolCallRequested {
+                        agent: state.active_agent.name.clone(),
+                        tool_call: tool_call.clone(),
+                    },
+                );
+            }
+            state.pending_tool_calls = tool_calls;
+            checkpoint(&state);
+            let tool_context = tool_context(&state);
+            telemetry::in_span(
+                self.execute_pending_tool_calls(
+                    &mut state,
+                    approval_handler.as_deref(),
+                    &tool_context,
+                    debug,
+                    events,
+                ),
+                &span,
+            )
+            .await?;
+            if let Some(nudge) = nudge {
+                state.history.push(user_message(&nudge));
             }
+            checkpoint(&state);
 
-            // 3.4 Handle tool calls and update state
-            let partial_response = self.handle_tool_calls(
-                &completion.tool_calls.unwrap(),
-                &mut context_variables,
+            // 2.6 Stop early if the caller's predicate holds
+            if let Some(stop_when) = &stop_when {
+                let turn = Turn {
+                    index: state.turn - 1,
+                    agent: turn_agent,
+                    messages: state.history[turn_start..].to_vec(),
+                };
+                if stop_when(&state.clone().into_response(), &turn) {
+                    state.status = RunStatus::Stopped;
+                    break;
+                }
+            }
+        }
+
+        // 3. Return final response
+        if let Some(metrics) = &self.metrics {
+            metrics.observe_turns(state.turn);
+        }
+        Ok(state.into_response())
+    }
+
+    // Executes the state's pending tool calls and applies their results
+    async fn execute_pending_tool_calls(
+        &self,
+        state: &mut RunState,
+        approval_handler: Option<&dyn ApprovalHandler>,
+        context: &RunContext,
+        debug: bool,
+        events: Option<&EventSender>,
+    ) -> Result<()> {
+        let partial_response = self
+            .handle_tool_calls(
+                &state.active_agent,
+                &state.pending_tool_calls,
+                &state.context_variables,
+                approval_handler,
+                context,
                 debug,
+                events,
+            )
+            .await?;
+        state.pending_tool_calls.clear();
+
+        if partial_response.usage != Usage::default() || partial_response.cost_usd > 0.0 {
+            state.usage.add(&partial_response.usage);
+            state.cost_usd += partial_response.cost_usd;
+            emit(
+                events,
+                SwarmEvent::UsageUpdated {
+                    usage: state.usage,
+                    cost_usd: state.cost_usd,
+                },
             );
+        }
+        state.history.extend(partial_response.messages);
+        state
+            .context_variables
+            .extend(partial_response.context_variables);
+        if let Some(new_agent) = partial_response.agent {
+            // Agents handed over as JSON arrive without their tool functions
+            let new_agent = self.resolve_agent(new_agent)?;
+            for hooks in self.hooks(debug) {
+                hooks.on_handoff(&state.active_agent, &new_agent);
+            }
+            emit(
+                events,
+                SwarmEvent::AgentHandoff {
+                    from: state.active_agent.name.clone(),
+                    to: new_agent.name.clone(),
+                },
+            );
+            state.active_agent = new_agent;
+        }
+        Ok(())
+    }
 
-            history.extend(partial_response.messages);
-            context_variables.extend(partial_response.context_variables);
-            if let Some(new_agent) = partial_response.agent {
-                active_agent = new_agent;
+    // Streams a completion, emitting token deltas and reassembling tool calls
+    #[allow(clippy::too_many_arguments)]
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(
+            name = "swarm.completion",
+            skip_all,
+            fields(
+                agent = %agent.name,
+                model = model_override.unwrap_or(&agent.model),
+                stream = true,
+                prompt_tokens = tracing::field::Empty,
+                completion_tokens = tracing::field::Empty,
+                total_tokens = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                otel.name = format!("chat {}", model_override.unwrap_or(&agent.model)),
+                otel.kind = "client",
+                gen_ai.operation.name = "chat",
+                gen_ai.system = self.provider.system(),
+                gen_ai.request.model = model_override.unwrap_or(&agent.model),
+                gen_ai.agent.name = %agent.name,
+                gen_ai.usage.input_tokens = tracing::field::Empty,
+                gen_ai.usage.output_tokens = tracing::field::Empty,
+            )
+        )
+    )]
+    async fn stream_completion(
+        &self,
+        agent: &Agent,
+        history: &[ChatCompletionRequestMessage],
+        context_variables: &ContextVariables,
+        context: &RunContext,
+        model_override: Option<&str>,
+        user_id: Option<&str>,
+        memory_context: Option<&str>,
+        events: Option<&EventSender>,
+        on_token: Option<&(dyn Fn(&str) + Send + Sync)>,
+    ) -> Result<ChatResponse> {
+        // 1. Open completion stream
+        let started = std::time::Instant::now();
+        let request = self
+            .prepare_request(
+                agent,
+                history,
+                context_variables,
+                context,
+                model_override,
+                user_id,
+                memory_context,
+            )
+            .await?;
+        let mut completion = self
+            .config
+            .retry
+            .run(|| async {
+                self.throttle().await;
+                let stream = self.provider.chat_stream(request.clone()).await;
+                self.record_provider_result(&stream);
+                stream
+            })
+            .await?;
+
+        // 2. Forward deltas while accumulating the assistant message
+        let mut content: Option<String> = None;
+        let mut refusal: Option<String> = None;
+        let mut tool_calls: Vec<ChatCompletionMessageToolCall> = Vec::new();
+        let mut usage: Option<Usage> = None;
+        let mut system_fingerprint: Option<String> = None;
+        let mut finish_reason: Option<FinishReason> = None;
+        while let Some(chunk) = completion.next().await {
+            let chunk = chunk?;
+            if chunk.finish_reason.is_some() {
+                finish_reason = chunk.finish_reason;
+            }
+            if let Some(chunk_usage) = chunk.usage {
+                usage = Some(chunk_usage);
+            }
+            if chunk.system_fingerprint.is_some() {
+                system_fingerprint = chunk.system_fingerprint;
+            }
+            let Some(delta) = chunk.delta else {
+                continue;
+            };
+            if let Some(text) = delta.content {
+                emit(
+                    events,
+                    SwarmEvent::TokenDelta {
+                        agent: agent.name.clone(),
+                        content: text.clone(),
+                    },
+                );
+                if let Some(on_token) = on_token {
+                    on_token(&text);
+                }
+                content.get_or_insert_with(String::new).push_str(&text);
+            }
+            if let Some(text) = delta.refusal {
+                refusal.get_or_insert_with(String::new).push_str(&text);
+            }
+            for tool_call_chunk in delta.tool_calls.iter().flatten() {
+                merge_tool_call_chunk(&mut tool_calls, tool_call_chunk)?;
             }
         }
 
-        // 4. Return final response
-        Ok(Response {
-            messages: history[init_len..].to_vec(),
-            agent: Some(active_agent),
-            context_variables,
+        // 3. Assemble the complete message
+        let tool_calls = finish_tool_calls(tool_calls);
+        #[allow(deprecated)]
+        let message = ChatCompletionResponseMessage {
+            content,
+            refusal,
+            tool_calls: (!tool_calls.is_empty()).then_some(tool_calls),
+            role: Role::Assistant,
+            function_call: None,
+        };
+        if let Some(usage) = &usage {
+            self.record_usage(usage).await;
+        }
+        telemetry::record_latency(started);
+        let response = ChatResponse {
+            message,
+            usage,
+            system_fingerprint,
+            finish_reason,
+        };
+        self.check_finish_reason(agent, &response)?;
+        Ok(response)
+    }
+}
+
+pub(crate) type EventSender = mpsc::UnboundedSender<SwarmEvent>;
+
+// Sends an event to the run's listener, if any
+pub(crate) fn emit(events: Option<&EventSender>, event: SwarmEvent) {
+    if let Some(events) = events {
+        let _ = events.unbounded_send(event);
+    }
+}
+
+// Name for the response format of `T`, e.g. `Weather` for `my_crate::Weather<u8>`
+fn schema_name<T>() -> String {
+    let name = std::any::type_name::<T>();
+    let name = name.split('<').next().unwrap_or(name);
+    let name = name.rsplit("::").next().unwrap_or(name);
+    name.chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '-')
+        .collect()
+}
+
+// Parses a structured reply, stripping a surrounding markdown code fence
+fn parse_structured<T: DeserializeOwned>(text: &str) -> serde_json::Result<T> {
+    serde_json::from_str(schema::strip_code_fence(text))
+}
+
+// Parses tool arguments into a JSON object. Empty arguments count as no arguments;
+// invalid JSON gets one lenient repair attempt before the parse error is reported.
+fn parse_arguments(arguments: &str) -> serde_json::Result<Value> {
+    let arguments = arguments.trim();
+    if arguments.is_empty() {
+        return Ok(Value::Object(Default::default()));
+    }
+    let value = serde_json::from_str::<Value>(arguments)
+        .or_else(|e| serde_json::from_str::<Value>(&repair_json(arguments)).map_err(|_| e))?;
+    if value.is_object() {
+        Ok(value)
+    } else {
+        Err(serde::de::Error::custom("arguments must be a JSON object"))
+    }
+}
+
+// Fixes the usual defects of model-written JSON: code fences, trailing commas and
+// output cut off before its strings, arrays and objects were closed
+fn repair_json(text: &str) -> String {
+    // 1. Strip a surrounding markdown code fence
+    let text = schema::strip_code_fence(text);
+
+    // 2. Copy the text, dropping commas in front of closing brackets
+    let mut repaired = String::with_capacity(text.len() + 8);
+    let mut closers = Vec::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in text.trim().chars() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+        } else {
+            match c {
+                '"' => in_string = true,
+                '{' => closers.push('}'),
+                '[' => closers.push(']'),
+                '}' | ']' => {
+                    trim_trailing_comma(&mut repaired);
+                    closers.pop();
+                }
+                _ => {}
+            }
+        }
+        repaired.push(c);
+    }
+
+    // 3. Close whatever was left open
+    if in_string {
+        if escaped {
+            repaired.pop();
+        }
+        repaired.push('"');
+    }
+    trim_trailing_comma(&mut repaired);
+    if repaired.ends_with(':') {
+        repaired.push_str("null");
+    }
+    while let Some(closer) = closers.pop() {
+        repaired.push(closer);
+    }
+    repaired
+}
+
+fn trim_trailing_comma(text: &mut String) {
+    let trimmed = text.trim_end().len();
+    text.truncate(trimmed);
+    if text.ends_with(',') {
+        text.pop();
+    }
+}
+
+// Converts legacy string-only context variables
+fn string_context(context_variables: HashMap<String, String>) -> ContextVariables {
+    context_variables
+        .into_iter()
+        .map(|(key, value)| (key, Value::String(value)))
+        .collect()
+}
+
+// Extracts the message of a caught panic payload
+fn panic_message(panic: &Box<dyn Any + Send>) -> &str {
+    panic
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
+}
+
+// Highest tool call index accepted from a stream; the index comes from the server and
+// sizes the list of calls, so it must not be trusted
+const MAX_TOOL_CALL_INDEX: i32 = 127;
+
+// Folds a streamed tool call fragment into the calls accumulated so far. Fragments of
+// parallel calls may interleave; the index says which call each belongs to.
+fn merge_tool_call_chunk(
+    tool_calls: &mut Vec<ChatCompletionMessageToolCall>,
+    chunk: &ChatCompletionMessageToolCallChunk,
+) -> Result<()> {
+    if !(0..=MAX_TOOL_CALL_INDEX).contains(&chunk.index) {
+        return Err(SwarmError::Provider(format!(
+            "streamed tool call index {} is out of range",
+            chunk.index
+        )));
+    }
+    let index = chunk.index as usize;
+    while tool_calls.len() <= index {
+        tool_calls.push(ChatCompletionMessageToolCall {
+            id: String::new(),
+            r#type: ChatCompletionToolType::Function,
+            function: FunctionCall {
+                name: String::new(),
+                arguments: String::new(),
+            },
+        });
+    }
+
+    // Ids and names are set once; some servers repeat them on every fragment, so only
+    // arguments are appended
+    let tool_call = &mut tool_calls[index];
+    if let Some(id) = &chunk.id {
+        merge_fragment(&mut tool_call.id, id);
+    }
+    if let Some(function) = &chunk.function {
+        if let Some(name) = &function.name {
+            merge_fragment(&mut tool_call.function.name, name);
+        }
+        if let Some(arguments) = &function.arguments {
+            tool_call.function.arguments.push_str(arguments);
+        }
+    }
+    Ok(())
+}
+
+// Replaces the value with a fragment that repeats or extends it, and appends any other
+fn merge_fragment(value: &mut String, fragment: &str) {
+    if fragment.starts_with(value.as_str()) {
+        *value = fragment.to_string();
+    } else {
+        value.push_str(fragment);
+    }
+}
+
+// Drops slots no fragment named, e.g. gaps in the indices, and gives calls streamed
+// without an id one so their results can be matched
+fn finish_tool_calls(
+    tool_calls: Vec<ChatCompletionMessageToolCall>,
+) -> Vec<ChatCompletionMessageToolCall> {
+    tool_calls
+        .into_iter()
+        .enumerate()
+        .filter(|(_, tool_call)| !tool_call.function.name.is_empty())
+        .map(|(index, mut tool_call)| {
+            if tool_call.id.is_empty() {
+                tool_call.id = format!("call_{}", index);
+            }
+            tool_call
         })
+        .collect()
+}
+
+// Chainable run configuration, awaited to execute the run
+pub struct RunBuilder<'a, P: ChatProvider = OpenAIProvider> {
+    swarm: &'a Swarm<P>,
+    agent: Agent,
+    messages: Vec<ChatCompletionRequestMessage>,
+    options: RunOptions,
+}
+
+impl<P: ChatProvider> RunBuilder<'_, P> {
+    pub fn context_variables(mut self, context_variables: ContextVariables) -> Self {
+        self.options.context_variables = context_variables;
+        self
     }
 
-    // Placeholder for streaming implementation
-    fn run_and_stream(
-        &self,
-        agent: Agent,
-        messages: Vec<ChatCompletionRequestMessage>,
-        context_variables: Option<HashMap<String, String>>,
-        model_override: Option<String>,
-        debug: bool,
-        max_turns: Option<usize>,
-        execute_tools: bool,
-    ) -> Result<Response, Box<dyn std::error::Error>> {
-        unimplemented!()
+    pub fn context_variable(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.options
+            .context_variables
+            .insert(key.to_string(), value.into());
+        self
+    }
+
+    pub fn model_override(mut self, model: impl Into<String>) -> Self {
+        self.options.model_override = Some(model.into());
+        self
+    }
+
+    pub fn stream(mut self, stream: bool) -> Self {
+        self.options.stream = stream;
+        self
+    }
+
+    pub fn debug(mut self, debug: bool) -> Self {
+        self.options.debug = debug;
+        self
+    }
+
+    pub fn max_turns(mut self, max_turns: usize) -> Self {
+        self.options.max_turns = Some(max_turns);
+        self
+    }
+
+    pub fn max_cost_usd(mut self, max_cost_usd: f64) -> Self {
+        self.options.max_cost_usd = Some(max_cost_usd);
+        self
+    }
+
+    pub fn max_total_tokens(mut self, max_total_tokens: u32) -> Self {
+        self.options.max_total_tokens = Some(max_total_tokens);
+        self
+    }
+
+    pub fn execute_tools(mut self, execute_tools: bool) -> Self {
+        self.options.execute_tools = execute_tools;
+        self
+    }
+
+    pub fn on_checkpoint(
+        mut self,
+        on_checkpoint: impl Fn(&RunState) + Send + Sync + 'static,
+    ) -> Self {
+        self.options.on_checkpoint = Some(Arc::new(on_checkpoint));
+        self
+    }
+
+    pub fn stop_when(
+        mut self,
+        stop_when: impl Fn(&Response, &Turn) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.options = self.options.stop_when(stop_when);
+        self
+    }
+
+    pub fn on_token(mut self, on_token: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.options = self.options.on_token(on_token);
+        self
+    }
+
+    // Reads the final reply aloud into `Response::speech`
+    pub fn speech(mut self, synthesizer: Synthesizer) -> Self {
+        self.options = self.options.speech(synthesizer);
+        self
+    }
+
+    // Recalls and updates the facts the swarm's `AgentMemory` keeps about this user
+    pub fn user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.options = self.options.user_id(user_id);
+        self
+    }
+
+    // Shares typed application state with context-aware tools and instructions
+    pub fn context<Ctx: Send + 'static>(mut self, ctx: Ctx) -> Self {
+        self.options.context = RunContext::new(ctx);
+        self
+    }
+
+    pub fn run_context(mut self, context: RunContext) -> Self {
+        self.options.context = context;
+        self
+    }
+
+    pub fn approval_handler(mut self, handler: impl ApprovalHandler + 'static) -> Self {
+        self.options.approval_handler = Some(Arc::new(handler));
+        self
+    }
+
+    pub fn options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+}
+
+impl<'a, P: ChatProvider> RunBuilder<'a, P> {
+    // Runs as a stream of events instead of awaiting the final response
+    pub fn into_stream(self) -> impl Stream<Item = SwarmEvent> + 'a {
+        self.swarm
+            .run_stream(self.agent, self.messages, self.options)
+    }
+}
+
+impl<'a, P: ChatProvider> std::future::IntoFuture for RunBuilder<'a, P> {
+    type Output = Result<Response>;
+    type IntoFuture = BoxFuture<'a, Result<Response>>;
+
+    fn into_future(self) -> Self::IntoFuture {
+        Box::pin(
+            self.swarm
+                .run_with_options(self.agent, self.messages, self.options),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LoopDetection;
+    use async_openai::types::FunctionCallStream;
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn chunk(
+        index: i32,
+        id: Option<&str>,
+        name: Option<&str>,
+        arguments: Option<&str>,
+    ) -> ChatCompletionMessageToolCallChunk {
+        ChatCompletionMessageToolCallChunk {
+            index,
+            id: id.map(String::from),
+            r#type: None,
+            function: Some(FunctionCallStream {
+                name: name.map(String::from),
+                arguments: arguments.map(String::from),
+            }),
+        }
+    }
+
+    fn merge(chunks: &[ChatCompletionMessageToolCallChunk]) -> Vec<ChatCompletionMessageToolCall> {
+        let mut tool_calls = Vec::new();
+        for chunk in chunks {
+            merge_tool_call_chunk(&mut tool_calls, chunk).unwrap();
+        }
+        finish_tool_calls(tool_calls)
+    }
+
+    #[test]
+    fn merges_interleaved_parallel_calls() {
+        let tool_calls = merge(&[
+            chunk(0, Some("call_a"), Some("weather"), None),
+            chunk(1, Some("call_b"), Some("time"), None),
+            chunk(0, None, None, Some("{\"city\":")),
+            chunk(1, None, None, Some("{\"zone\":\"UTC\"}")),
+            chunk(0, None, None, Some("\"Paris\"}")),
+        ]);
+        assert_eq!(tool_calls.len(), 2);
+        assert_eq!(tool_calls[0].id, "call_a");
+        assert_eq!(tool_calls[0].function.name, "weather");
+        assert_eq!(tool_calls[0].function.arguments, "{\"city\":\"Paris\"}");
+        assert_eq!(tool_calls[1].id, "call_b");
+        assert_eq!(tool_calls[1].function.name, "time");
+        assert_eq!(tool_calls[1].function.arguments, "{\"zone\":\"UTC\"}");
+    }
+
+    #[test]
+    fn joins_arguments_split_mid_token() {
+        let tool_calls = merge(&[
+            chunk(0, Some("call_a"), Some("weather"), Some("{\"ci")),
+            chunk(0, None, None, Some("ty\":\"Par")),
+            chunk(0, None, None, Some("is\"}")),
+        ]);
+        assert_eq!(tool_calls[0].function.arguments, "{\"city\":\"Paris\"}");
+    }
+
+    #[test]
+    fn accepts_id_and_name_sent_once() {
+        let tool_calls = merge(&[
+            chunk(0, Some("call_a"), Some("weather"), Some("")),
+            chunk(0, None, None, Some("{}")),
+        ]);
+        assert_eq!(tool_calls[0].id, "call_a");
+        assert_eq!(tool_calls[0].function.name, "weather");
+    }
+
+    #[test]
+    fn ignores_id_and_name_repeated_on_every_chunk() {
+        let tool_calls = merge(&[
+            chunk(0, Some("call_a"), Some("weather"), Some("{\"city\":")),
+            chunk(0, Some("call_a"), Some("weather"), Some("\"Paris\"}")),
+        ]);
+        assert_eq!(tool_calls[0].id, "call_a");
+        assert_eq!(tool_calls[0].function.name, "weather");
+        assert_eq!(tool_calls[0].function.arguments, "{\"city\":\"Paris\"}");
+    }
+
+    #[test]
+    fn replaces_id_and_name_with_repeated_or_full_copies() {
+        let tool_calls = merge(&[
+            chunk(0, Some("ab"), Some("a"), None),
+            chunk(0, Some("ab"), Some("a"), None),
+            chunk(0, None, None, Some("{}")),
+        ]);
+        assert_eq!(tool_calls[0].id, "ab");
+        assert_eq!(tool_calls[0].function.name, "a");
+
+        let tool_calls = merge(&[
+            chunk(0, Some("call"), Some("get"), None),
+            chunk(0, Some("call_a"), Some("get_weather"), Some("{}")),
+        ]);
+        assert_eq!(tool_calls[0].id, "call_a");
+        assert_eq!(tool_calls[0].function.name, "get_weather");
+    }
+
+    fn repaired(text: &str) -> Value {
+        serde_json::from_str(&repair_json(text)).unwrap()
+    }
+
+    #[test]
+    fn repair_strips_code_fences() {
+        assert_eq!(repaired("```json\n{\"a\": 1}\n```"), json!({"a": 1}));
+        assert_eq!(repaired("```\n{\"a\": 1}"), json!({"a": 1}));
+    }
+
+    #[test]
+    fn repair_drops_trailing_commas() {
+        assert_eq!(
+            repaired("{\"a\": [1, 2,], \"b\": 3,}"),
+            json!({"a": [1, 2], "b": 3})
+        );
+    }
+
+    #[test]
+    fn repair_closes_truncated_output() {
+        assert_eq!(
+            repaired("{\"a\": [1, {\"b\": \"te"),
+            json!({"a": [1, {"b": "te"}]})
+        );
+        assert_eq!(repaired("{\"a\":"), json!({"a": null}));
+        assert_eq!(repaired("{\"a\": \"x\\"), json!({"a": "x"}));
+    }
+
+    #[test]
+    fn repair_leaves_commas_in_strings() {
+        assert_eq!(repaired("{\"a\": \"x,]\",}"), json!({"a": "x,]"}));
+    }
+
+    #[test]
+    fn rejects_out_of_range_indexes() {
+        let mut tool_calls = Vec::new();
+        assert!(merge_tool_call_chunk(&mut tool_calls, &chunk(-1, None, None, None)).is_err());
+        assert!(
+            merge_tool_call_chunk(&mut tool_calls, &chunk(i32::MAX, None, None, None)).is_err()
+        );
+        assert!(tool_calls.is_empty());
+    }
+
+    // Fails every embedding request, as an embeddings outage would
+    struct FailingEmbedder;
+
+    #[async_trait::async_trait]
+    impl crate::embeddings::Embedder for FailingEmbedder {
+        async fn embed(&self, _texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+            Err(SwarmError::Provider("embeddings unavailable".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn memory_failures_keep_the_finished_run() {
+        let swarm = Swarm::with_provider(crate::provider::MockProvider::new().with_text("hi"))
+            .with_memory(Arc::new(SemanticMemory::new(FailingEmbedder)));
+        let messages = vec![crate::messages::user_message("hello")];
+        let response = swarm.runner(Agent::default(), messages).await.unwrap();
+        assert_eq!(response.final_text(), Some("hi".to_string()));
+        assert!(response
+            .memory_error
+            .unwrap()
+            .contains("embeddings unavailable"));
+    }
+
+    // A tool taking `{"text": ...}` and answering with `function`'s output
+    fn tool(
+        name: &str,
+        function: impl Fn(Value) -> ToolOutput + Send + Sync + 'static,
+    ) -> ToolDefinition {
+        ToolDefinition::new(
+            Tool::new(
+                name,
+                "",
+                json!({ "type": "object", "properties": { "text": { "type": "string" } } }),
+            ),
+            ToolFunction::Sync(Arc::new(function)),
+        )
+    }
+
+    fn echo() -> ToolDefinition {
+        tool("echo", |args| {
+            ToolOutput::Text(args["text"].as_str().unwrap_or("").to_string())
+        })
+    }
+
+    fn hello() -> Vec<ChatCompletionRequestMessage> {
+        vec![crate::messages::user_message("hello")]
+    }
+
+    // Text of the tool messages of a response, in order
+    fn tool_results(response: &Response) -> Vec<String> {
+        response
+            .messages
+            .iter()
+            .filter(|message| matches!(message, ChatCompletionRequestMessage::Tool(_)))
+            .map(crate::messages::message_text)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn streams_events_for_every_turn() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_call("echo", json!({ "text": "ping" }))
+            .with_text("done");
+        let swarm = Swarm::with_provider(provider);
+        let agent = Agent::default().with_tool(echo());
+        let events: Vec<SwarmEvent> = swarm.runner(agent, hello()).into_stream().collect().await;
+
+        assert!(matches!(
+            &events[0],
+            SwarmEvent::TurnStarted { turn: 0, .. }
+        ));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            SwarmEvent::ToolCallRequested { tool_call, .. } if tool_call.function.name == "echo"
+        )));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            SwarmEvent::ToolResult { content, .. } if content == "ping"
+        )));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            SwarmEvent::TokenDelta { content, .. } if content == "done"
+        )));
+        let Some(SwarmEvent::RunCompleted(response)) = events.last() else {
+            panic!(
+                "the stream did not end with the response: {:?}",
+                events.last()
+            );
+        };
+        assert_eq!(response.final_text(), Some("done".to_string()));
+    }
+
+    #[tokio::test]
+    async fn streams_tokens_to_the_callback() {
+        let swarm =
+            Swarm::with_provider(crate::provider::MockProvider::new().with_text("hi there"));
+        let tokens = Arc::new(std::sync::Mutex::new(String::new()));
+        let sink = tokens.clone();
+        let response = swarm
+            .runner(Agent::default(), hello())
+            .on_token(move |token| sink.lock().unwrap().push_str(token))
+            .await
+            .unwrap();
+        assert_eq!(response.final_text(), Some("hi there".to_string()));
+        assert_eq!(*tokens.lock().unwrap(), "hi there");
+    }
+
+    #[tokio::test]
+    async fn streaming_failures_end_the_stream() {
+        let swarm = Swarm::with_provider(crate::provider::MockProvider::new());
+        let events: Vec<SwarmEvent> = swarm
+            .runner(Agent::default(), hello())
+            .into_stream()
+            .collect()
+            .await;
+        assert!(matches!(events.last(), Some(SwarmEvent::RunFailed(_))));
+    }
+
+    // A tool that answers after `delay`
+    fn slow(name: &str, delay: Duration) -> ToolDefinition {
+        let function = move |_| {
+            async move {
+                tokio::time::sleep(delay).await;
+                ToolOutput::Text("late".to_string())
+            }
+            .boxed()
+        };
+        ToolDefinition::new(
+            Tool::new(name, "", json!({ "type": "object" })),
+            ToolFunction::Async(Arc::new(function)),
+        )
+    }
+
+    #[tokio::test]
+    async fn times_out_slow_tools() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_calls(vec![("slow", json!({})), ("quick", json!({}))])
+            .with_text("done");
+        let swarm = Swarm::with_provider(provider).with_config(
+            SwarmConfig::new()
+                .with_tool_timeout(Duration::from_millis(50))
+                .with_tool_timeout_for("quick", Duration::from_secs(5)),
+        );
+        let agent = Agent::default()
+            .with_tool(slow("slow", Duration::from_secs(5)))
+            .with_tool(slow("quick", Duration::from_millis(100)));
+        let response = swarm.runner(agent, hello()).await.unwrap();
+        assert_eq!(
+            tool_results(&response),
+            ["Error: tool slow timed out.", "late"]
+        );
+        assert_eq!(response.final_text(), Some("done".to_string()));
+    }
+
+    #[tokio::test]
+    async fn times_out_the_whole_turn() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_calls(vec![("a", json!({})), ("b", json!({}))])
+            .with_text("done");
+        let swarm = Swarm::with_provider(provider)
+            .with_config(SwarmConfig::new().with_turn_timeout(Duration::from_millis(50)));
+        let agent = Agent::default()
+            .with_tool(slow("a", Duration::from_secs(5)))
+            .with_tool(slow("b", Duration::from_secs(5)));
+        let started = Instant::now();
+        let response = swarm.runner(agent, hello()).await.unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(tool_results(&response)
+            .iter()
+            .all(|result| result.contains("timed out")));
+    }
+
+    #[tokio::test]
+    async fn reports_tool_panics_to_the_model() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_calls(vec![("boom", json!({})), ("echo", json!({ "text": "ok" }))])
+            .with_text("recovered");
+        let swarm = Swarm::with_provider(provider);
+        let agent = Agent::default()
+            .with_tool(tool("boom", |_| panic!("kaboom")))
+            .with_tool(echo());
+        let response = swarm.runner(agent, hello()).await.unwrap();
+        let results = tool_results(&response);
+        assert!(
+            results[0].contains("tool boom panicked: kaboom"),
+            "{}",
+            results[0]
+        );
+        assert_eq!(results[1], "ok");
+        assert_eq!(response.final_text(), Some("recovered".to_string()));
+    }
+
+    // Rewrites echo's text before the call, blocks `secret` and tags every output
+    struct Policy;
+
+    #[async_trait::async_trait]
+    impl crate::middleware::ToolMiddleware for Policy {
+        async fn before_call(&self, name: &str, args: Value) -> crate::middleware::BeforeCall {
+            match name {
+                "secret" => crate::middleware::BeforeCall::Respond(ToolOutput::Text(
+                    "blocked by policy".to_string(),
+                )),
+                _ => crate::middleware::BeforeCall::Proceed(json!({
+                    "text": format!("{}!", args["text"].as_str().unwrap_or("")),
+                })),
+            }
+        }
+
+        async fn after_call(&self, _name: &str, output: ToolOutput) -> ToolOutput {
+            match output {
+                ToolOutput::Text(text) => ToolOutput::Text(format!("[{}]", text)),
+                other => other,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn runs_tool_calls_through_middleware() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_calls(vec![
+                ("echo", json!({ "text": "hi" })),
+                ("secret", json!({})),
+            ])
+            .with_text("done");
+        let swarm = Swarm::with_provider(provider).with_tool_middleware(Policy);
+        let agent = Agent::default()
+            .with_tool(echo())
+            .with_tool(tool("secret", |_| {
+                panic!("middleware let the call through")
+            }));
+        let response = swarm.runner(agent, hello()).await.unwrap();
+        assert_eq!(tool_results(&response), ["[hi!]", "[blocked by policy]"]);
+    }
+
+    #[tokio::test]
+    async fn applies_approval_decisions_before_tools_run() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_calls(vec![
+                ("echo", json!({ "text": "kept" })),
+                ("echo", json!({ "text": "edited" })),
+                ("danger", json!({})),
+            ])
+            .with_text("done");
+        let swarm = Swarm::with_provider(provider);
+        let agent = Agent::default()
+            .with_tool(echo())
+            .with_tool(tool("danger", |_| panic!("a denied call ran")));
+        let review = |tool_call: &ChatCompletionMessageToolCall| {
+            let args: Value = serde_json::from_str(&tool_call.function.arguments).unwrap();
+            match (tool_call.function.name.as_str(), args["text"].as_str()) {
+                ("danger", _) => Approval::Deny("not today".to_string()),
+                (_, Some("edited")) => Approval::Edit(json!({ "text": "changed" })),
+                _ => Approval::Approve,
+            }
+        };
+        let response = swarm
+            .runner(agent, hello())
+            .approval_handler(review)
+            .await
+            .unwrap();
+        assert_eq!(
+            tool_results(&response),
+            ["kept", "changed", "Error: tool call denied: not today"]
+        );
+    }
+
+    fn named(name: &str) -> Agent {
+        Agent {
+            name: name.to_string(),
+            ..Agent::default()
+        }
+    }
+
+    // Names of the tools offered in each request the provider received
+    fn offered_tools(swarm: &Swarm<crate::provider::MockProvider>) -> Vec<Vec<String>> {
+        swarm
+            .provider()
+            .requests()
+            .iter()
+            .map(|request| request.tools.iter().map(|tool| tool.name.clone()).collect())
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn hands_the_conversation_over() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_call("transfer_to_sales_agent", json!({}))
+            .with_text("sales here");
+        let swarm = Swarm::with_provider(provider);
+        let sales = named("Sales Agent").with_tool(echo());
+        let triage = named("Triage").with_handoffs(&[sales]);
+        let events: Vec<SwarmEvent> = swarm.runner(triage, hello()).into_stream().collect().await;
+
+        assert!(events.iter().any(|event| matches!(
+            event,
+            SwarmEvent::AgentHandoff { from, to } if from == "Triage" && to == "Sales Agent"
+        )));
+        let Some(SwarmEvent::RunCompleted(response)) = events.last() else {
+            panic!("the run failed: {:?}", events.last());
+        };
+        assert_eq!(response.agent.as_ref().unwrap().name, "Sales Agent");
+        assert_eq!(response.final_text(), Some("sales here".to_string()));
+        assert_eq!(
+            offered_tools(&swarm),
+            [vec!["transfer_to_sales_agent"], vec!["echo"]]
+        );
+    }
+
+    #[tokio::test]
+    async fn hands_the_conversation_back_through_wired_agents() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_call("transfer_to_sales", json!({}))
+            .with_tool_call("transfer_to_triage", json!({}))
+            .with_text("triage again");
+        let swarm = Swarm::with_provider(provider);
+        let agents = crate::definitions::Agents::new(vec![
+            (named("Triage"), vec!["Sales".to_string()]),
+            (named("Sales"), vec!["Triage".to_string()]),
+        ])
+        .unwrap();
+        let response = swarm.runner(agents.entry().clone(), hello()).await.unwrap();
+
+        assert_eq!(response.agent.as_ref().unwrap().name, "Triage");
+        assert_eq!(response.final_text(), Some("triage again".to_string()));
+        assert_eq!(
+            offered_tools(&swarm),
+            [
+                vec!["transfer_to_sales"],
+                vec!["transfer_to_triage"],
+                vec!["transfer_to_sales"]
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn pii_redaction_masks_tool_arguments_only_when_asked() {
+        for (mask, expected) in [(false, "ada@example.com"), (true, "[EMAIL]")] {
+            let provider = crate::provider::MockProvider::new()
+                .with_tool_call("lookup", json!({ "text": "ada@example.com" }))
+                .with_text("done");
+            let redactor = PiiRedactor::new().with_tool_arguments(mask);
+            let swarm = Swarm::with_provider(provider).with_pii_redaction(redactor);
+            let received = Arc::new(std::sync::Mutex::new(Vec::new()));
+            let seen = received.clone();
+            let lookup = tool("lookup", move |args| {
+                seen.lock()
+                    .unwrap()
+                    .push(args["text"].as_str().unwrap().to_string());
+                ToolOutput::Text("found".to_string())
+            });
+            let response = swarm
+                .runner(Agent::default().with_tool(lookup), hello())
+                .await
+                .unwrap();
+
+            assert_eq!(*received.lock().unwrap(), [expected]);
+            let second = &swarm.provider().requests()[1];
+            assert!(!format!("{:?}", second.messages).contains("ada@example.com"));
+            assert_eq!(response.redactions[0].count, 1);
+        }
+    }
+
+    #[tokio::test]
+    async fn input_guardrails_stop_runs_before_the_model() {
+        let off_topic = |messages: &[ChatCompletionRequestMessage]| match messages
+            .iter()
+            .any(|m| message_text(m).contains("weather"))
+        {
+            true => GuardrailDecision::Tripwire("off topic".to_string()),
+            false => GuardrailDecision::Allow,
+        };
+        let swarm = Swarm::with_provider(crate::provider::MockProvider::new().with_text("hi"))
+            .with_input_guardrail(off_topic);
+
+        let messages = vec![crate::messages::user_message("what's the weather?")];
+        let error = swarm.runner(Agent::default(), messages).await.unwrap_err();
+        assert!(matches!(
+            error,
+            SwarmError::GuardrailTripped { ref reason, .. } if reason == "off topic"
+        ));
+        assert!(swarm.provider().requests().is_empty());
+
+        let response = swarm.runner(Agent::default(), hello()).await.unwrap();
+        assert_eq!(response.final_text(), Some("hi".to_string()));
+    }
+
+    #[tokio::test]
+    async fn output_guardrails_rewrite_and_annotate() {
+        let disclaimer = |text: &str| OutputDecision::Rewrite(format!("{} (not advice)", text));
+        let note = |_: &str| OutputDecision::Annotate("checked".to_string());
+        let swarm = Swarm::with_provider(crate::provider::MockProvider::new().with_text("buy low"))
+            .with_output_guardrail(disclaimer)
+            .with_output_guardrail(note);
+        let response = swarm.runner(Agent::default(), hello()).await.unwrap();
+        assert_eq!(
+            response.final_text(),
+            Some("buy low (not advice)".to_string())
+        );
+        let decisions: Vec<OutputDecision> = response
+            .guardrail_outcomes
+            .into_iter()
+            .map(|outcome| outcome.decision)
+            .collect();
+        assert_eq!(
+            decisions,
+            [
+                OutputDecision::Rewrite("buy low (not advice)".to_string()),
+                OutputDecision::Annotate("checked".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn output_guardrails_stop_at_a_block() {
+        let secrets = |text: &str| match text.contains("password") {
+            true => OutputDecision::Block("[withheld]".to_string()),
+            false => OutputDecision::Pass,
+        };
+        let checked = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = checked.clone();
+        let later = move |_: &str| {
+            counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            OutputDecision::Pass
+        };
+        let provider = crate::provider::MockProvider::new().with_text("the password is hunter2");
+        let swarm = Swarm::with_provider(provider)
+            .with_output_guardrail(secrets)
+            .with_output_guardrail(later);
+        let response = swarm.runner(Agent::default(), hello()).await.unwrap();
+        assert_eq!(response.final_text(), Some("[withheld]".to_string()));
+        assert_eq!(response.guardrail_outcomes.len(), 1);
+        assert_eq!(checked.load(std::sync::atomic::Ordering::SeqCst), 0);
+    }
+
+    // A provider whose agent calls echo on every turn, each reply costing 100 prompt tokens
+    fn endless_echo(turns: usize) -> crate::provider::MockProvider {
+        let usage = Usage {
+            prompt_tokens: 100,
+            completion_tokens: 0,
+            total_tokens: 100,
+        };
+        (0..turns).fold(crate::provider::MockProvider::new(), |provider, _| {
+            provider
+                .with_tool_call("echo", json!({ "text": "again" }))
+                .with_usage(usage)
+        })
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_token_budget() {
+        let swarm = Swarm::with_provider(endless_echo(5));
+        let agent = Agent::default().with_tool(echo());
+        let response = swarm
+            .runner(agent, hello())
+            .max_total_tokens(150)
+            .await
+            .unwrap();
+        assert_eq!(response.status, RunStatus::BudgetExceeded);
+        assert_eq!(response.usage.total_tokens, 200);
+        assert_eq!(swarm.provider().requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stops_at_the_cost_budget() {
+        // 100 prompt tokens cost $1
+        let price = crate::cost::ModelPrice::new(10_000.0, 0.0);
+        let pricing = PricingTable::empty().with_price("gpt-4", price);
+        let swarm = Swarm::with_provider(endless_echo(5)).with_pricing(pricing);
+        let agent = Agent::default().with_tool(echo());
+        let response = swarm
+            .runner(agent, hello())
+            .max_cost_usd(2.5)
+            .await
+            .unwrap();
+        assert_eq!(response.status, RunStatus::BudgetExceeded);
+        assert_eq!(swarm.provider().requests().len(), 3);
+        assert!((response.cost_usd - 3.0).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn max_turns_counts_model_calls() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_calls(vec![
+                ("echo", json!({ "text": "a" })),
+                ("echo", json!({ "text": "b" })),
+                ("echo", json!({ "text": "c" })),
+            ])
+            .with_tool_call("echo", json!({ "text": "d" }))
+            .with_text("done");
+        let swarm = Swarm::with_provider(provider);
+        let agent = Agent::default().with_tool(echo());
+        let response = swarm.runner(agent, hello()).max_turns(2).await.unwrap();
+        assert_eq!(response.status, RunStatus::TurnLimitReached);
+        assert_eq!(swarm.provider().requests().len(), 2);
+        assert_eq!(tool_results(&response), ["a", "b", "c", "d"]);
+    }
+
+    #[tokio::test]
+    async fn max_turns_allows_a_final_reply_on_the_last_turn() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_call("echo", json!({ "text": "a" }))
+            .with_text("done");
+        let swarm = Swarm::with_provider(provider);
+        let agent = Agent::default().with_tool(echo());
+        let response = swarm.runner(agent, hello()).max_turns(2).await.unwrap();
+        assert_eq!(response.status, RunStatus::Completed);
+        assert_eq!(response.final_text(), Some("done".to_string()));
+    }
+
+    #[tokio::test]
+    async fn typed_runs_fail_when_the_turn_limit_is_reached() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_call("echo", json!({ "text": "a" }))
+            .with_text("{}");
+        let swarm = Swarm::with_provider(provider);
+        let agent = Agent::default().with_tool(echo());
+        let options = RunOptions {
+            max_turns: Some(1),
+            ..Default::default()
+        };
+        let error = swarm
+            .run_typed::<Value>(agent, hello(), options)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, SwarmError::MaxTurnsExceeded(1)));
+        assert_eq!(swarm.provider().remaining(), 1);
+    }
+
+    #[tokio::test]
+    async fn direct_tool_calls_fail_on_unknown_tools_and_bad_arguments() {
+        let swarm = Swarm::with_provider(crate::provider::MockProvider::new());
+        let agent = Agent::default().with_tool(echo());
+        let (context_variables, context) = (ContextVariables::new(), RunContext::default());
+        let call = |name: &'static str, arguments: &'static str| {
+            swarm.call_tool(&agent, name, arguments, &context_variables, &context, false)
+        };
+        assert!(matches!(
+            call("missing", "{}").await,
+            Err(SwarmError::ToolNotFound(name)) if name == "missing"
+        ));
+        assert!(matches!(
+            call("echo", "\"text\"").await,
+            Err(SwarmError::ToolArguments { name, .. }) if name == "echo"
+        ));
+        assert_eq!(call("echo", r#"{"text": "hi"}"#).await.unwrap().value, "hi");
+    }
+
+    #[tokio::test]
+    async fn stop_when_ends_the_run_after_the_turn() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_call("echo", json!({ "text": "working" }))
+            .with_tool_call("echo", json!({ "text": "FINISHED" }))
+            .with_text("never sent");
+        let swarm = Swarm::with_provider(provider);
+        let agent = Agent::default().with_tool(echo());
+        let response = swarm
+            .runner(agent, hello())
+            .stop_when(|_, turn| {
+                turn.messages
+                    .iter()
+                    .any(|message| message_text(message) == "FINISHED")
+            })
+            .await
+            .unwrap();
+        assert_eq!(response.status, RunStatus::Stopped);
+        assert_eq!(tool_results(&response), ["working", "FINISHED"]);
+        assert_eq!(swarm.provider().remaining(), 1);
+    }
+
+    fn looping_swarm(action: LoopAction) -> Swarm<crate::provider::MockProvider> {
+        let provider = (0..3).fold(crate::provider::MockProvider::new(), |provider, _| {
+            provider.with_tool_call("echo", json!({ "text": "same" }))
+        });
+        Swarm::with_provider(provider.with_text("moved on"))
+            .with_config(SwarmConfig::new().with_loop_detection(LoopDetection::new(3, action)))
+    }
+
+    #[tokio::test]
+    async fn loop_detection_stops_before_the_repeated_call() {
+        let swarm = looping_swarm(LoopAction::Stop);
+        let agent = Agent::default().with_tool(echo());
+        let response = swarm.runner(agent, hello()).await.unwrap();
+        assert_eq!(response.status, RunStatus::ToolLoopDetected);
+        assert_eq!(tool_results(&response), ["same", "same"]);
+    }
+
+    #[tokio::test]
+    async fn loop_detection_fails_the_run() {
+        let swarm = looping_swarm(LoopAction::Error);
+        let agent = Agent::default().with_tool(echo());
+        let error = swarm.runner(agent, hello()).await.unwrap_err();
+        assert!(matches!(error, SwarmError::ToolLoop { ref name, repeats: 3 } if name == "echo"));
+    }
+
+    #[tokio::test]
+    async fn loop_detection_nudges_the_model() {
+        let swarm = looping_swarm(LoopAction::Nudge("try something else".to_string()));
+        let agent = Agent::default().with_tool(echo());
+        let response = swarm.runner(agent, hello()).await.unwrap();
+        assert_eq!(response.status, RunStatus::Completed);
+        assert_eq!(response.final_text(), Some("moved on".to_string()));
+        let last_request = swarm.provider().requests().pop().unwrap();
+        assert_eq!(
+            last_request.messages.last().map(message_text).as_deref(),
+            Some("try something else")
+        );
+    }
+
+    #[tokio::test]
+    async fn loop_detection_ignores_changing_arguments() {
+        let provider = crate::provider::MockProvider::new()
+            .with_tool_call("echo", json!({ "text": "a" }))
+            .with_tool_call("echo", json!({ "text": "b" }))
+            .with_tool_call("echo", json!({ "text": "a" }))
+            .with_text("done");
+        let swarm = Swarm::with_provider(provider).with_config(
+            SwarmConfig::new().with_loop_detection(LoopDetection::new(2, LoopAction::Error)),
+        );
+        let agent = Agent::default().with_tool(echo());
+        let response = swarm.runner(agent, hello()).await.unwrap();
+        assert_eq!(response.final_text(), Some("done".to_string()));
     }
 }
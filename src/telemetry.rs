@@ -0,0 +1,55 @@
+// Helpers for the optional `tracing` instrumentation; without the feature they compile to no-ops
+use std::future::Future;
+use std::time::Instant;
+
+use crate::types::Usage;
+
+#[cfg(feature = "tracing")]
+pub(crate) type Span = tracing::Span;
+#[cfg(not(feature = "tracing"))]
+pub(crate) struct Span;
+
+// Span covering one turn: the completion and the tool calls it requested
+pub(crate) fn turn_span(turn: usize, agent: &str, model: &str) -> Span {
+    #[cfg(feature = "tracing")]
+    return tracing::info_span!("swarm.turn", turn, agent, model);
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = (turn, agent, model);
+        Span
+    }
+}
+
+// Runs a future inside a span
+pub(crate) fn in_span<F: Future>(future: F, span: &Span) -> impl Future<Output = F::Output> {
+    #[cfg(feature = "tracing")]
+    return tracing::Instrument::instrument(future, span.clone());
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = span;
+        future
+    }
+}
+
+// Records token counts on the current span
+pub(crate) fn record_usage(usage: &Usage) {
+    #[cfg(feature = "tracing")]
+    {
+        let span = tracing::Span::current();
+        span.record("prompt_tokens", usage.prompt_tokens);
+        span.record("completion_tokens", usage.completion_tokens);
+        span.record("total_tokens", usage.total_tokens);
+        span.record("gen_ai.usage.input_tokens", usage.prompt_tokens);
+        span.record("gen_ai.usage.output_tokens", usage.completion_tokens);
+    }
+    #[cfg(not(feature = "tracing"))]
+    let _ = usage;
+}
+
+// Records the time since `started` on the current span
+pub(crate) fn record_latency(started: Instant) {
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("latency_ms", started.elapsed().as_millis() as u64);
+    #[cfg(not(feature = "tracing"))]
+    let _ = started;
+}
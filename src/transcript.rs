@@ -0,0 +1,151 @@
+use async_openai::types::ChatCompletionRequestMessage;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::messages::{message_images, message_text};
+use crate::types::Response;
+
+// How transcripts are rendered by `Response::to_markdown_with` and `render_markdown`
+#[derive(Debug, Clone, Default)]
+pub struct TranscriptOptions {
+    // Tool results longer than this many characters are cut, noting what was left out
+    pub max_tool_output: Option<usize>,
+    // Includes system messages, which usually repeat the instructions
+    pub include_system: bool,
+}
+
+impl TranscriptOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_tool_output(mut self, chars: usize) -> Self {
+        self.max_tool_output = Some(chars);
+        self
+    }
+
+    pub fn with_system(mut self, include_system: bool) -> Self {
+        self.include_system = include_system;
+        self
+    }
+}
+
+// Renders messages as markdown: a heading per message naming the role and, where known,
+// the agent or tool, followed by the text, requested tool calls and tool results
+pub fn render_markdown(
+    messages: &[ChatCompletionRequestMessage],
+    options: &TranscriptOptions,
+) -> String {
+    // Tool results only carry the call id, so look up which tool each call was for
+    let tool_names: HashMap<&str, &str> = messages
+        .iter()
+        .filter_map(|message| match message {
+            ChatCompletionRequestMessage::Assistant(msg) => msg.tool_calls.as_ref(),
+            _ => None,
+        })
+        .flatten()
+        .map(|call| (call.id.as_str(), call.function.name.as_str()))
+        .collect();
+
+    let mut out = String::new();
+    for message in messages {
+        let heading = match message {
+            ChatCompletionRequestMessage::System(_) if !options.include_system => continue,
+            ChatCompletionRequestMessage::System(msg) => labeled("System", msg.name.as_deref()),
+            ChatCompletionRequestMessage::User(msg) => labeled("User", msg.name.as_deref()),
+            ChatCompletionRequestMessage::Assistant(msg) => {
+                labeled("Assistant", msg.name.as_deref())
+            }
+            ChatCompletionRequestMessage::Tool(msg) => {
+                labeled("Tool", tool_names.get(msg.tool_call_id.as_str()).copied())
+            }
+            #[allow(deprecated)]
+            ChatCompletionRequestMessage::Function(msg) => labeled("Function", Some(&msg.name)),
+        };
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let mut blocks = vec![format!("### {}", heading)];
+
+        let text = message_text(message);
+        match message {
+            ChatCompletionRequestMessage::Tool(_) | ChatCompletionRequestMessage::Function(_) => {
+                let text = match options.max_tool_output {
+                    Some(max) => truncate(&text, max),
+                    None => text,
+                };
+                blocks.push(format!("```\n{}\n```", text));
+            }
+            _ if !text.is_empty() => blocks.push(text),
+            _ => {}
+        }
+        let images = message_images(message).len();
+        if images > 0 {
+            blocks.push(format!("_[{} image(s)]_", images));
+        }
+        if let ChatCompletionRequestMessage::Assistant(msg) = message {
+            let calls: Vec<String> = msg
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|call| {
+                    format!(
+                        "- calls `{}({})` (`{}`)",
+                        call.function.name, call.function.arguments, call.id
+                    )
+                })
+                .collect();
+            if !calls.is_empty() {
+                blocks.push(calls.join("\n"));
+            }
+        }
+        let _ = writeln!(out, "{}", blocks.join("\n\n"));
+    }
+    out
+}
+
+fn labeled(role: &str, name: Option<&str>) -> String {
+    match name {
+        Some(name) if !name.is_empty() => format!("{} · {}", role, name),
+        _ => role.to_string(),
+    }
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    let total = text.chars().count();
+    if total <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max).collect();
+    format!("{}… ({} more characters)", kept, total - max)
+}
+
+impl Response {
+    // Transcript of the run's new messages, ending with how the run finished
+    pub fn to_markdown(&self) -> String {
+        self.to_markdown_with(&TranscriptOptions::default())
+    }
+
+    pub fn to_markdown_with(&self, options: &TranscriptOptions) -> String {
+        let mut out = render_markdown(&self.messages, options);
+        let mut summary = format!("{:?}", self.status);
+        if let Some(agent) = self.final_agent_name() {
+            let _ = write!(summary, " · {}", agent);
+        }
+        let _ = write!(summary, " · {} tokens", self.usage.total_tokens);
+        if self.cost_usd > 0.0 {
+            let _ = write!(summary, " · ${:.4}", self.cost_usd);
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+        let _ = writeln!(out, "---\n_{}_", summary);
+        out
+    }
+}
+
+impl std::fmt::Display for Response {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_markdown())
+    }
+}
@@ -1,13 +1,31 @@
-use serde::{Deserialize, Serialize};
+use futures::{future::BoxFuture, FutureExt};
+use schemars::JsonSchema;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+
+use crate::run_context::RunContext;
+
+// Parameter through which tools receive the run's context variables
+pub const CONTEXT_VARIABLES: &str = "context_variables";
+
+// Joins a namespace and a tool name, e.g. `github__create_issue`. Providers only accept
+// letters, digits, `_` and `-` in tool names, so `github.create_issue` cannot be sent as is
+pub const NAMESPACE_SEPARATOR: &str = "__";
 
 #[derive(Serialize, Deserialize)]
 pub struct Tool {
     pub(crate) name: String,
     pub(crate) description: String,
     pub(crate) parameters: Value,
+    // Asks the provider to match arguments exactly against the schema
+    #[serde(default)]
+    pub(crate) strict: bool,
+    // Set by `with_namespace`; names that merely contain the separator have none
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) namespace: Option<String>,
 }
 
 impl Tool {
@@ -16,8 +34,89 @@ impl Tool {
             name: name.to_string(),
             description: description.to_string(),
             parameters,
+            strict: false,
+            namespace: None,
+        }
+    }
+
+    // Opts into strict function calling, closing every object in the schema
+    pub fn with_strict(mut self) -> Self {
+        self.parameters = crate::schema::strict_schema(self.parameters);
+        self.strict = true;
+        self
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // Prefixes the name with a namespace, e.g. `create_issue` becomes `github__create_issue`
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.name = format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, self.name);
+        self.namespace = Some(match self.namespace.take() {
+            Some(inner) => format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, inner),
+            None => namespace.to_string(),
+        });
+        self
+    }
+
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    // Name without its namespace
+    pub fn bare_name(&self) -> &str {
+        match &self.namespace {
+            Some(namespace) => &self.name[namespace.len() + NAMESPACE_SEPARATOR.len()..],
+            None => &self.name,
         }
     }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+
+    // JSON schema of the arguments
+    pub fn parameters(&self) -> &Value {
+        &self.parameters
+    }
+
+    // Whether the schema declares the parameter that receives the run's context variables
+    pub fn declares_context_variables(&self) -> bool {
+        self.parameters
+            .get("properties")
+            .and_then(Value::as_object)
+            .is_some_and(|properties| properties.contains_key(CONTEXT_VARIABLES))
+    }
+
+    // Copy of the tool as the model sees it; context variables are filled in by the swarm
+    pub fn without_context_variables(&self) -> Tool {
+        let mut tool = self.clone();
+        if let Some(properties) = tool
+            .parameters
+            .get_mut("properties")
+            .and_then(Value::as_object_mut)
+        {
+            properties.remove(CONTEXT_VARIABLES);
+        }
+        if let Some(required) = tool
+            .parameters
+            .get_mut("required")
+            .and_then(Value::as_array_mut)
+        {
+            required.retain(|name| name != CONTEXT_VARIABLES);
+        }
+        tool
+    }
+
+    // Creates a tool whose parameters schema is generated from `Args`
+    pub fn typed<Args: JsonSchema>(name: &str, description: &str) -> Self {
+        Tool::new(name, description, crate::schema::schema_for::<Args>())
+    }
 }
 
 impl Clone for Tool {
@@ -26,6 +125,8 @@ impl Clone for Tool {
             name: self.name.clone(),
             description: self.description.clone(),
             parameters: self.parameters.clone(),
+            strict: self.strict,
+            namespace: self.namespace.clone(),
         }
     }
 }
@@ -36,6 +137,8 @@ impl Default for Tool {
             name: String::new(),
             description: String::new(),
             parameters: Value::Null,
+            strict: false,
+            namespace: None,
         }
     }
 }
@@ -46,25 +149,209 @@ impl std::fmt::Debug for Tool {
             .field("name", &self.name)
             .field("description", &self.description)
             .field("parameters", &self.parameters)
+            .field("strict", &self.strict)
+            .field("namespace", &self.namespace)
             .finish()
     }
 }
 
+// Run state shared with tools and instructions; plain strings from older sessions still load
+pub type ContextVariables = serde_json::Map<String, Value>;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ToolResult {
     pub value: String,
     pub agent: Option<Agent>,
-    pub context_variables: HashMap<String, String>,
+    pub context_variables: ContextVariables,
+    // Spent by runs the tool started; added to the calling run's totals
+    #[serde(default)]
+    pub usage: Usage,
+    #[serde(default)]
+    pub cost_usd: f64,
+}
+
+// What a tool hands back to the run loop
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum ToolOutput {
+    Text(String),
+    Json(Value),
+    Handoff(Agent),
+    ContextUpdate(ContextVariables),
+    Error(String),
+    // A run the tool started, e.g. an agent used as a tool. Its final text answers the
+    // model, and its usage and cost count toward the calling run
+    SubRun(Response),
+}
+
+impl ToolOutput {
+    // Serializes a typed return value, keeping strings as plain text
+    pub fn from_serializable<T: Serialize>(output: T) -> Self {
+        match serde_json::to_value(output) {
+            Ok(Value::String(text)) => ToolOutput::Text(text),
+            Ok(value) => ToolOutput::Json(value),
+            Err(e) => ToolOutput::Error(format!("failed to serialize tool output: {}", e)),
+        }
+    }
+}
+
+impl From<String> for ToolOutput {
+    fn from(text: String) -> Self {
+        ToolOutput::Text(text)
+    }
+}
+
+impl From<&str> for ToolOutput {
+    fn from(text: &str) -> Self {
+        ToolOutput::Text(text.to_string())
+    }
+}
+
+impl From<Agent> for ToolOutput {
+    fn from(agent: Agent) -> Self {
+        ToolOutput::Handoff(agent)
+    }
+}
+
+// Compatibility shim for tools returning raw JSON
+impl From<Value> for ToolOutput {
+    fn from(raw_result: Value) -> Self {
+        match raw_result {
+            // 1. Object with 'value' key is a serialized ToolResult
+            Value::Object(obj) if obj.contains_key("value") => {
+                let text = obj["value"].as_str().unwrap_or("").to_string();
+                match serde_json::from_value::<ToolResult>(Value::Object(obj)) {
+                    Ok(ToolResult {
+                        agent: Some(agent), ..
+                    }) => ToolOutput::Handoff(agent),
+                    Ok(ToolResult {
+                        context_variables, ..
+                    }) if !context_variables.is_empty() => {
+                        ToolOutput::ContextUpdate(context_variables)
+                    }
+                    Ok(result) => ToolOutput::Text(result.value),
+                    Err(_) => ToolOutput::Text(text),
+                }
+            }
+            // 2. Object with 'assistant' key is a serialized Agent
+            Value::Object(obj) if obj.contains_key("assistant") => {
+                let value = Value::Object(obj);
+                match serde_json::from_value(value.clone()) {
+                    Ok(agent) => ToolOutput::Handoff(agent),
+                    Err(_) => ToolOutput::Text(value.to_string()),
+                }
+            }
+            // 3. Strings are text, anything else is JSON
+            Value::String(text) => ToolOutput::Text(text),
+            value => ToolOutput::Json(value),
+        }
+    }
+}
+
+impl From<ToolOutput> for ToolResult {
+    fn from(output: ToolOutput) -> Self {
+        let mut usage = Usage::default();
+        let mut cost_usd = 0.0;
+        let (value, agent, context_variables) = match output {
+            ToolOutput::Text(text) => (text, None, ContextVariables::new()),
+            ToolOutput::Json(value) => (value.to_string(), None, ContextVariables::new()),
+            ToolOutput::Handoff(agent) => (
+                serde_json::json!({ "assistant": agent.name }).to_string(),
+                Some(agent),
+                ContextVariables::new(),
+            ),
+            ToolOutput::ContextUpdate(context_variables) => {
+                let mut keys: Vec<&str> = context_variables.keys().map(String::as_str).collect();
+                keys.sort_unstable();
+                (
+                    format!("Updated context variables: {}", keys.join(", ")),
+                    None,
+                    context_variables,
+                )
+            }
+            ToolOutput::Error(message) => {
+                (format!("Error: {}", message), None, ContextVariables::new())
+            }
+            ToolOutput::SubRun(response) => {
+                usage = response.usage;
+                cost_usd = response.cost_usd;
+                (
+                    response.final_text().unwrap_or_default(),
+                    None,
+                    ContextVariables::new(),
+                )
+            }
+        };
+        ToolResult {
+            value,
+            agent,
+            context_variables,
+            usage,
+            cost_usd,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Agent {
     pub name: String,
     pub model: String,
-    pub instructions: String,
+    pub instructions: Instructions,
     pub tools: Vec<Tool>,
     pub tool_choice: Option<String>,
     pub parallel_tool_calls: bool,
+    #[serde(default)]
+    pub model_settings: ModelSettings,
+    // Tools only this agent can run, checked before the swarm's registry; not serialized
+    #[serde(skip)]
+    pub registry: Option<Arc<ToolRegistry>>,
+}
+
+impl Agent {
+    // Attaches a tool to this agent, both its schema and its implementation
+    pub fn with_tool(mut self, definition: ToolDefinition) -> Self {
+        self.tools.push(definition.tool());
+        Arc::make_mut(self.registry.get_or_insert_with(Default::default)).register(definition);
+        self
+    }
+
+    // Checks that every tool has a function after deserialization, which keeps only their
+    // schemas. Tools missing from the agent's own registry are looked up in `registry`
+    // when called rather than copied, so deregistering them there still revokes them;
+    // fails on the first tool found in neither.
+    pub fn resolve_tools(self, registry: &ToolRegistry) -> crate::error::Result<Agent> {
+        let unbound = self.tools.iter().find(|tool| {
+            !self
+                .registry
+                .as_ref()
+                .is_some_and(|own| own.contains(&tool.name))
+                && !registry.contains(&tool.name)
+        });
+        if let Some(tool) = unbound {
+            return Err(crate::error::SwarmError::UnboundTool {
+                agent: self.name.clone(),
+                tool: tool.name.clone(),
+            });
+        }
+        Ok(self)
+    }
+
+    // Adds a `transfer_to_<name>` tool per target that hands the conversation over to it.
+    // Targets are captured by value, so attach their own handoffs first. This cannot express
+    // a cycle: the copy of A inside B has no way back to B. Use `Agents::new` for agents
+    // that hand the conversation back and forth
+    pub fn with_handoffs(self, targets: &[Agent]) -> Self {
+        targets.iter().fold(self, |agent, target| {
+            let description = format!("Transfer the conversation to {}.", target.name);
+            agent.with_tool(handoff_tool(target, &description))
+        })
+    }
+
+    // Attaches a registry whose tools take precedence over the swarm's
+    pub fn with_registry(mut self, registry: ToolRegistry) -> Self {
+        self.registry = Some(Arc::new(registry));
+        self
+    }
 }
 
 impl Default for Agent {
@@ -72,24 +359,745 @@ impl Default for Agent {
         Agent {
             name: "Agent".to_string(),
             model: "gpt-4".to_string(),
-            instructions: "You are a helpful agent.".to_string(),
+            instructions: "You are a helpful agent.".into(),
             tools: Vec::new(),
             tool_choice: None,
             parallel_tool_calls: true,
+            model_settings: ModelSettings::default(),
+            registry: None,
+        }
+    }
+}
+
+// Closure computing an agent's instructions from the run's context variables
+pub type InstructionsFn = Arc<dyn Fn(&ContextVariables) -> String + Send + Sync>;
+
+// Closure computing an agent's instructions from the run's typed context
+pub type ContextualInstructionsFn =
+    Arc<dyn Fn(&RunContext, &ContextVariables) -> crate::error::Result<String> + Send + Sync>;
+
+// An agent's system prompt, fixed, recomputed or rendered every turn
+#[derive(Clone)]
+pub enum Instructions {
+    Static(String),
+    Dynamic(InstructionsFn),
+    Template(InstructionsTemplate),
+    Contextual(ContextualInstructionsFn),
+}
+
+impl Instructions {
+    pub fn dynamic(function: impl Fn(&ContextVariables) -> String + Send + Sync + 'static) -> Self {
+        Instructions::Dynamic(Arc::new(function))
+    }
+
+    // Instructions computed from the run's typed context; the run fails if it is missing
+    pub fn with_context<Ctx: Send + 'static>(
+        function: impl Fn(&Ctx, &ContextVariables) -> String + Send + Sync + 'static,
+    ) -> Self {
+        Instructions::Contextual(Arc::new(move |context, context_variables| {
+            context
+                .with(|ctx: &mut Ctx| function(ctx, context_variables))
+                .ok_or(crate::error::SwarmError::MissingContext(
+                    std::any::type_name::<Ctx>(),
+                ))
+        }))
+    }
+
+    pub fn template(source: impl Into<String>) -> Self {
+        Instructions::Template(InstructionsTemplate::new(source))
+    }
+
+    // Produces the prompt for the current context variables and typed context
+    pub fn render(
+        &self,
+        context_variables: &ContextVariables,
+        context: &RunContext,
+    ) -> crate::error::Result<String> {
+        match self {
+            Instructions::Static(text) => Ok(text.clone()),
+            Instructions::Dynamic(function) => Ok(function(context_variables)),
+            Instructions::Template(template) => template.render(context_variables),
+            Instructions::Contextual(function) => function(context, context_variables),
+        }
+    }
+}
+
+impl Default for Instructions {
+    fn default() -> Self {
+        Instructions::Static(String::new())
+    }
+}
+
+impl From<String> for Instructions {
+    fn from(text: String) -> Self {
+        Instructions::Static(text)
+    }
+}
+
+impl From<&str> for Instructions {
+    fn from(text: &str) -> Self {
+        Instructions::Static(text.to_string())
+    }
+}
+
+impl From<InstructionsTemplate> for Instructions {
+    fn from(template: InstructionsTemplate) -> Self {
+        Instructions::Template(template)
+    }
+}
+
+impl std::fmt::Debug for Instructions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Instructions::Static(text) => f.debug_tuple("Static").field(text).finish(),
+            Instructions::Dynamic(_) => f.write_str("Dynamic"),
+            Instructions::Contextual(_) => f.write_str("Contextual"),
+            Instructions::Template(template) => {
+                f.debug_tuple("Template").field(&template.source).finish()
+            }
+        }
+    }
+}
+
+// Serialized form of instructions; closures and template environments are not serialized
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum InstructionsRepr {
+    Text(String),
+    Template { template: String },
+}
+
+impl Serialize for Instructions {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let repr = match self {
+            Instructions::Static(text) => Some(InstructionsRepr::Text(text.clone())),
+            Instructions::Dynamic(_) | Instructions::Contextual(_) => None,
+            Instructions::Template(template) => Some(InstructionsRepr::Template {
+                template: template.source.clone(),
+            }),
+        };
+        repr.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Instructions {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(
+            match Option::<InstructionsRepr>::deserialize(deserializer)? {
+                Some(InstructionsRepr::Text(text)) => Instructions::Static(text),
+                Some(InstructionsRepr::Template { template }) => Instructions::template(template),
+                None => Instructions::default(),
+            },
+        )
+    }
+}
+
+// Minijinja template rendered with the context variables, e.g. "Hello {{ user_name }}"
+#[derive(Clone)]
+pub struct InstructionsTemplate {
+    source: String,
+    environment: Option<Arc<minijinja::Environment<'static>>>,
+}
+
+impl InstructionsTemplate {
+    pub fn new(source: impl Into<String>) -> Self {
+        InstructionsTemplate {
+            source: source.into(),
+            environment: None,
+        }
+    }
+
+    // Renders with a custom environment, e.g. one with globals or filters
+    pub fn with_environment(mut self, environment: Arc<minijinja::Environment<'static>>) -> Self {
+        self.environment = Some(environment);
+        self
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    // Renders the template; undefined variables are errors unless the environment says otherwise
+    pub fn render(&self, context_variables: &ContextVariables) -> crate::error::Result<String> {
+        match &self.environment {
+            Some(environment) => Ok(environment.render_str(&self.source, context_variables)?),
+            None => {
+                let mut environment = minijinja::Environment::new();
+                environment.set_undefined_behavior(minijinja::UndefinedBehavior::Strict);
+                Ok(environment.render_str(&self.source, context_variables)?)
+            }
         }
     }
 }
 
+// Tool named `transfer_to_<name>` that hands the conversation over to `target`
+pub(crate) fn handoff_tool(target: &Agent, description: &str) -> ToolDefinition {
+    let name = target.name.clone();
+    let target = target.clone();
+    handoff_tool_with(&name, description, move || {
+        ToolOutput::Handoff(target.clone())
+    })
+}
+
+// Handoff tool for the agent named `target`, with `handoff` producing the output
+pub(crate) fn handoff_tool_with(
+    target: &str,
+    description: &str,
+    handoff: impl Fn() -> ToolOutput + Send + Sync + 'static,
+) -> ToolDefinition {
+    let name = format!("transfer_to_{}", handoff_name(target));
+    let parameters = serde_json::json!({ "type": "object", "properties": {} });
+    ToolDefinition::new(
+        Tool::new(&name, description, parameters),
+        ToolFunction::Sync(Arc::new(move |_: Value| handoff())),
+    )
+}
+
+// Turns an agent name like "Sales Agent" into "sales_agent"
+pub(crate) fn handoff_name(name: &str) -> String {
+    name.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|part| !part.is_empty())
+        .map(str::to_ascii_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelSettings {
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub stop: Option<Vec<String>>,
+    pub presence_penalty: Option<f32>,
+    pub frequency_penalty: Option<f32>,
+    // Requests best-effort deterministic sampling; compare `Response::system_fingerprint`
+    // across runs to detect backend changes
+    pub seed: Option<i64>,
+    // Constrains replies to JSON, optionally matching a schema
+    pub response_format: Option<async_openai::types::ResponseFormat>,
+}
+
+impl Default for ModelSettings {
+    fn default() -> Self {
+        ModelSettings {
+            max_tokens: Some(512),
+            temperature: None,
+            top_p: None,
+            stop: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            seed: None,
+            response_format: None,
+        }
+    }
+}
+
+impl ModelSettings {
+    pub fn with_response_format(mut self, format: async_openai::types::ResponseFormat) -> Self {
+        self.response_format = Some(format);
+        self
+    }
+
+    // Requests a syntactically valid JSON object in every reply
+    pub fn with_json_mode(self) -> Self {
+        self.with_response_format(async_openai::types::ResponseFormat::JsonObject)
+    }
+
+    // Requests replies matching the JSON schema of `T`
+    pub fn with_json_schema<T: JsonSchema>(self, name: &str) -> Self {
+        self.with_response_format(crate::schema::response_format_for::<T>(name))
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Response {
     pub messages: Vec<async_openai::types::ChatCompletionRequestMessage>,
     pub agent: Option<Agent>,
-    pub context_variables: HashMap<String, String>,
+    pub context_variables: ContextVariables,
+    #[serde(default)]
+    pub usage: Usage,
+    #[serde(default)]
+    pub cost_usd: f64,
+    // Backend configuration reported with the last completion
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+    // Why the last completion ended; `Length` means the final message was cut off
+    #[serde(default)]
+    pub finish_reason: Option<async_openai::types::FinishReason>,
+    // Why the run stopped
+    #[serde(default)]
+    pub status: RunStatus,
+    // Output guardrails that blocked, rewrote or annotated the final message
+    #[serde(default)]
+    pub guardrail_outcomes: Vec<crate::guardrails::GuardrailOutcome>,
+    // Personal data masked before it reached the model
+    #[serde(default)]
+    pub redactions: Vec<crate::pii::Redaction>,
+    // Audio of the final reply when the run asked for it; not serialized
+    #[serde(skip)]
+    pub speech: Option<crate::audio::Speech>,
+    // Why the final reply could not be read aloud; the run itself still succeeds
+    #[serde(default)]
+    pub speech_error: Option<String>,
+    // Why semantic memory could not store the exchange; the run itself still succeeds
+    #[serde(default)]
+    pub memory_error: Option<String>,
+}
+
+impl Response {
+    // A completed response with no messages, usage or cost, to absorb runs into
+    pub fn empty() -> Self {
+        Response {
+            messages: Vec::new(),
+            agent: None,
+            context_variables: ContextVariables::new(),
+            usage: Usage::default(),
+            cost_usd: 0.0,
+            system_fingerprint: None,
+            finish_reason: None,
+            status: RunStatus::Completed,
+            guardrail_outcomes: Vec::new(),
+            redactions: Vec::new(),
+            speech: None,
+            speech_error: None,
+            memory_error: None,
+        }
+    }
+
+    // Adds the usage, cost, guardrail outcomes and redactions of another run, e.g. one
+    // step of an orchestration, and takes over how it ended. Messages, agent and
+    // context variables are left to the caller
+    pub fn absorb(&mut self, other: &Response) {
+        self.usage.add(&other.usage);
+        self.cost_usd += other.cost_usd;
+        self.system_fingerprint = other
+            .system_fingerprint
+            .clone()
+            .or(self.system_fingerprint.take());
+        self.finish_reason = other.finish_reason;
+        self.status = other.status;
+        self.guardrail_outcomes
+            .extend(other.guardrail_outcomes.iter().cloned());
+        crate::pii::merge_redactions(&mut self.redactions, &other.redactions);
+    }
+
+    // Text of the last assistant message, None when the run produced no text
+    pub fn final_text(&self) -> Option<String> {
+        self.messages
+            .iter()
+            .rev()
+            .find(|message| {
+                matches!(
+                    message,
+                    async_openai::types::ChatCompletionRequestMessage::Assistant(_)
+                )
+            })
+            .map(crate::messages::message_text)
+            .filter(|text| !text.is_empty())
+    }
+
+    // Agent that was active when the run ended
+    pub fn final_agent_name(&self) -> Option<&str> {
+        self.agent.as_ref().map(|agent| agent.name.as_str())
+    }
+
+    // Tool calls the agents requested during the run, in order
+    pub fn tool_calls(
+        &self,
+    ) -> impl Iterator<Item = &async_openai::types::ChatCompletionMessageToolCall> {
+        self.iter_assistant_messages()
+            .flat_map(|message| message.tool_calls.iter().flatten())
+    }
+
+    pub fn iter_assistant_messages(
+        &self,
+    ) -> impl Iterator<Item = &async_openai::types::ChatCompletionRequestAssistantMessage> {
+        self.messages.iter().filter_map(|message| match message {
+            async_openai::types::ChatCompletionRequestMessage::Assistant(assistant) => {
+                Some(assistant)
+            }
+            _ => None,
+        })
+    }
+}
+
+// How a run ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RunStatus {
+    // The agent answered without requesting further tool calls
+    #[default]
+    Completed,
+    // `max_cost_usd` or `max_total_tokens` was reached; the transcript is partial
+    BudgetExceeded,
+    // `max_turns` model calls were made while the agent still had work to do
+    TurnLimitReached,
+    // The `stop_when` predicate ended the run
+    Stopped,
+    // Loop detection ended the run before a repeated tool call
+    ToolLoopDetected,
+}
+
+// Outcome of `Swarm::run_typed`: the parsed final reply next to the full transcript
+#[derive(Debug, Clone)]
+pub struct TypedResponse<T> {
+    pub value: T,
+    pub response: Response,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Usage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+impl Usage {
+    pub fn add(&mut self, other: &Usage) {
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.total_tokens += other.total_tokens;
+    }
+}
+
+impl From<async_openai::types::CompletionUsage> for Usage {
+    fn from(usage: async_openai::types::CompletionUsage) -> Self {
+        Usage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }
+    }
+}
+
+// Tool function that also receives the run's typed context
+pub type ContextualToolFn = Arc<dyn Fn(&RunContext, Value) -> ToolOutput + Send + Sync>;
+
+// Async tool function that gets the run's context and the calling run's limits
+pub type ContextualAsyncToolFn =
+    Arc<dyn Fn(RunContext, Value) -> BoxFuture<'static, ToolOutput> + Send + Sync>;
+
+#[derive(Clone)]
+pub enum ToolFunction {
+    Sync(Arc<dyn Fn(Value) -> ToolOutput + Send + Sync>),
+    Async(Arc<dyn Fn(Value) -> BoxFuture<'static, ToolOutput> + Send + Sync>),
+    Contextual(ContextualToolFn),
+    ContextualAsync(ContextualAsyncToolFn),
+}
+
+impl ToolFunction {
+    // Wraps a closure taking deserialized arguments and returning a serializable value
+    pub fn typed<Args, Output>(function: impl Fn(Args) -> Output + Send + Sync + 'static) -> Self
+    where
+        Args: DeserializeOwned,
+        Output: Serialize,
+    {
+        ToolFunction::Sync(Arc::new(move |args: Value| {
+            match serde_json::from_value::<Args>(args) {
+                Ok(args) => ToolOutput::from_serializable(function(args)),
+                Err(e) => typed_error(e),
+            }
+        }))
+    }
+
+    // Async counterpart of `typed`
+    pub fn typed_async<Args, Output, Fut>(
+        function: impl Fn(Args) -> Fut + Send + Sync + 'static,
+    ) -> Self
+    where
+        Args: DeserializeOwned,
+        Output: Serialize,
+        Fut: Future<Output = Output> + Send + 'static,
+    {
+        ToolFunction::Async(Arc::new(move |args: Value| {
+            match serde_json::from_value::<Args>(args) {
+                Ok(args) => {
+                    let future = function(args);
+                    async move { ToolOutput::from_serializable(future.await) }.boxed()
+                }
+                Err(e) => futures::future::ready(typed_error(e)).boxed(),
+            }
+        }))
+    }
+
+    // Wraps a typed closure that also gets mutable access to the run's typed context
+    pub fn typed_with_context<Ctx, Args, Output>(
+        function: impl Fn(&mut Ctx, Args) -> Output + Send + Sync + 'static,
+    ) -> Self
+    where
+        Ctx: Send + 'static,
+        Args: DeserializeOwned,
+        Output: Serialize,
+    {
+        ToolFunction::Contextual(Arc::new(move |context: &RunContext, args: Value| {
+            let args = match serde_json::from_value::<Args>(args) {
+                Ok(args) => args,
+                Err(e) => return typed_error(e),
+            };
+            context
+                .with(|ctx: &mut Ctx| ToolOutput::from_serializable(function(ctx, args)))
+                .unwrap_or_else(|| {
+                    ToolOutput::Error(format!(
+                        "run context of type {} is not available",
+                        std::any::type_name::<Ctx>()
+                    ))
+                })
+        }))
+    }
+
+    pub async fn call(&self, args: Value, context: &RunContext) -> ToolOutput {
+        match self {
+            ToolFunction::Sync(function) => function(args),
+            ToolFunction::Async(function) => function(args).await,
+            ToolFunction::Contextual(function) => function(context, args),
+            ToolFunction::ContextualAsync(function) => function(context.clone(), args).await,
+        }
+    }
+}
+
+// Reports arguments that do not match the tool's declared type back to the model
+fn typed_error(error: serde_json::Error) -> ToolOutput {
+    ToolOutput::Error(format!("invalid arguments: {}", error))
+}
+
+// Callback invoked with a snapshot of the run at each checkpoint
+pub type CheckpointFn = Arc<dyn Fn(&RunState) + Send + Sync>;
+
+// One model call and the tool results it led to
+#[derive(Debug, Clone)]
+pub struct Turn {
+    pub index: usize,
+    pub agent: String,
+    // The assistant message followed by its tool messages
+    pub messages: Vec<async_openai::types::ChatCompletionRequestMessage>,
+}
+
+// Decides after a turn, from the run so far, whether to stop early
+pub type StopFn = Arc<dyn Fn(&Response, &Turn) -> bool + Send + Sync>;
+
+// Receives assistant content as it streams in
+pub type TokenFn = Arc<dyn Fn(&str) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct RunOptions {
+    pub context_variables: ContextVariables,
+    pub model_override: Option<String>,
+    pub stream: bool,
+    pub debug: bool,
+    // Limit on model calls; tool results do not count as turns
+    pub max_turns: Option<usize>,
+    // Budgets checked before each model call; the limit may be overshot by one turn
+    pub max_cost_usd: Option<f64>,
+    pub max_total_tokens: Option<u32>,
+    pub execute_tools: bool,
+    pub on_checkpoint: Option<CheckpointFn>,
+    pub stop_when: Option<StopFn>,
+    // Streams completions and passes each content delta here; tool calls are
+    // reassembled before they run
+    pub on_token: Option<TokenFn>,
+    pub approval_handler: Option<Arc<dyn crate::approval::ApprovalHandler>>,
+    pub context: RunContext,
+    // Reads the final reply aloud into `Response::speech`
+    pub speech: Option<Arc<crate::audio::Synthesizer>>,
+    // Whose facts the swarm's `AgentMemory` recalls and updates; run_session defaults it
+    // to the session id
+    pub user_id: Option<String>,
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        RunOptions {
+            context_variables: ContextVariables::new(),
+            model_override: None,
+            stream: false,
+            debug: false,
+            max_turns: None,
+            max_cost_usd: None,
+            max_total_tokens: None,
+            execute_tools: true,
+            on_checkpoint: None,
+            stop_when: None,
+            on_token: None,
+            approval_handler: None,
+            context: RunContext::default(),
+            speech: None,
+            user_id: None,
+        }
+    }
+}
+
+impl RunOptions {
+    // Ends the run after any turn for which the predicate holds, e.g. on a keyword,
+    // a specific tool result or a context variable
+    pub fn stop_when(
+        mut self,
+        stop_when: impl Fn(&Response, &Turn) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.stop_when = Some(Arc::new(stop_when));
+        self
+    }
+
+    pub fn on_token(mut self, on_token: impl Fn(&str) + Send + Sync + 'static) -> Self {
+        self.on_token = Some(Arc::new(on_token));
+        self
+    }
+
+    pub fn speech(mut self, synthesizer: crate::audio::Synthesizer) -> Self {
+        self.speech = Some(Arc::new(synthesizer));
+        self
+    }
+
+    pub fn user_id(mut self, user_id: impl Into<String>) -> Self {
+        self.user_id = Some(user_id.into());
+        self
+    }
+}
+
+impl std::fmt::Debug for RunOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RunOptions")
+            .field("context_variables", &self.context_variables)
+            .field("model_override", &self.model_override)
+            .field("stream", &self.stream)
+            .field("debug", &self.debug)
+            .field("max_turns", &self.max_turns)
+            .field("max_cost_usd", &self.max_cost_usd)
+            .field("max_total_tokens", &self.max_total_tokens)
+            .field("execute_tools", &self.execute_tools)
+            .field("on_checkpoint", &self.on_checkpoint.is_some())
+            .field("stop_when", &self.stop_when.is_some())
+            .field("on_token", &self.on_token.is_some())
+            .field("approval_handler", &self.approval_handler.is_some())
+            .field("context", &self.context)
+            .field("speech", &self.speech)
+            .field("user_id", &self.user_id)
+            .finish()
+    }
+}
+
+// Serializable snapshot of a run, taken after each turn and before tool execution
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunState {
+    pub history: Vec<async_openai::types::ChatCompletionRequestMessage>,
+    pub init_len: usize,
+    pub active_agent: Agent,
+    pub context_variables: ContextVariables,
+    pub pending_tool_calls: Vec<async_openai::types::ChatCompletionMessageToolCall>,
+    pub turn: usize,
+    pub usage: Usage,
+    pub cost_usd: f64,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+    #[serde(default)]
+    pub finish_reason: Option<async_openai::types::FinishReason>,
+    #[serde(default)]
+    pub redactions: Vec<crate::pii::Redaction>,
+    #[serde(default)]
+    pub status: RunStatus,
+}
+
+impl RunState {
+    pub fn new(
+        agent: Agent,
+        messages: Vec<async_openai::types::ChatCompletionRequestMessage>,
+        context_variables: ContextVariables,
+    ) -> Self {
+        RunState {
+            init_len: messages.len(),
+            history: messages,
+            active_agent: agent,
+            context_variables,
+            pending_tool_calls: Vec::new(),
+            turn: 0,
+            usage: Usage::default(),
+            cost_usd: 0.0,
+            system_fingerprint: None,
+            finish_reason: None,
+            redactions: Vec::new(),
+            status: RunStatus::Completed,
+        }
+    }
+
+    pub fn into_response(self) -> Response {
+        Response {
+            messages: self.history[self.init_len..].to_vec(),
+            agent: Some(self.active_agent),
+            context_variables: self.context_variables,
+            usage: self.usage,
+            cost_usd: self.cost_usd,
+            system_fingerprint: self.system_fingerprint,
+            finish_reason: self.finish_reason,
+            status: self.status,
+            redactions: self.redactions,
+            ..Response::empty()
+        }
+    }
+}
+
+// A tool's metadata bundled with the function that implements it
+#[derive(Clone)]
+pub struct ToolDefinition {
+    tool: Tool,
+    function: ToolFunction,
+}
+
+impl ToolDefinition {
+    pub fn new(tool: Tool, function: ToolFunction) -> Self {
+        ToolDefinition { tool, function }
+    }
+
+    // Opts the tool into strict function calling
+    pub fn with_strict(mut self) -> Self {
+        self.tool = self.tool.with_strict();
+        self
+    }
+
+    // Prefixes the tool's name with a namespace, see `Tool::with_namespace`
+    pub fn with_namespace(mut self, namespace: &str) -> Self {
+        self.tool = self.tool.with_namespace(namespace);
+        self
+    }
+
+    pub fn tool(&self) -> Tool {
+        self.tool.clone()
+    }
+
+    pub fn function(&self) -> ToolFunction {
+        self.function.clone()
+    }
+}
+
+impl std::fmt::Debug for ToolDefinition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ToolDefinition")
+            .field("tool", &self.tool)
+            .finish()
+    }
 }
 
+// Named tools with their functions. Tools can be registered and removed through a shared
+// reference, e.g. on a swarm behind a running server; clones are independent copies
 pub struct ToolRegistry {
-    tools: HashMap<String, Tool>,
-    functions: HashMap<String, Arc<dyn Fn(Value) -> Value + Send + Sync>>,
+    definitions: RwLock<HashMap<String, ToolDefinition>>,
+}
+
+impl Clone for ToolRegistry {
+    fn clone(&self) -> Self {
+        ToolRegistry {
+            definitions: RwLock::new(self.definitions.read().unwrap().clone()),
+        }
+    }
+}
+
+impl std::fmt::Debug for ToolRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let definitions = self.definitions.read().unwrap();
+        let mut names: Vec<&String> = definitions.keys().collect();
+        names.sort_unstable();
+        f.debug_struct("ToolRegistry")
+            .field("tools", &names)
+            .finish()
+    }
 }
 
 impl Default for ToolRegistry {
@@ -101,28 +1109,327 @@ impl Default for ToolRegistry {
 impl ToolRegistry {
     pub fn new() -> Self {
         ToolRegistry {
-            tools: HashMap::new(),
-            functions: HashMap::new(),
+            definitions: RwLock::new(HashMap::new()),
         }
     }
 
     pub fn register_tool(
-        &mut self,
+        &self,
         name: &str,
         description: &str,
         parameters: Value,
         function: Box<dyn Fn(Value) -> Value + Send + Sync>,
     ) {
         let tool = Tool::new(name, description, parameters);
-        self.tools.insert(name.to_string(), tool);
-        self.functions.insert(name.to_string(), Arc::from(function));
+        let function = move |args: Value| ToolOutput::from(function(args));
+        self.register(ToolDefinition::new(
+            tool,
+            ToolFunction::Sync(Arc::new(function)),
+        ));
+    }
+
+    pub fn register_async_tool(
+        &self,
+        name: &str,
+        description: &str,
+        parameters: Value,
+        function: Box<dyn Fn(Value) -> BoxFuture<'static, Value> + Send + Sync>,
+    ) {
+        let tool = Tool::new(name, description, parameters);
+        let function = move |args: Value| function(args).map(ToolOutput::from).boxed();
+        self.register(ToolDefinition::new(
+            tool,
+            ToolFunction::Async(Arc::new(function)),
+        ));
+    }
+
+    pub fn register_tool_typed<Args, Output>(
+        &self,
+        name: &str,
+        description: &str,
+        function: impl Fn(Args) -> Output + Send + Sync + 'static,
+    ) where
+        Args: JsonSchema + DeserializeOwned,
+        Output: Serialize,
+    {
+        let tool = Tool::typed::<Args>(name, description);
+        self.register(ToolDefinition::new(tool, ToolFunction::typed(function)));
+    }
+
+    pub fn register_async_tool_typed<Args, Output, Fut>(
+        &self,
+        name: &str,
+        description: &str,
+        function: impl Fn(Args) -> Fut + Send + Sync + 'static,
+    ) where
+        Args: JsonSchema + DeserializeOwned,
+        Output: Serialize,
+        Fut: Future<Output = Output> + Send + 'static,
+    {
+        let tool = Tool::typed::<Args>(name, description);
+        self.register(ToolDefinition::new(
+            tool,
+            ToolFunction::typed_async(function),
+        ));
+    }
+
+    pub fn register_context_tool<Ctx, Args, Output>(
+        &self,
+        name: &str,
+        description: &str,
+        function: impl Fn(&mut Ctx, Args) -> Output + Send + Sync + 'static,
+    ) where
+        Ctx: Send + 'static,
+        Args: JsonSchema + DeserializeOwned,
+        Output: Serialize,
+    {
+        let tool = Tool::typed::<Args>(name, description);
+        self.register(ToolDefinition::new(
+            tool,
+            ToolFunction::typed_with_context(function),
+        ));
+    }
+
+    // Adds a tool, replacing any tool with the same name
+    pub fn register(&self, definition: ToolDefinition) {
+        let name = definition.tool.name.clone();
+        self.definitions.write().unwrap().insert(name, definition);
+    }
+
+    // Adds a tool unless one with the same name is registered
+    pub fn try_register(&self, definition: ToolDefinition) -> crate::error::Result<()> {
+        let mut definitions = self.definitions.write().unwrap();
+        if definitions.contains_key(&definition.tool.name) {
+            return Err(crate::error::SwarmError::DuplicateTool(
+                definition.tool.name.clone(),
+            ));
+        }
+        definitions.insert(definition.tool.name.clone(), definition);
+        Ok(())
+    }
+
+    // Adds a pack of tools under a namespace, e.g. the tools of one MCP server or plugin.
+    // Fails without registering any of them if a qualified name is taken
+    pub fn register_namespace(
+        &self,
+        namespace: &str,
+        definitions: impl IntoIterator<Item = ToolDefinition>,
+    ) -> crate::error::Result<()> {
+        let pack: Vec<ToolDefinition> = definitions
+            .into_iter()
+            .map(|definition| definition.with_namespace(namespace))
+            .collect();
+        let mut registered = self.definitions.write().unwrap();
+        for (index, definition) in pack.iter().enumerate() {
+            let name = &definition.tool.name;
+            if registered.contains_key(name)
+                || pack[..index].iter().any(|other| other.tool.name == *name)
+            {
+                return Err(crate::error::SwarmError::DuplicateTool(name.clone()));
+            }
+        }
+        registered.extend(
+            pack.into_iter()
+                .map(|definition| (definition.tool.name.clone(), definition)),
+        );
+        Ok(())
+    }
+
+    // Removes a tool, returning it if it was registered. Runs already calling it finish
+    pub fn deregister_tool(&self, name: &str) -> Option<ToolDefinition> {
+        self.definitions.write().unwrap().remove(name)
+    }
+
+    // Removes every tool in the namespace, returning how many there were
+    pub fn deregister_namespace(&self, namespace: &str) -> usize {
+        let mut definitions = self.definitions.write().unwrap();
+        let before = definitions.len();
+        definitions.retain(|_, definition| definition.tool.namespace() != Some(namespace));
+        before - definitions.len()
+    }
+
+    // Registered name a call refers to: the name itself, the namespaced tool it spells
+    // with a dot (`github.create_issue`), or the only namespaced tool with that bare name
+    pub fn resolve_name(&self, name: &str) -> Option<String> {
+        let definitions = self.definitions.read().unwrap();
+        if definitions.contains_key(name) {
+            return Some(name.to_string());
+        }
+        if let Some((namespace, bare)) = name.split_once('.') {
+            let qualified = format!("{}{}{}", namespace, NAMESPACE_SEPARATOR, bare);
+            if definitions.contains_key(&qualified) {
+                return Some(qualified);
+            }
+        }
+        let mut matches = definitions
+            .values()
+            .filter(|definition| {
+                definition.tool.namespace().is_some() && definition.tool.bare_name() == name
+            })
+            .map(|definition| definition.tool.name.clone());
+        match (matches.next(), matches.next()) {
+            (Some(qualified), None) => Some(qualified),
+            _ => None,
+        }
+    }
+
+    pub fn get_function(&self, name: &str) -> Option<ToolFunction> {
+        let definitions = self.definitions.read().unwrap();
+        definitions
+            .get(name)
+            .map(|definition| definition.function.clone())
+    }
+
+    pub fn get_tool(&self, name: &str) -> Option<Tool> {
+        let definitions = self.definitions.read().unwrap();
+        definitions
+            .get(name)
+            .map(|definition| definition.tool.clone())
+    }
+
+    // Schema and implementation of a registered tool, ready to attach to an agent
+    pub fn get_definition(&self, name: &str) -> Option<ToolDefinition> {
+        self.definitions.read().unwrap().get(name).cloned()
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.definitions.read().unwrap().contains_key(name)
+    }
+
+    // Registered tool schemas, ordered by name
+    pub fn list_tools(&self) -> Vec<Tool> {
+        self.definitions()
+            .into_iter()
+            .map(|definition| definition.tool)
+            .collect()
+    }
+
+    // Every registered tool with its implementation, ordered by name
+    pub fn definitions(&self) -> Vec<ToolDefinition> {
+        let mut definitions: Vec<ToolDefinition> =
+            self.definitions.read().unwrap().values().cloned().collect();
+        definitions.sort_by(|a, b| a.tool.name.cmp(&b.tool.name));
+        definitions
+    }
+}
+
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum SwarmEvent {
+    TurnStarted {
+        turn: usize,
+        agent: String,
+    },
+    TokenDelta {
+        agent: String,
+        content: String,
+    },
+    ToolCallRequested {
+        agent: String,
+        tool_call: async_openai::types::ChatCompletionMessageToolCall,
+    },
+    ToolResult {
+        tool_call_id: String,
+        name: String,
+        content: String,
+    },
+    AgentHandoff {
+        from: String,
+        to: String,
+    },
+    UsageUpdated {
+        usage: Usage,
+        cost_usd: f64,
+    },
+    PlanCreated {
+        steps: Vec<crate::patterns::PlanStep>,
+    },
+    StepStarted {
+        step: usize,
+        description: String,
+    },
+    StepFinished {
+        step: usize,
+        status: crate::patterns::StepStatus,
+        output: String,
+    },
+    RunCompleted(Response),
+    RunFailed(crate::error::SwarmError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(name: &str) -> ToolDefinition {
+        ToolDefinition::new(
+            Tool::new(name, "", serde_json::json!({ "type": "object" })),
+            ToolFunction::Sync(Arc::new(|_| ToolOutput::Text(String::new()))),
+        )
+    }
+
+    fn registry() -> ToolRegistry {
+        let registry = ToolRegistry::new();
+        registry.register(definition("search"));
+        registry
+            .register_namespace("github", [definition("create_issue"), definition("search")])
+            .unwrap();
+        registry
+            .register_namespace("jira", [definition("search")])
+            .unwrap();
+        registry
+    }
+
+    #[test]
+    fn resolves_exact_names_first() {
+        let registry = registry();
+        assert_eq!(registry.resolve_name("search").as_deref(), Some("search"));
+        assert_eq!(
+            registry.resolve_name("github__create_issue").as_deref(),
+            Some("github__create_issue")
+        );
+    }
+
+    #[test]
+    fn resolves_dotted_names() {
+        let registry = registry();
+        assert_eq!(
+            registry.resolve_name("github.search").as_deref(),
+            Some("github__search")
+        );
+        assert_eq!(registry.resolve_name("gitlab.search"), None);
+    }
+
+    #[test]
+    fn resolves_unique_bare_names() {
+        let registry = registry();
+        assert_eq!(
+            registry.resolve_name("create_issue").as_deref(),
+            Some("github__create_issue")
+        );
     }
 
-    pub fn get_function(&self, name: &str) -> Option<Arc<dyn Fn(Value) -> Value + Send + Sync>> {
-        self.functions.get(name).cloned()
+    #[test]
+    fn refuses_ambiguous_or_unknown_bare_names() {
+        let registry = ToolRegistry::new();
+        registry
+            .register_namespace("github", [definition("search")])
+            .unwrap();
+        registry
+            .register_namespace("jira", [definition("search")])
+            .unwrap();
+        assert_eq!(registry.resolve_name("search"), None);
+        assert_eq!(registry.resolve_name("delete"), None);
     }
 
-    pub fn get_tool(&self, name: &str) -> Option<&Tool> {
-        self.tools.get(name)
+    #[test]
+    fn names_containing_the_separator_are_not_namespaced() {
+        let registry = ToolRegistry::new();
+        registry.register(definition("my__tool"));
+        assert_eq!(registry.resolve_name("tool"), None);
+        assert_eq!(
+            registry.resolve_name("my__tool").as_deref(),
+            Some("my__tool")
+        );
     }
 }
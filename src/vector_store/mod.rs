@@ -0,0 +1,115 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+use crate::embeddings::cosine_similarity;
+use crate::error::Result;
+
+#[cfg(feature = "qdrant")]
+mod qdrant;
+
+#[cfg(feature = "qdrant")]
+pub use self::qdrant::QdrantStore;
+
+// A vector with its id and arbitrary JSON payload, e.g. the text it embeds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VectorRecord {
+    pub id: String,
+    pub vector: Vec<f32>,
+    #[serde(default)]
+    pub payload: Map<String, Value>,
+}
+
+impl VectorRecord {
+    pub fn new(id: impl Into<String>, vector: Vec<f32>) -> Self {
+        VectorRecord {
+            id: id.into(),
+            vector,
+            payload: Map::new(),
+        }
+    }
+
+    pub fn with_payload(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.payload.insert(key.to_string(), value.into());
+        self
+    }
+}
+
+// A query match; higher scores are closer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScoredRecord {
+    pub id: String,
+    pub score: f32,
+    #[serde(default)]
+    pub payload: Map<String, Value>,
+}
+
+// Storage backend for embeddings, searched by cosine similarity
+#[async_trait]
+pub trait VectorStore: Send + Sync {
+    // Inserts records, replacing those with the same id
+    async fn upsert(&self, records: Vec<VectorRecord>) -> Result<()>;
+
+    // Returns up to `limit` records closest to the vector, best first
+    async fn query(&self, vector: &[f32], limit: usize) -> Result<Vec<ScoredRecord>>;
+
+    // Removes records by id; unknown ids are ignored
+    async fn delete(&self, ids: &[String]) -> Result<()>;
+}
+
+// Process-local vector store with exact search, lost on restart
+#[derive(Debug, Default)]
+pub struct InMemoryVectorStore {
+    records: RwLock<HashMap<String, VectorRecord>>,
+}
+
+impl InMemoryVectorStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn len(&self) -> usize {
+        self.records.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.records.read().await.is_empty()
+    }
+}
+
+#[async_trait]
+impl VectorStore for InMemoryVectorStore {
+    async fn upsert(&self, records: Vec<VectorRecord>) -> Result<()> {
+        let mut stored = self.records.write().await;
+        for record in records {
+            stored.insert(record.id.clone(), record);
+        }
+        Ok(())
+    }
+
+    async fn query(&self, vector: &[f32], limit: usize) -> Result<Vec<ScoredRecord>> {
+        let records = self.records.read().await;
+        let mut scored: Vec<ScoredRecord> = records
+            .values()
+            .map(|record| ScoredRecord {
+                id: record.id.clone(),
+                score: cosine_similarity(vector, &record.vector),
+                payload: record.payload.clone(),
+            })
+            .collect();
+        // Ties break by id so results are stable
+        scored.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.id.cmp(&b.id)));
+        scored.truncate(limit);
+        Ok(scored)
+    }
+
+    async fn delete(&self, ids: &[String]) -> Result<()> {
+        let mut stored = self.records.write().await;
+        for id in ids {
+            stored.remove(id);
+        }
+        Ok(())
+    }
+}
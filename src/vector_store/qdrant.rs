@@ -0,0 +1,166 @@
+use async_trait::async_trait;
+use reqwest::{Method, RequestBuilder};
+use serde_json::{json, Map, Value};
+
+use super::{ScoredRecord, VectorRecord, VectorStore};
+use crate::error::{Result, SwarmError};
+
+// Payload key holding the record id, since Qdrant point ids must be integers or UUIDs
+const ID_KEY: &str = "swarm_id";
+
+// Vector store backed by a Qdrant collection over its REST API
+#[derive(Clone)]
+pub struct QdrantStore {
+    http: reqwest::Client,
+    url: String,
+    collection: String,
+    api_key: Option<String>,
+}
+
+impl QdrantStore {
+    // `url` is the REST endpoint, e.g. "http://localhost:6333"
+    pub fn new(url: &str, collection: &str) -> Self {
+        QdrantStore {
+            http: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            collection: collection.to_string(),
+            api_key: None,
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    // Creates the collection for vectors of this size with cosine distance, unless it exists
+    pub async fn ensure_collection(&self, dimensions: usize) -> Result<()> {
+        let exists = self
+            .send(self.request(Method::GET, "/exists"))
+            .await?
+            .pointer("/result/exists")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if !exists {
+            let body = json!({ "vectors": { "size": dimensions, "distance": "Cosine" } });
+            self.send(self.request(Method::PUT, "").json(&body)).await?;
+        }
+        Ok(())
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = format!("{}/collections/{}{}", self.url, self.collection, path);
+        let request = self.http.request(method, url);
+        match &self.api_key {
+            Some(api_key) => request.header("api-key", api_key),
+            None => request,
+        }
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Value> {
+        let response = request.send().await.map_err(store_error)?;
+        let status = response.status();
+        let body: Value = response.json().await.map_err(store_error)?;
+        if !status.is_success() {
+            let message = body
+                .pointer("/status/error")
+                .and_then(Value::as_str)
+                .unwrap_or(status.as_str());
+            return Err(SwarmError::VectorStore(format!("qdrant: {}", message)));
+        }
+        Ok(body)
+    }
+}
+
+#[async_trait]
+impl VectorStore for QdrantStore {
+    async fn upsert(&self, records: Vec<VectorRecord>) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let points: Vec<Value> = records
+            .into_iter()
+            .map(|record| {
+                let mut payload = record.payload;
+                payload.insert(ID_KEY.to_string(), Value::String(record.id.clone()));
+                json!({ "id": point_id(&record.id), "vector": record.vector, "payload": payload })
+            })
+            .collect();
+        self.send(
+            self.request(Method::PUT, "/points?wait=true")
+                .json(&json!({ "points": points })),
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn query(&self, vector: &[f32], limit: usize) -> Result<Vec<ScoredRecord>> {
+        let body = json!({ "query": vector, "limit": limit, "with_payload": true });
+        let response = self
+            .send(self.request(Method::POST, "/points/query").json(&body))
+            .await?;
+        let points = response
+            .pointer("/result/points")
+            .and_then(Value::as_array)
+            .cloned()
+            .unwrap_or_default();
+        Ok(points
+            .into_iter()
+            .map(|point| {
+                let mut payload = match point.get("payload") {
+                    Some(Value::Object(payload)) => payload.clone(),
+                    _ => Map::new(),
+                };
+                let id = match payload.remove(ID_KEY) {
+                    Some(Value::String(id)) => id,
+                    _ => match &point["id"] {
+                        Value::String(id) => id.clone(),
+                        id => id.to_string(),
+                    },
+                };
+                ScoredRecord {
+                    id,
+                    score: point["score"].as_f64().unwrap_or(0.0) as f32,
+                    payload,
+                }
+            })
+            .collect())
+    }
+
+    async fn delete(&self, ids: &[String]) -> Result<()> {
+        if ids.is_empty() {
+            return Ok(());
+        }
+        let points: Vec<Value> = ids.iter().map(|id| point_id(id)).collect();
+        self.send(
+            self.request(Method::POST, "/points/delete?wait=true")
+                .json(&json!({ "points": points })),
+        )
+        .await?;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for QdrantStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QdrantStore")
+            .field("url", &self.url)
+            .field("collection", &self.collection)
+            .finish()
+    }
+}
+
+// Integer ids pass through; any other id maps to a stable 64-bit FNV-1a hash
+fn point_id(id: &str) -> Value {
+    if let Ok(number) = id.parse::<u64>() {
+        return json!(number);
+    }
+    let hash = id.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    json!(hash)
+}
+
+fn store_error(error: reqwest::Error) -> SwarmError {
+    SwarmError::VectorStore(format!("qdrant: {}", error))
+}
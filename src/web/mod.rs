@@ -0,0 +1,3 @@
+pub mod sse;
+
+pub use sse::{encode_events, SseEncoder};
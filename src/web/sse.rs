@@ -0,0 +1,138 @@
+use futures::{Stream, StreamExt};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::types::SwarmEvent;
+
+// Names the SSE event a run event is sent as
+pub type EventNameFn = Arc<dyn Fn(&SwarmEvent) -> String + Send + Sync>;
+
+// Turns run events into Server-Sent Events frames, e.g. to proxy `run_and_stream`
+// to a browser through any HTTP framework
+#[derive(Clone)]
+pub struct SseEncoder {
+    event_name: EventNameFn,
+    heartbeat: Option<Duration>,
+}
+
+impl Default for SseEncoder {
+    fn default() -> Self {
+        SseEncoder {
+            event_name: Arc::new(|event| event_name(event).to_string()),
+            heartbeat: Some(Duration::from_secs(15)),
+        }
+    }
+}
+
+impl SseEncoder {
+    // Names events in snake case, e.g. `token_delta`, with a heartbeat every 15 seconds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_event_names(
+        mut self,
+        event_name: impl Fn(&SwarmEvent) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.event_name = Arc::new(event_name);
+        self
+    }
+
+    // Prefixes the default names, e.g. `agent.token_delta`
+    pub fn with_prefix(self, prefix: &str) -> Self {
+        let prefix = prefix.to_string();
+        self.with_event_names(move |event| format!("{}{}", prefix, event_name(event)))
+    }
+
+    // Comment frames sent while the run is quiet keep proxies from closing the
+    // connection; `None` disables them
+    pub fn with_heartbeat(mut self, heartbeat: Option<Duration>) -> Self {
+        self.heartbeat = heartbeat;
+        self
+    }
+
+    // Encodes a single event as an `event:`/`data:` frame with a JSON payload
+    pub fn encode(&self, event: &SwarmEvent) -> String {
+        format!(
+            "event: {}\ndata: {}\n\n",
+            (self.event_name)(event),
+            event_data(event)
+        )
+    }
+
+    // Encodes a stream of events, interleaving heartbeats when enabled
+    pub fn encode_stream<'a>(
+        self,
+        events: impl Stream<Item = SwarmEvent> + Send + 'a,
+    ) -> impl Stream<Item = String> + Send + 'a {
+        let heartbeat = self.heartbeat;
+        let frames = events.map(move |event| self.encode(&event)).boxed();
+        futures::stream::unfold(Some(frames), move |frames| async move {
+            let mut frames = frames?;
+            let next = match heartbeat {
+                Some(period) => match tokio::time::timeout(period, frames.next()).await {
+                    Ok(frame) => frame,
+                    Err(_) => return Some((": heartbeat\n\n".to_string(), Some(frames))),
+                },
+                None => frames.next().await,
+            };
+            next.map(|frame| (frame, Some(frames)))
+        })
+    }
+}
+
+// Encodes a run's events with the default settings
+pub fn encode_events<'a>(
+    events: impl Stream<Item = SwarmEvent> + Send + 'a,
+) -> impl Stream<Item = String> + Send + 'a {
+    SseEncoder::new().encode_stream(events)
+}
+
+// Default SSE event name of a run event
+pub fn event_name(event: &SwarmEvent) -> &'static str {
+    match event {
+        SwarmEvent::TurnStarted { .. } => "turn_started",
+        SwarmEvent::TokenDelta { .. } => "token_delta",
+        SwarmEvent::ToolCallRequested { .. } => "tool_call_requested",
+        SwarmEvent::ToolResult { .. } => "tool_result",
+        SwarmEvent::AgentHandoff { .. } => "agent_handoff",
+        SwarmEvent::UsageUpdated { .. } => "usage_updated",
+        SwarmEvent::PlanCreated { .. } => "plan_created",
+        SwarmEvent::StepStarted { .. } => "step_started",
+        SwarmEvent::StepFinished { .. } => "step_finished",
+        SwarmEvent::RunCompleted(_) => "run_completed",
+        SwarmEvent::RunFailed(_) => "run_failed",
+    }
+}
+
+// JSON payload of a run event
+pub fn event_data(event: &SwarmEvent) -> Value {
+    match event {
+        SwarmEvent::TurnStarted { turn, agent } => json!({ "turn": turn, "agent": agent }),
+        SwarmEvent::TokenDelta { agent, content } => json!({ "agent": agent, "content": content }),
+        SwarmEvent::ToolCallRequested { agent, tool_call } => {
+            json!({ "agent": agent, "tool_call": tool_call })
+        }
+        SwarmEvent::ToolResult {
+            tool_call_id,
+            name,
+            content,
+        } => json!({ "tool_call_id": tool_call_id, "name": name, "content": content }),
+        SwarmEvent::AgentHandoff { from, to } => json!({ "from": from, "to": to }),
+        SwarmEvent::UsageUpdated { usage, cost_usd } => {
+            json!({ "usage": usage, "cost_usd": cost_usd })
+        }
+        SwarmEvent::PlanCreated { steps } => json!({ "steps": steps }),
+        SwarmEvent::StepStarted { step, description } => {
+            json!({ "step": step, "description": description })
+        }
+        SwarmEvent::StepFinished {
+            step,
+            status,
+            output,
+        } => json!({ "step": step, "status": status, "output": output }),
+        SwarmEvent::RunCompleted(response) => json!(response),
+        SwarmEvent::RunFailed(error) => json!({ "error": error.to_string() }),
+    }
+}
@@ -0,0 +1,257 @@
+use futures::future::BoxFuture;
+use futures::stream::FuturesUnordered;
+use futures::{FutureExt, StreamExt};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::error::{Result, SwarmError};
+use crate::messages::user_message;
+use crate::orchestration::final_text;
+use crate::provider::{ChatProvider, OpenAIProvider};
+use crate::swarm::Swarm;
+use crate::types::{Agent, RunOptions, Usage};
+
+// What a node receives: the workflow's input and the outputs of its upstream nodes
+#[derive(Debug, Clone)]
+pub struct NodeInput {
+    pub input: Value,
+    pub upstream: HashMap<String, Value>,
+}
+
+// Async function node
+pub type NodeFn = Arc<dyn Fn(NodeInput) -> BoxFuture<'static, Result<Value>> + Send + Sync>;
+
+#[derive(Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum Node {
+    // Runs the agent on its inputs; the output is the agent's final answer as a string
+    Agent(Agent),
+    Function(NodeFn),
+}
+
+impl std::fmt::Debug for Node {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Node::Agent(agent) => f.debug_tuple("Agent").field(&agent.name).finish(),
+            Node::Function(_) => f.write_str("Function"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkflowOutput {
+    pub outputs: HashMap<String, Value>,
+    pub usage: Usage,
+    pub cost_usd: f64,
+}
+
+impl WorkflowOutput {
+    pub fn output(&self, node: &str) -> Option<&Value> {
+        self.outputs.get(node)
+    }
+}
+
+// A DAG of agent and function nodes; edges carry each node's output to its dependents
+pub struct Workflow<'a, P: ChatProvider = OpenAIProvider> {
+    swarm: &'a Swarm<P>,
+    nodes: Vec<(String, Node)>,
+    edges: Vec<(String, String)>,
+    options: RunOptions,
+}
+
+impl<'a, P: ChatProvider> Workflow<'a, P> {
+    pub fn new(swarm: &'a Swarm<P>) -> Self {
+        Workflow {
+            swarm,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+            options: RunOptions::default(),
+        }
+    }
+
+    pub fn add_agent(mut self, name: impl Into<String>, agent: Agent) -> Self {
+        self.nodes.push((name.into(), Node::Agent(agent)));
+        self
+    }
+
+    pub fn add_function<F, Fut>(mut self, name: impl Into<String>, function: F) -> Self
+    where
+        F: Fn(NodeInput) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<Value>> + Send + 'static,
+    {
+        let function: NodeFn = Arc::new(move |input| function(input).boxed());
+        self.nodes.push((name.into(), Node::Function(function)));
+        self
+    }
+
+    // Feeds the output of `from` into `to`
+    pub fn add_edge(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.edges.push((from.into(), to.into()));
+        self
+    }
+
+    // Options applied to every agent node
+    pub fn with_options(mut self, options: RunOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    // Runs every node once its dependencies are done, independent nodes concurrently
+    pub async fn run(&self, input: impl Into<Value>) -> Result<WorkflowOutput> {
+        let input = input.into();
+        let dependencies = self.dependencies()?;
+
+        let mut remaining: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+        let mut outputs: Vec<Option<Value>> = vec![None; self.nodes.len()];
+        let mut started = vec![false; self.nodes.len()];
+        let mut running = FuturesUnordered::new();
+        let mut usage = Usage::default();
+        let mut cost_usd = 0.0;
+
+        loop {
+            // 1. Start every node whose dependencies have finished
+            for index in 0..self.nodes.len() {
+                if started[index] || remaining[index] > 0 {
+                    continue;
+                }
+                started[index] = true;
+                let upstream = dependencies[index]
+                    .iter()
+                    .map(|&dependency| {
+                        let output = outputs[dependency].clone().unwrap_or_default();
+                        (self.nodes[dependency].0.clone(), output)
+                    })
+                    .collect();
+                let node_input = NodeInput {
+                    input: input.clone(),
+                    upstream,
+                };
+                running.push(self.run_node(index, node_input));
+            }
+
+            // 2. Collect the next finished node and unblock its dependents
+            let Some((index, result)) = running.next().await else {
+                break;
+            };
+            let (output, node_usage, node_cost) = result?;
+            usage.add(&node_usage);
+            cost_usd += node_cost;
+            if self.options.debug {
+                self.swarm
+                    .debug_event("workflow", format!("node {} finished", self.nodes[index].0));
+            }
+            outputs[index] = Some(output);
+            for (dependent, dependencies) in dependencies.iter().enumerate() {
+                remaining[dependent] -= dependencies.iter().filter(|&&d| d == index).count();
+            }
+        }
+
+        Ok(WorkflowOutput {
+            outputs: self
+                .nodes
+                .iter()
+                .zip(outputs)
+                .filter_map(|((name, _), output)| Some((name.clone(), output?)))
+                .collect(),
+            usage,
+            cost_usd,
+        })
+    }
+
+    async fn run_node(
+        &self,
+        index: usize,
+        input: NodeInput,
+    ) -> (usize, Result<(Value, Usage, f64)>) {
+        let result = match &self.nodes[index].1 {
+            Node::Function(function) => function(input)
+                .await
+                .map(|output| (output, Usage::default(), 0.0)),
+            Node::Agent(agent) => self
+                .swarm
+                .run_with_options(
+                    agent.clone(),
+                    vec![user_message(&agent_prompt(&input))],
+                    self.options.clone(),
+                )
+                .await
+                .map(|response| {
+                    (
+                        Value::String(final_text(&response)),
+                        response.usage,
+                        response.cost_usd,
+                    )
+                }),
+        };
+        (index, result)
+    }
+
+    // Upstream node indices of every node; rejects unknown names, duplicates and cycles
+    fn dependencies(&self) -> Result<Vec<Vec<usize>>> {
+        let mut indices = HashMap::new();
+        for (index, (name, _)) in self.nodes.iter().enumerate() {
+            if indices.insert(name.as_str(), index).is_some() {
+                return Err(SwarmError::Workflow(format!("duplicate node {}", name)));
+            }
+        }
+        let index_of = |name: &str| {
+            indices
+                .get(name)
+                .copied()
+                .ok_or_else(|| SwarmError::Workflow(format!("unknown node {}", name)))
+        };
+        let mut dependencies = vec![Vec::new(); self.nodes.len()];
+        for (from, to) in &self.edges {
+            dependencies[index_of(to)?].push(index_of(from)?);
+        }
+
+        // Kahn's algorithm: every node must eventually have no unfinished dependencies
+        let mut remaining: Vec<usize> = dependencies.iter().map(Vec::len).collect();
+        let mut ready: Vec<usize> = (0..remaining.len())
+            .filter(|&i| remaining[i] == 0)
+            .collect();
+        let mut visited = 0;
+        while let Some(index) = ready.pop() {
+            visited += 1;
+            for (dependent, upstream) in dependencies.iter().enumerate() {
+                let edges = upstream.iter().filter(|&&d| d == index).count();
+                if edges > 0 {
+                    remaining[dependent] -= edges;
+                    if remaining[dependent] == 0 {
+                        ready.push(dependent);
+                    }
+                }
+            }
+        }
+        if visited < self.nodes.len() {
+            return Err(SwarmError::Workflow("edges form a cycle".to_string()));
+        }
+        Ok(dependencies)
+    }
+}
+
+// Source nodes see the workflow input; others see their upstream outputs
+fn agent_prompt(input: &NodeInput) -> String {
+    if input.upstream.is_empty() {
+        return value_text(&input.input);
+    }
+    if input.upstream.len() == 1 {
+        return input.upstream.values().map(value_text).collect();
+    }
+    let mut upstream: Vec<_> = input.upstream.iter().collect();
+    upstream.sort_by_key(|(name, _)| name.as_str());
+    upstream
+        .into_iter()
+        .map(|(name, output)| format!("## {}\n{}", name, value_text(output)))
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}